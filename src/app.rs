@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -11,26 +13,45 @@ mod redraw_debug {
 }
 
 use alacritty_terminal::selection::SelectionType;
+use serde_json::{Value, json};
 use winit::application::ApplicationHandler;
-use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::event::{ElementState, Ime, MouseButton, MouseScrollDelta, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoopProxy};
-use winit::keyboard::{Key, KeyCode, PhysicalKey};
-use winit::window::{CursorIcon, Window, WindowAttributes, WindowId};
-
+use winit::keyboard::{Key, NamedKey, PhysicalKey};
+use winit::window::{CursorIcon, Theme as WindowTheme, Window, WindowAttributes, WindowId};
+
+use crate::cli::{Options, SshTarget};
+use crate::colors::{ColorScheme, TabColor};
+use crate::command_palette::{CommandPalette, PaletteAction, PaletteItem, PaletteKey};
+use crate::config::Config;
+use crate::config_watch::{self, ConfigFile};
 use crate::draw::DrawContext;
-use crate::dropdown::{DropdownElement, DropdownMenu, MenuAction, MenuEntry, MenuPosition};
-use crate::font;
+use crate::dropdown::{
+    DropdownElement, DropdownKey, DropdownMenu, MenuAction, MenuEntry, MenuPosition,
+};
+use crate::export::ExportFormat;
+use crate::font::{self, CellMetrics};
 use crate::gpu::GpuContext;
 use crate::hotkeys::{HotkeyAction, HotkeyConfig, HotkeyLookup};
 use crate::icons;
 use crate::icons::IconManager;
+use crate::ipc::{self, Request};
+use crate::kitty_keys::Mods;
 use crate::layout::{Rect, TextSpec};
+use crate::replay::Recording;
 use crate::saved_sessions::{now_unix, SavedAuthType, SavedSession, SavedSessions};
+use crate::settings::Appearance;
 use crate::ssh_config::SshHostEntry;
 use crate::ssh_dialog::{AuthMethod, SshDialog, SshPrefill, SshResult};
-use crate::tab_bar::{TabBar, TabBarElement};
-use crate::terminal_panel::{EventProxy, PanelId, TermSize, TerminalEvent, TerminalPanel};
-use crate::theme::Theme;
+use crate::tab_bar::{TabBar, TabBarElement, TabLabel};
+use crate::terminal_panel::{
+    EventProxy, Launch, PanelId, PanelStyle, TabSource, TermSize, TerminalEvent, TerminalPanel,
+};
+use crate::theme::{PanelTheme, Theme};
+use crate::themes;
+use crate::toast::Toast;
+use crate::upload;
+use crate::widgets::TextField;
 
 /// Frame interval for animations (cursor move, smooth scroll) — 60fps.
 const FRAME_INTERVAL: Duration = Duration::from_millis(16);
@@ -38,12 +59,42 @@ const FRAME_INTERVAL: Duration = Duration::from_millis(16);
 const UNFOCUSED_FRAME_INTERVAL: Duration = Duration::from_millis(500);
 /// Cursor stays solid for this long after typing before blink resumes.
 const BLINK_PAUSE: Duration = Duration::from_millis(500);
+/// Cursor travel (logical px) before a pressed tab starts dragging.
+const TAB_DRAG_THRESHOLD: f32 = 4.0;
 
 /// Smooth blink timing (must match GPU shader constants).
 const BLINK_FADE_DUR: f32 = 0.3;  // fade phase duration in seconds
 const BLINK_HOLD_DUR: f32 = 0.3;  // hold phase duration in seconds
 const BLINK_CYCLE: f32 = (BLINK_FADE_DUR + BLINK_HOLD_DUR) * 2.0; // 1.2s
 
+/// Font size and cell metrics a panel renders with: its profile font if it
+/// has one, otherwise the window font.
+fn panel_font(gpu: &GpuContext, panel: &TerminalPanel) -> (f32, CellMetrics) {
+    panel.font_metrics().unwrap_or((gpu.font_size, gpu.cell))
+}
+
+/// Panel theme with the panel's profile padding applied.
+fn panel_theme(theme: &PanelTheme, panel: &TerminalPanel) -> PanelTheme {
+    let mut theme = theme.clone();
+    if let Some(padding) = panel.style().padding {
+        theme.island_padding = padding;
+    }
+    theme
+}
+
+/// Measure the cell for a profile font, scaled by the current zoom. `None`
+/// when the style uses the window font unchanged.
+fn measure_panel_font(gpu: &mut GpuContext, style: &PanelStyle) -> Option<(f32, CellMetrics)> {
+    if style.font_family.is_none() && style.font_size.is_none() {
+        return None;
+    }
+    let base = style.font_size.unwrap_or(font::DEFAULT_FONT_SIZE);
+    let size = (base * gpu.font_size / font::DEFAULT_FONT_SIZE)
+        .clamp(font::MIN_FONT_SIZE, font::MAX_FONT_SIZE);
+    let cell = font::measure_cell_for(&mut gpu.font_system, size, style.font_family.as_deref());
+    Some((size, cell))
+}
+
 /// Returns (is_fading, seconds_until_next_phase_change) for the cursor blink.
 /// `input_age` is seconds since last input.
 /// During fade phases `is_fading=true` — render at 30fps.
@@ -69,6 +120,21 @@ fn blink_phase(input_age: f32) -> (bool, f32) {
     }
 }
 
+/// A tab held down with the left button; reordering starts once the
+/// cursor has moved past a small threshold.
+struct TabDrag {
+    panel: PanelId,
+    start_x: f32,
+    last_x: f32,
+    moved: bool,
+}
+
+/// Inline editor for a tab title, drawn over the tab.
+struct TabRename {
+    panel: PanelId,
+    field: TextField,
+}
+
 pub struct App {
     window: Option<Arc<Window>>,
     gpu: Option<GpuContext>,
@@ -79,6 +145,7 @@ pub struct App {
     active_tab: usize,
     tab_bar: TabBar,
     dropdown: DropdownMenu,
+    palette: CommandPalette,
     ssh_dialog: Option<SshDialog>,
     saved_sessions: SavedSessions,
     ssh_config_hosts: Vec<SshHostEntry>,
@@ -90,12 +157,34 @@ pub struct App {
     alt_pressed: bool,
     shift_pressed: bool,
     mouse_left_pressed: bool,
+    /// Keys whose press went to the terminal; only their releases follow.
+    forwarded_keys: HashSet<PhysicalKey>,
+    tab_drag: Option<TabDrag>,
+    renaming: Option<TabRename>,
+    /// Caret area last reported to the IME, for its candidate window.
+    ime_area: Option<Rect>,
+    /// Files dropped on an SSH tab, waiting for the upload/insert choice.
+    dropped_files: Vec<PathBuf>,
+    /// Command-line options for the first tab, consumed when the window opens.
+    startup: Option<Options>,
     last_click_time: Instant,
     click_count: u8,
     screenshot_pending: Option<String>,
     hotkey_config: HotkeyConfig,
     hotkey_lookup: HotkeyLookup,
     hotkeys_enabled: bool,
+    config: Config,
+    /// Problems found at startup (loading `config.json`, opening the
+    /// remote-control socket); shown once the window exists.
+    startup_error: Option<String>,
+    /// OS appearance is light (from the window theme).
+    system_light: bool,
+    /// Reports config reload errors; dismissed after a timeout.
+    toast: Option<Toast>,
+    /// Kept alive so config edits keep being delivered as events.
+    _config_watcher: Option<notify::RecommendedWatcher>,
+    /// Remote-control socket; `None` when disabled or it couldn't be opened.
+    ipc: Option<ipc::Server>,
     last_redraw: Instant,
     /// Set when new terminal content or user input arrives — forces an
     /// immediate render regardless of the cursor-blink throttle.
@@ -115,12 +204,35 @@ pub struct App {
 }
 
 impl App {
-    pub fn new(event_proxy_raw: EventLoopProxy<TerminalEvent>) -> Self {
-        let theme = Theme::new();
-        let saved_sessions = SavedSessions::load();
+    pub fn new(event_proxy_raw: EventLoopProxy<TerminalEvent>, options: Options) -> Self {
+        // An invalid file still starts with whatever parsed; the error is
+        // reported once the window is up.
+        let (config, config_error) = match Config::load() {
+            Ok(config) => (config, None),
+            Err(error) => (Config::read().unwrap_or_default(), Some(error)),
+        };
+        let mut theme = Theme::new();
+        theme.apply_layout(&config.layout);
+        // The OS appearance isn't known until the window exists; `resumed`
+        // re-resolves once it is.
+        theme.colors =
+            themes::resolve(&config, config.settings.is_light(false)).unwrap_or_default();
+        let saved_sessions = SavedSessions::new(config.sessions.clone());
         let ssh_config_hosts = crate::ssh_config::load_ssh_config();
-        let hotkey_config = HotkeyConfig::load();
+        let hotkey_config = HotkeyConfig::with_overrides(&config.hotkeys);
         let hotkey_lookup = hotkey_config.build_lookup();
+        let config_watcher = config_watch::spawn(event_proxy_raw.clone());
+        let (ipc, ipc_error) = match config.settings.remote_control {
+            true => match ipc::spawn(event_proxy_raw.clone()) {
+                Ok(server) => (Some(server), None),
+                Err(error) => (None, Some(format!("Remote control is unavailable: {error}"))),
+            },
+            false => (None, None),
+        };
+        let startup_error = match (config_error, ipc_error) {
+            (Some(config), Some(ipc)) => Some(format!("{config}\n{ipc}")),
+            (config, ipc) => config.or(ipc),
+        };
         let (shell_tx, shell_rx) = std::sync::mpsc::channel();
         std::thread::spawn(move || {
             let _ = shell_tx.send(detect_shells());
@@ -135,6 +247,7 @@ impl App {
             active_tab: 0,
             tab_bar: TabBar::new(),
             dropdown: DropdownMenu::new(),
+            palette: CommandPalette::new(),
             ssh_dialog: None,
             saved_sessions,
             ssh_config_hosts,
@@ -146,12 +259,24 @@ impl App {
             alt_pressed: false,
             shift_pressed: false,
             mouse_left_pressed: false,
+            forwarded_keys: HashSet::new(),
+            tab_drag: None,
+            renaming: None,
+            ime_area: None,
+            dropped_files: Vec::new(),
+            startup: Some(options),
             last_click_time: Instant::now(),
             click_count: 0,
             screenshot_pending: std::env::var("SCREENSHOT").ok().filter(|s| !s.is_empty()),
             hotkey_config,
             hotkey_lookup,
             hotkeys_enabled: true,
+            config,
+            startup_error,
+            system_light: false,
+            toast: None,
+            _config_watcher: config_watcher,
+            ipc,
             last_redraw: Instant::now(),
             dirty: false,
             occluded: false,
@@ -181,15 +306,18 @@ impl App {
     fn create_terminal_panel(
         &self,
         gpu: &GpuContext,
-        shell: Option<String>,
-        args: Vec<String>,
+        mut launch: Launch,
     ) -> Result<TerminalPanel, String> {
+        if let Some(ipc) = &self.ipc {
+            let path = ipc.path().to_string_lossy().into_owned();
+            launch.env.push((ipc::SOCKET_ENV.into(), path));
+        }
         let (panel_id, vp, event_proxy) = self.new_panel_params(gpu);
         let cell_px = (
             (gpu.cell.width * gpu.scale_factor) as u16,
             (gpu.cell.height * gpu.scale_factor) as u16,
         );
-        TerminalPanel::new(panel_id, TermSize::new(vp.cols, vp.rows), cell_px, event_proxy, shell, args)
+        TerminalPanel::new(panel_id, TermSize::new(vp.cols, vp.rows), cell_px, event_proxy, launch)
     }
 
     fn panel_area(&self, gpu: &GpuContext) -> Rect {
@@ -205,26 +333,28 @@ impl App {
 
     fn update_viewports(&mut self) {
         let Some(gpu) = self.gpu.as_ref() else { return };
-        let cell = gpu.cell;
         let scale = gpu.scale_factor;
         let area = self.panel_area(gpu);
         let tab_h = TabBar::height(&self.theme.tab_bar, scale);
 
         if let Some(panel) = self.tabs.get_mut(self.active_tab) {
-            let viewport = TerminalPanel::compute_viewport(&area, &cell, scale, tab_h, &self.theme.panel);
+            let (_, cell) = panel_font(gpu, panel);
+            let panel_theme = panel_theme(&self.theme.panel, panel);
+            let viewport = TerminalPanel::compute_viewport(&area, &cell, scale, tab_h, &panel_theme);
             panel.set_viewport(viewport, &cell);
         }
     }
 
     fn update_all_viewports(&mut self) {
         let Some(gpu) = self.gpu.as_ref() else { return };
-        let cell = gpu.cell;
         let scale = gpu.scale_factor;
         let area = self.panel_area(gpu);
         let tab_h = TabBar::height(&self.theme.tab_bar, scale);
 
         for panel in &mut self.tabs {
-            let viewport = TerminalPanel::compute_viewport(&area, &cell, scale, tab_h, &self.theme.panel);
+            let (_, cell) = panel_font(gpu, panel);
+            let panel_theme = panel_theme(&self.theme.panel, panel);
+            let viewport = TerminalPanel::compute_viewport(&area, &cell, scale, tab_h, &panel_theme);
             panel.set_viewport(viewport, &cell);
         }
     }
@@ -235,14 +365,23 @@ impl App {
         let pad = self.theme.general.panel_area_padding * scale;
         let panel_width = gpu.surface_config.width as f32 - 2.0 * pad;
 
-        let titles: Vec<String> = self
+        let labels: Vec<TabLabel> = self
             .tabs
             .iter()
-            .map(|p| p.title().to_string())
+            .map(|p| TabLabel {
+                // Recording indicator
+                title: if p.is_logging() {
+                    format!("\u{25cf} {}", p.title())
+                } else {
+                    p.title().to_string()
+                },
+                broadcast: p.broadcast(),
+                color: p.tab_color(),
+            })
             .collect();
 
         self.tab_bar.update(
-            &titles,
+            &labels,
             self.active_tab,
             panel_width,
             pad,
@@ -250,6 +389,17 @@ impl App {
             &mut gpu.font_system,
             &self.theme.tab_bar,
         );
+
+        // Keep the title editor over its tab as the bar is laid out again
+        if let Some(rename) = self.renaming.as_mut() {
+            match self.tabs.iter().position(|p| p.id() == rename.panel) {
+                Some(idx) => rename.field.set_rect(self.tab_bar.tab_rect(idx)),
+                None => {
+                    self.renaming = None;
+                    self.hotkeys_enabled = true;
+                }
+            }
+        }
     }
 
     /// Full redraw: rebuild scene from scratch, upload everything to GPU.
@@ -263,8 +413,11 @@ impl App {
 
         let Some(gpu) = self.gpu.as_mut() else { return false };
         let scale = gpu.scale_factor;
-        let cell = gpu.cell;
-        let colors = gpu.colors.clone();
+        let (font_size, cell) = panel_font(gpu, &self.tabs[self.active_tab]);
+        let colors = self.tabs[self.active_tab]
+            .color_scheme()
+            .unwrap_or(&gpu.colors)
+            .clone();
         let theme = &self.theme;
 
         // Scene: panels + tab bar — reuse cached Vecs to avoid per-frame allocation
@@ -272,6 +425,7 @@ impl App {
         let mut scene_tab_text = std::mem::take(&mut self.cached_scene_tab_text);
         let mut scene_panel_text = std::mem::take(&mut self.cached_scene_panel_text);
         scene.clear();
+        scene.light_icons = theme.colors.is_light();
         scene_tab_text.clear();
         scene_panel_text.clear();
 
@@ -280,14 +434,15 @@ impl App {
 
         // Panel rendering
         let panel = &mut self.tabs[self.active_tab];
+        let panel_theme = panel_theme(&theme.panel, panel);
         panel.draw(
             &mut scene,
             &mut scene_panel_text,
             &mut gpu.font_system,
             &colors,
             &cell,
-            gpu.font_size,
-            &theme.panel,
+            font_size,
+            &panel_theme,
         );
         let panel_bufs = panel.buffers();
 
@@ -316,15 +471,23 @@ impl App {
         let mut overlay = std::mem::take(&mut self.cached_overlay);
         let mut overlay_dd_text = std::mem::take(&mut self.cached_overlay_text);
         overlay.clear();
+        overlay.light_icons = theme.colors.is_light();
         overlay_dd_text.clear();
         panel.draw_scrollbar(&mut overlay);
+        let mut dialog_text_areas: Vec<glyphon::TextArea> = Vec::new();
+        panel.draw_upload(&mut overlay, &mut dialog_text_areas, &colors);
+        panel.draw_preedit(&mut overlay, &mut dialog_text_areas, &colors);
+        if let Some(rename) = &self.renaming {
+            rename.field.draw(&mut overlay, &mut dialog_text_areas, scale, &theme.colors);
+        }
         if self.dropdown.is_open() {
             self.dropdown.draw(&mut overlay, &mut overlay_dd_text, theme, scale);
         }
         let dd_bufs = self.dropdown.item_buffers();
+        let mut palette_text: Vec<TextSpec> = Vec::new();
+        self.palette.draw(&mut overlay, &mut palette_text, &mut dialog_text_areas, theme, scale);
 
         // SSH dialog overlay (scrim + dialog body + auth dropdown)
-        let mut dialog_text_areas: Vec<glyphon::TextArea> = Vec::new();
         let mut dialog_dd_text: Vec<TextSpec> = Vec::new();
         if let Some(dialog) = &self.ssh_dialog {
             let sw = gpu.surface_config.width as f32;
@@ -344,6 +507,23 @@ impl App {
             .map(|d| d.auth_dropdown().item_buffers())
             .unwrap_or(&[]);
 
+        // Toast sits above everything else
+        if self.toast.as_ref().is_some_and(Toast::expired) {
+            self.toast = None;
+        }
+        let mut toast_text: Vec<TextSpec> = Vec::new();
+        if let Some(toast) = &self.toast {
+            toast.draw(
+                &mut overlay,
+                &mut toast_text,
+                theme,
+                scale,
+                gpu.surface_config.width as f32,
+                gpu.surface_config.height as f32,
+            );
+        }
+        let toast_bufs = self.toast.as_ref().map(|t| t.buffers()).unwrap_or(&[]);
+
         #[cfg(feature = "debug-fps")]
         let _debug_t3 = Instant::now();
 
@@ -353,7 +533,9 @@ impl App {
         ];
         let overlay_text: Vec<(&[TextSpec], &[glyphon::Buffer])> = vec![
             (&overlay_dd_text, dd_bufs),
+            (&palette_text, self.palette.buffers()),
             (&dialog_dd_text, auth_dd_bufs),
+            (&toast_text, toast_bufs),
         ];
 
         let screenshot = self.screenshot_pending.take();
@@ -418,9 +600,134 @@ impl App {
                     p/dc, t/dc, g/dc, tot/dc);
             }
         }
+        self.update_ime_area();
         took_screenshot
     }
 
+    /// A file dropped on the window. Local tabs get its quoted path typed at
+    /// the cursor; SSH tabs offer to upload it. Several files arrive as
+    /// separate events, so the offer grows with each.
+    fn file_dropped(&mut self, path: PathBuf) {
+        if self.ssh_dialog.is_some() || self.palette.is_open() || self.renaming.is_some() {
+            return;
+        }
+        let Some(panel) = self.tabs.get(self.active_tab) else { return };
+        if !panel.accepts_input() {
+            return;
+        }
+        if !panel.is_ssh() {
+            self.insert_paths(&[path]);
+            return;
+        }
+        // Files left over from a drop menu dismissed without a choice, or
+        // with another menu open now, don't join this offer
+        let offering = self.dropdown.is_open()
+            && matches!(self.dropdown.action_for(0), Some(MenuAction::UploadDroppedFiles));
+        if !offering {
+            self.dropped_files.clear();
+        }
+        self.dropped_files.push(path);
+        let upload = match self.dropped_files.as_slice() {
+            [file] => {
+                let name = file.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+                format!("Upload {name} to Remote Directory")
+            }
+            files => format!("Upload {} Files to Remote Directory", files.len()),
+        };
+        let insert = if self.dropped_files.len() == 1 { "Insert Path" } else { "Insert Paths" };
+        let entries = vec![
+            MenuEntry::item(&upload, MenuAction::UploadDroppedFiles),
+            MenuEntry::item(insert, MenuAction::InsertDroppedPaths),
+        ];
+        let (x, y) = self.cursor_position;
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        let scale = gpu.scale_factor;
+        let surface_w = gpu.surface_config.width as f32;
+        let surface_h = gpu.surface_config.height as f32;
+        self.dropdown.open(
+            entries,
+            MenuPosition::AtPoint(x, y),
+            None,
+            scale,
+            surface_w,
+            surface_h,
+            &mut gpu.font_system,
+            &self.theme.dropdown,
+        );
+        self.request_redraw();
+    }
+
+    /// Type shell-quoted local paths into the active tab, space-separated.
+    fn insert_paths(&mut self, paths: &[PathBuf]) {
+        let Some(panel) = self.tabs.get_mut(self.active_tab) else { return };
+        let mut text = String::new();
+        for path in paths {
+            text.push_str(&upload::local_path_arg(path));
+            text.push(' ');
+        }
+        panel.notify_input();
+        panel.write_to_pty(text.into_bytes());
+        self.dirty = true;
+    }
+
+    /// Tell the IME where the caret is so its candidate window follows it.
+    fn update_ime_area(&mut self) {
+        let area = if let Some(dialog) = &self.ssh_dialog {
+            Some(dialog.ime_area())
+        } else if let Some(rename) = &self.renaming {
+            let scale = self.gpu.as_ref().map_or(1.0, |g| g.scale_factor);
+            Some(rename.field.cursor_rect(scale))
+        } else if self.palette.is_open() {
+            self.palette.ime_area()
+        } else {
+            self.tabs.get(self.active_tab).and_then(TerminalPanel::ime_area)
+        };
+        if area == self.ime_area {
+            return;
+        }
+        self.ime_area = area;
+        if let (Some(window), Some(r)) = (&self.window, area) {
+            window.set_ime_cursor_area(
+                winit::dpi::PhysicalPosition::new(r.x, r.y),
+                winit::dpi::PhysicalSize::new(r.width, r.height),
+            );
+        }
+    }
+
+    /// Show an IME composition in whatever has the keyboard.
+    fn ime_preedit(&mut self, text: &str, cursor: Option<usize>) {
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        if let Some(dialog) = self.ssh_dialog.as_mut() {
+            dialog.set_preedit(text, cursor, &mut gpu.font_system);
+        } else if let Some(rename) = self.renaming.as_mut() {
+            rename.field.set_preedit(text, cursor, &mut gpu.font_system);
+        } else if self.palette.is_open() {
+            self.palette.set_preedit(text, cursor, &mut gpu.font_system);
+        } else if !self.dropdown.is_open()
+            && let Some(panel) = self.tabs.get_mut(self.active_tab)
+        {
+            panel.set_preedit(text, cursor);
+            self.dirty = true;
+        }
+    }
+
+    /// Deliver text committed by the IME, like typed text.
+    fn ime_commit(&mut self, text: &str) {
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        if let Some(dialog) = self.ssh_dialog.as_mut() {
+            dialog.commit_text(text, &mut gpu.font_system);
+        } else if let Some(rename) = self.renaming.as_mut() {
+            rename.field.insert_text(text, &mut gpu.font_system);
+        } else if self.palette.is_open() {
+            self.palette.insert_text(text, &mut gpu.font_system);
+        } else if !self.dropdown.is_open() {
+            for panel in self.input_targets() {
+                panel.commit_text(text);
+            }
+            self.dirty = true;
+        }
+    }
+
     /// Blink-only redraw: reuse cached scene, only update cursor uniform.
     /// Skips scene rebuild and GPU data uploads for minimal CPU usage.
     fn redraw_blink_only(&mut self) {
@@ -435,11 +742,11 @@ impl App {
         }
 
         let Some(gpu) = self.gpu.as_mut() else { return };
-        let colors = &gpu.colors;
         let scale = gpu.scale_factor;
 
         // Update only the cursor data in the cached scene
         if let Some(panel) = self.tabs.get(self.active_tab) {
+            let colors = panel.color_scheme().unwrap_or(&gpu.colors);
             self.cached_scene.cursor = panel.cursor_data(colors, scale);
         }
 
@@ -519,18 +826,91 @@ impl App {
         }
     }
 
-    fn add_tab(&mut self, panel: TerminalPanel) {
+    fn add_tab(&mut self, mut panel: TerminalPanel) {
+        panel.set_ligatures(self.config.settings.ligatures);
+        self.apply_scrollback(&mut panel);
         self.tabs.push(panel);
         self.active_tab = self.tabs.len() - 1;
         self.sync_tab_state();
     }
 
     fn new_tab(&mut self, shell: Option<String>) {
-        let Some(gpu) = self.gpu.as_ref() else { return };
         // When shell is None, alacritty_terminal uses its own default_shell_command
         // which launches a proper login shell via /usr/bin/login on macOS.
         // This ensures ~/.zprofile is sourced and Homebrew PATH is available.
-        let panel = match self.create_terminal_panel(gpu, shell, Vec::new()) {
+        let launch = Launch {
+            shell: shell.clone(),
+            login: true,
+            ..Launch::default()
+        };
+        self.spawn_tab(launch, PanelStyle::default(), TabSource::Shell(shell));
+    }
+
+    /// Open a tab with the configured default profile, or the login shell.
+    fn new_default_tab(&mut self) {
+        match self.config.default_profile.clone() {
+            Some(name) => self.open_profile(&name),
+            None => self.new_tab(None),
+        }
+    }
+
+    fn open_profile(&mut self, name: &str) {
+        self.open_profile_with(name, None, None);
+    }
+
+    /// Open profile `name` with `command` and `cwd` (from the command line)
+    /// replacing the profile's own.
+    fn open_profile_with(
+        &mut self,
+        name: &str,
+        command: Option<Vec<String>>,
+        cwd: Option<PathBuf>,
+    ) {
+        let Some(profile) = self.config.profile(name).cloned() else {
+            self.new_tab(None);
+            return;
+        };
+        let (shell, args) = match command {
+            Some(mut command) => (Some(command.remove(0)), command),
+            None => (profile.command.clone(), profile.args.clone()),
+        };
+        let launch = Launch {
+            shell,
+            args,
+            login: false,
+            env: profile.env.clone().into_iter().collect(),
+            cwd: cwd.or_else(|| profile.working_directory()),
+        };
+        let font = profile.font.unwrap_or_default();
+        let mut style = PanelStyle {
+            color_scheme: None,
+            font_family: font.family,
+            font_size: font.size,
+            padding: profile.padding,
+        };
+        if let Some(theme) = &profile.theme {
+            match themes::load(theme) {
+                Ok(scheme) => style.color_scheme = Some(scheme),
+                Err(error) => self.show_toast(&error),
+            }
+        }
+        self.spawn_tab(launch, style, TabSource::Profile(profile.name.clone()));
+        if let Some(mut panel) = self.tabs.pop() {
+            panel.set_title(profile.name.clone());
+            panel.set_tab_color(profile.color);
+            panel.set_profile(Some(profile.name));
+            self.apply_scrollback(&mut panel);
+            self.tabs.push(panel);
+            if profile.log {
+                self.start_logging(self.tabs.len() - 1);
+            }
+        }
+        self.update_tab_bar();
+    }
+
+    fn spawn_tab(&mut self, launch: Launch, style: PanelStyle, source: TabSource) {
+        let Some(gpu) = self.gpu.as_ref() else { return };
+        let mut panel = match self.create_terminal_panel(gpu, launch) {
             Ok(p) => p,
             Err(error) => {
                 let (panel_id, vp, event_proxy) = self.new_panel_params(gpu);
@@ -538,6 +918,11 @@ impl App {
                 TerminalPanel::new_error(panel_id, size, event_proxy, &error)
             }
         };
+        if let Some(gpu) = self.gpu.as_mut() {
+            panel.set_font_metrics(measure_panel_font(gpu, &style));
+        }
+        panel.set_style(style);
+        panel.set_source(source);
         self.add_tab(panel);
     }
 
@@ -549,7 +934,107 @@ impl App {
         let Some(gpu) = self.gpu.as_ref() else { return };
         let (panel_id, vp, event_proxy) = self.new_panel_params(gpu);
         let size = TermSize::new(vp.cols, vp.rows);
-        let panel = TerminalPanel::new_ssh(panel_id, size, event_proxy, config);
+        let log = self.config.logging.logs_host(&config.host);
+        let source = TabSource::Ssh(config.clone());
+        let color = self.config.host_color(&config.host);
+        let mut panel = TerminalPanel::new_ssh(panel_id, size, event_proxy, config);
+        panel.set_source(source);
+        panel.set_tab_color(color);
+        self.add_tab(panel);
+        if log {
+            // Started right after the thread spawns; the connection takes
+            // far longer, so no session output is missed.
+            self.start_logging(self.tabs.len() - 1);
+            self.update_tab_bar();
+        }
+    }
+
+    /// Connection settings for a target, filled in from the `~/.ssh/config`
+    /// entry when the host is one of its aliases.
+    fn ssh_target_config(&self, target: &SshTarget) -> crate::ssh::SshConfig {
+        let entry = self.ssh_config_hosts.iter().find(|h| h.alias == target.host);
+        crate::ssh::SshConfig {
+            host: entry.map_or(target.host.as_str(), |h| h.effective_host()).to_string(),
+            port: target.port.or(entry.and_then(|h| h.port)).unwrap_or(22),
+            username: target
+                .user
+                .clone()
+                .or_else(|| entry.and_then(|h| h.user.clone()))
+                .unwrap_or_else(current_username),
+            auth: match entry.and_then(|h| h.identity_file.clone()) {
+                Some(path) => crate::ssh::SshAuth::Key {
+                    path,
+                    passphrase: None,
+                },
+                None => crate::ssh::SshAuth::Agent,
+            },
+        }
+    }
+
+    /// Open a tab as the command line or a remote `open` request asks.
+    fn open_tab(&mut self, options: Options) {
+        // An unknown profile is reported; the command and directory still run.
+        let profile = options.profile.as_ref().filter(|name| {
+            let known = self.config.profile(name).is_some();
+            if !known {
+                self.show_toast(&format!("Unknown profile \"{name}\""));
+            }
+            known
+        });
+
+        if let Some(target) = &options.ssh {
+            self.connect_ssh(self.ssh_target_config(target));
+        } else if let Some(name) = profile {
+            self.open_profile_with(name, options.command, options.working_directory);
+        } else if options.command.is_some() || options.working_directory.is_some() {
+            let (shell, args, login) = match options.command {
+                Some(mut command) => (Some(command.remove(0)), command, false),
+                None => (None, Vec::new(), true),
+            };
+            let launch = Launch {
+                shell,
+                args,
+                login,
+                cwd: options.working_directory,
+                ..Launch::default()
+            };
+            let source = TabSource::Command(launch.clone());
+            self.spawn_tab(launch, PanelStyle::default(), source);
+        } else {
+            self.new_default_tab();
+        }
+
+        if let Some(title) = options.title
+            && let Some(panel) = self.tabs.last_mut()
+        {
+            panel.set_custom_title(Some(title));
+            self.update_tab_bar();
+            self.update_window_title();
+        }
+        // Like a profile's font size, this one is the tab's own.
+        if let Some(size) = options.font_size
+            && let Some(panel) = self.tabs.last_mut()
+            && let Some(gpu) = self.gpu.as_mut()
+        {
+            let style = PanelStyle { font_size: Some(size), ..panel.style().clone() };
+            panel.set_font_metrics(measure_panel_font(gpu, &style));
+            panel.set_style(style);
+            self.update_viewports();
+            self.request_redraw();
+        }
+    }
+
+    fn open_recording(&mut self, path: &std::path::Path) {
+        let recording = match Recording::load(path) {
+            Ok(recording) => recording,
+            Err(error) => return self.show_toast(&error),
+        };
+        let Some(gpu) = self.gpu.as_ref() else { return };
+        let (panel_id, vp, event_proxy) = self.new_panel_params(gpu);
+        let size = TermSize::new(vp.cols, vp.rows);
+        let name = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+        let mut panel = TerminalPanel::new_replay(panel_id, size, event_proxy, recording, &name);
+        panel.set_source(TabSource::Replay(path.to_path_buf()));
         self.add_tab(panel);
     }
 
@@ -561,7 +1046,7 @@ impl App {
 
         if self.tabs.is_empty() {
             // Last tab closed — open a fresh one instead of exiting
-            self.new_tab(None);
+            self.new_default_tab();
             return;
         }
 
@@ -595,7 +1080,7 @@ impl App {
         match action {
             // --- Tab management ---
             NewTab => {
-                self.new_tab(None);
+                self.new_default_tab();
                 self.request_redraw();
             }
             CloseTab => {
@@ -654,10 +1139,8 @@ impl App {
             Paste => {
                 if let Ok(mut clip) = arboard::Clipboard::new()
                     && let Ok(text) = clip.get_text()
-                    && let Some(panel) = self.tabs.get_mut(self.active_tab)
                 {
-                    panel.notify_input();
-                    panel.write_to_pty(text.into_bytes());
+                    self.send_input(text.as_bytes());
                 }
             }
             CtrlC => {
@@ -677,6 +1160,15 @@ impl App {
                     }
                 }
             }
+            ToggleBroadcast => {
+                // Off if any tab is broadcasting, otherwise on for all tabs.
+                let on = !self.tabs.iter().any(|p| p.broadcast());
+                for panel in &mut self.tabs {
+                    panel.set_broadcast(on);
+                }
+                self.update_tab_bar();
+                self.request_redraw();
+            }
             SelectAll => {
                 if let Some(panel) = self.tabs.get_mut(self.active_tab) {
                     panel.select_all();
@@ -692,48 +1184,33 @@ impl App {
 
             // --- Line editing ---
             Home => {
-                if let Some(panel) = self.tabs.get_mut(self.active_tab) {
+                for panel in self.input_targets() {
                     panel.notify_input();
                     let seq = if panel.is_app_cursor() { b"\x1bOH".to_vec() } else { b"\x1b[H".to_vec() };
                     panel.write_to_pty(seq);
                 }
             }
             End => {
-                if let Some(panel) = self.tabs.get_mut(self.active_tab) {
+                for panel in self.input_targets() {
                     panel.notify_input();
                     let seq = if panel.is_app_cursor() { b"\x1bOF".to_vec() } else { b"\x1b[F".to_vec() };
                     panel.write_to_pty(seq);
                 }
             }
             PreviousWord => {
-                if let Some(panel) = self.tabs.get_mut(self.active_tab) {
-                    panel.notify_input();
-                    panel.write_to_pty(b"\x1bb".to_vec()); // ESC b
-                }
+                self.send_input(b"\x1bb"); // ESC b
             }
             NextWord => {
-                if let Some(panel) = self.tabs.get_mut(self.active_tab) {
-                    panel.notify_input();
-                    panel.write_to_pty(b"\x1bf".to_vec()); // ESC f
-                }
+                self.send_input(b"\x1bf"); // ESC f
             }
             DeletePreviousWord => {
-                if let Some(panel) = self.tabs.get_mut(self.active_tab) {
-                    panel.notify_input();
-                    panel.write_to_pty(b"\x17".to_vec()); // Ctrl+W
-                }
+                self.send_input(b"\x17"); // Ctrl+W
             }
             DeleteNextWord => {
-                if let Some(panel) = self.tabs.get_mut(self.active_tab) {
-                    panel.notify_input();
-                    panel.write_to_pty(b"\x1bd".to_vec()); // ESC d
-                }
+                self.send_input(b"\x1bd"); // ESC d
             }
             DeleteLine => {
-                if let Some(panel) = self.tabs.get_mut(self.active_tab) {
-                    panel.notify_input();
-                    panel.write_to_pty(b"\x15".to_vec()); // Ctrl+U
-                }
+                self.send_input(b"\x15"); // Ctrl+U
             }
 
             // --- Zoom ---
@@ -751,6 +1228,14 @@ impl App {
             ResetZoom => {
                 self.set_font_size(font::DEFAULT_FONT_SIZE);
             }
+            ToggleLigatures => {
+                self.config.settings.ligatures = !self.config.settings.ligatures;
+                self.config.settings.save();
+                for panel in &mut self.tabs {
+                    panel.set_ligatures(self.config.settings.ligatures);
+                }
+                self.request_redraw();
+            }
 
             // --- Search ---
             Search => {
@@ -808,6 +1293,8 @@ impl App {
                 }
             }
 
+            CommandPalette => self.open_command_palette(),
+
             // --- Not yet implemented ---
             NewWindow | Settings => {
                 // TODO
@@ -831,14 +1318,17 @@ impl App {
             return;
         }
         gpu.set_font_size(size);
-        drop(gpu);
+        for panel in &mut self.tabs {
+            let metrics = measure_panel_font(gpu, panel.style());
+            panel.set_font_metrics(metrics);
+        }
         self.update_all_viewports();
         self.dirty = true;
         self.request_redraw();
     }
 
-    fn open_new_tab_dropdown(&mut self) {
-        // Populate cache from background thread if not yet available
+    /// Populate the shell cache from the background thread if not yet available.
+    fn load_shells(&mut self) {
         if self.cached_shells.is_none() {
             if let Some(rx) = self.shell_receiver.take() {
                 // recv() blocks only if the thread hasn't finished yet;
@@ -848,30 +1338,58 @@ impl App {
                 }
             }
         }
-        let shells = self.cached_shells.as_deref().unwrap_or(&[]);
+    }
+
+    /// A saved session's name: its alias from the SSH config if it has one.
+    fn saved_session_label(&self, session: &SavedSession) -> String {
+        self.ssh_config_hosts
+            .iter()
+            .find(|h| h.effective_host() == session.host)
+            .map(|h| h.alias.clone())
+            .unwrap_or_else(|| session.display_label())
+    }
+
+    /// SSH config hosts that aren't already saved sessions.
+    fn unsaved_ssh_hosts(&self) -> Vec<&SshHostEntry> {
         let saved = &self.saved_sessions.sessions;
+        self.ssh_config_hosts
+            .iter()
+            .filter(|h| {
+                !saved
+                    .iter()
+                    .any(|s| s.host == h.effective_host() || s.host == h.alias)
+            })
+            .collect()
+    }
 
-        let Some(gpu) = self.gpu.as_mut() else { return };
-        let scale = gpu.scale_factor;
-        let surface_w = gpu.surface_config.width as f32;
-        let surface_h = gpu.surface_config.height as f32;
+    fn open_new_tab_dropdown(&mut self) {
+        self.load_shells();
+        let ssh_hosts = self.unsaved_ssh_hosts();
+        let shells = self.cached_shells.as_deref().unwrap_or(&[]);
+        let saved = &self.saved_sessions.sessions;
 
-        let mut entries: Vec<MenuEntry> = shells
+        // Profiles from config.json first, then detected shells
+        let mut entries: Vec<MenuEntry> = self
+            .config
+            .profiles
             .iter()
-            .map(|(label, path)| MenuEntry::item(label, MenuAction::NewShell(path.clone())))
+            .map(|p| MenuEntry::item(&p.name, MenuAction::NewProfile(p.name.clone())))
             .collect();
+        if !entries.is_empty() {
+            entries.push(MenuEntry::Separator);
+        }
+        entries.extend(
+            shells
+                .iter()
+                .map(|(label, path)| MenuEntry::item(label, MenuAction::NewShell(path.clone()))),
+        );
 
         // Separator between shells and SSH section
         entries.push(MenuEntry::Separator);
 
         // Saved SSH sessions (sorted by last used), with alias from SSH config if available
         for session in saved {
-            let label = self
-                .ssh_config_hosts
-                .iter()
-                .find(|h| h.effective_host() == session.host)
-                .map(|h| h.alias.clone())
-                .unwrap_or_else(|| session.display_label());
+            let label = self.saved_session_label(session);
             entries.push(MenuEntry::closeable_item_with_icon(
                 &label,
                 MenuAction::ConnectSavedSession(session.key()),
@@ -880,16 +1398,6 @@ impl App {
         }
 
         // SSH config hosts not yet in saved sessions
-        let ssh_hosts: Vec<_> = self
-            .ssh_config_hosts
-            .iter()
-            .filter(|h| {
-                !saved
-                    .iter()
-                    .any(|s| s.host == h.effective_host() || s.host == h.alias)
-            })
-            .collect();
-
         if !ssh_hosts.is_empty() {
             if !saved.is_empty() {
                 entries.push(MenuEntry::Separator);
@@ -914,6 +1422,10 @@ impl App {
             icons::ICON_ADD,
         ));
 
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        let scale = gpu.scale_factor;
+        let surface_w = gpu.surface_config.width as f32;
+        let surface_h = gpu.surface_config.height as f32;
         let anchor = self.tab_bar.plus_rect();
         self.dropdown.open(
             entries,
@@ -927,6 +1439,122 @@ impl App {
         );
     }
 
+    /// Close the dropdown and run its item `idx`.
+    fn activate_dropdown_item(&mut self, idx: usize) {
+        let action = self.dropdown.action_for(idx).cloned();
+        self.dropdown.close();
+        if let Some(action) = action {
+            self.execute_menu_action(&action);
+        }
+    }
+
+    /// Forget what closeable dropdown item `idx` stands for (a saved
+    /// session). Returns true if something was removed.
+    fn remove_dropdown_item(&mut self, idx: usize) -> bool {
+        match self.dropdown.action_for(idx).cloned() {
+            Some(MenuAction::ConnectSavedSession(key)) => {
+                self.saved_sessions.remove_by_key(&key);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Open the command palette over everything it can run: open tabs,
+    /// profiles, shells, SSH hosts and hotkey actions.
+    fn open_command_palette(&mut self) {
+        use HotkeyAction::*;
+        self.load_shells();
+        let menu = |label: &str, detail: &str, action| {
+            PaletteItem::new(label, detail, PaletteAction::Menu(action))
+        };
+
+        let mut items: Vec<PaletteItem> = self
+            .tabs
+            .iter()
+            .map(|p| menu(p.title(), "Tab", MenuAction::SelectTab(p.id())))
+            .collect();
+        for profile in &self.config.profiles {
+            let action = MenuAction::NewProfile(profile.name.clone());
+            items.push(menu(&profile.name, "Profile", action));
+        }
+        for (label, path) in self.cached_shells.as_deref().unwrap_or(&[]) {
+            items.push(menu(label, "Shell", MenuAction::NewShell(path.clone())));
+        }
+        for session in &self.saved_sessions.sessions {
+            let label = self.saved_session_label(session);
+            let mut item = menu(&label, "SSH", MenuAction::ConnectSavedSession(session.key()));
+            item.last_used = session.last_used;
+            items.push(item);
+        }
+        for host in self.unsaved_ssh_hosts() {
+            let action = MenuAction::ConnectSshConfigHost(host.alias.clone());
+            items.push(menu(&host.display_label(), "SSH config", action));
+        }
+        items.push(menu("New SSH Session...", "SSH", MenuAction::OpenSshDialog));
+        // Placeholders that don't do anything yet stay out of the list.
+        for &action in HotkeyAction::ALL {
+            if matches!(action, ReopenTab | Search | NewWindow | Settings | CommandPalette) {
+                continue;
+            }
+            let shortcut = self.hotkey_config.hotkeys.get(&action).and_then(|keys| keys.first());
+            let detail = shortcut.map(String::as_str).unwrap_or_default();
+            items.push(PaletteItem::new(action.label(), detail, PaletteAction::Hotkey(action)));
+        }
+
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        self.dropdown.close();
+        self.palette.open(
+            items,
+            gpu.scale_factor,
+            gpu.surface_config.width as f32,
+            gpu.surface_config.height as f32,
+            &mut gpu.font_system,
+            &self.theme,
+        );
+        self.hotkeys_enabled = false;
+        self.request_redraw();
+    }
+
+    fn palette_key(&mut self, event: &winit::event::KeyEvent, event_loop: &ActiveEventLoop) {
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        let clipboard_mod = if cfg!(target_os = "macos") {
+            self.super_pressed
+        } else {
+            self.ctrl_pressed
+        };
+        if clipboard_mod {
+            match event.logical_key.as_ref() {
+                Key::Character("v") => {
+                    if let Ok(mut clip) = arboard::Clipboard::new()
+                        && let Ok(text) = clip.get_text()
+                    {
+                        self.palette.insert_text(&text, &mut gpu.font_system);
+                    }
+                }
+                Key::Character("a") => self.palette.select_all(),
+                _ => {}
+            }
+            return;
+        }
+        match self.palette.key(event, &mut gpu.font_system, self.shift_pressed) {
+            PaletteKey::Handled => {}
+            PaletteKey::Closed => self.hotkeys_enabled = true,
+            PaletteKey::Run(action) => self.run_palette_action(action, event_loop),
+        }
+    }
+
+    fn run_palette_action(&mut self, action: PaletteAction, event_loop: &ActiveEventLoop) {
+        self.hotkeys_enabled = true;
+        match action {
+            PaletteAction::Hotkey(action) => {
+                self.handle_hotkey_action(action, event_loop);
+            }
+            PaletteAction::Menu(action) => self.execute_menu_action(&action),
+        }
+        self.request_redraw();
+    }
+
     fn open_context_menu(&mut self, x: f32, y: f32) {
         let has_selection = self
             .tabs
@@ -938,6 +1566,18 @@ impl App {
             entries.push(MenuEntry::item("Copy", MenuAction::Copy));
         }
         entries.push(MenuEntry::item("Paste", MenuAction::Paste));
+        entries.push(MenuEntry::Separator);
+        if let Some(panel) = self.tabs.get(self.active_tab) {
+            let (used, limit, bytes) = panel.scrollback_usage();
+            let label = format!(
+                "Clear Scrollback ({used} / {limit} lines, {:.1} MB)",
+                bytes as f64 / (1024.0 * 1024.0)
+            );
+            entries.push(MenuEntry::item(&label, MenuAction::ClearScrollback));
+        }
+        entries.push(MenuEntry::item("Export Scrollback...", MenuAction::ExportScrollback));
+        entries.push(MenuEntry::item("Replay Recording...", MenuAction::ReplayRecording));
+        entries.push(MenuEntry::item("Theme...", MenuAction::OpenThemePicker));
 
         let Some(gpu) = self.gpu.as_mut() else { return };
         let scale = gpu.scale_factor;
@@ -956,17 +1596,508 @@ impl App {
         );
     }
 
-    fn open_ssh_dialog(&mut self, prefill: Option<SshPrefill>) {
-        if self.ssh_dialog.is_some() {
-            return; // already open
-        }
+    /// List every tab under the overflow button.
+    fn open_tab_list(&mut self) {
+        let entries = self
+            .tabs
+            .iter()
+            .map(|p| MenuEntry::item(p.title(), MenuAction::SelectTab(p.id())))
+            .collect();
+
         let Some(gpu) = self.gpu.as_mut() else { return };
         let scale = gpu.scale_factor;
-        let sw = gpu.surface_config.width as f32;
-        let sh = gpu.surface_config.height as f32;
-        self.ssh_dialog = Some(SshDialog::new(
+        let surface_w = gpu.surface_config.width as f32;
+        let surface_h = gpu.surface_config.height as f32;
+
+        self.dropdown.open(
+            entries,
+            MenuPosition::BelowAnchor(self.tab_bar.overflow_rect()),
+            Some(280.0),
             scale,
-            &self.theme,
+            surface_w,
+            surface_h,
+            &mut gpu.font_system,
+            &self.theme.dropdown,
+        );
+    }
+
+    /// Move the dragged tab to the tab under the cursor, following the
+    /// drag direction so unequal widths don't swap back and forth.
+    fn drag_tab(&mut self, cx: f32) {
+        let scale = self.gpu.as_ref().map(|g| g.scale_factor).unwrap_or(1.0);
+        let Some(drag) = self.tab_drag.as_mut() else { return };
+        if !drag.moved && (cx - drag.start_x).abs() < TAB_DRAG_THRESHOLD * scale {
+            return;
+        }
+        drag.moved = true;
+        // The tab may have closed or moved under the drag
+        let Some(from) = self.tabs.iter().position(|p| p.id() == drag.panel) else {
+            self.tab_drag = None;
+            return;
+        };
+        let rightward = cx > drag.last_x;
+        drag.last_x = cx;
+        let Some(to) = self.tab_bar.tab_at(cx) else { return };
+        if to == from || (to > from) != rightward {
+            return;
+        }
+        let panel = self.tabs.remove(from);
+        self.tabs.insert(to, panel);
+        self.active_tab = to;
+        self.update_tab_bar();
+        self.request_redraw();
+    }
+
+    fn open_tab_menu(&mut self, idx: usize, x: f32, y: f32) {
+        let Some(panel) = self.tabs.get(idx) else { return };
+        let id = panel.id();
+        let mut entries = vec![MenuEntry::item("Rename Tab...", MenuAction::RenameTab(id))];
+        if panel.has_custom_title() {
+            entries.push(MenuEntry::item("Reset Title", MenuAction::ResetTabTitle(id)));
+        }
+        if !matches!(panel.source(), TabSource::None) {
+            entries.push(MenuEntry::item("Duplicate Tab", MenuAction::DuplicateTab(id)));
+        }
+        entries.push(MenuEntry::item("Set Color...", MenuAction::OpenTabColorPicker(id)));
+        entries.push(MenuEntry::Separator);
+
+        let label = if panel.is_logging() { "Stop Logging" } else { "Start Logging" };
+        entries.push(MenuEntry::item(label, MenuAction::ToggleLogging(id)));
+        if panel.accepts_input() {
+            let label = if panel.broadcast() { "Stop Broadcasting Input" } else { "Broadcast Input" };
+            entries.push(MenuEntry::item(label, MenuAction::ToggleBroadcast(id)));
+        }
+
+        if self.tabs.len() > 1 {
+            entries.push(MenuEntry::Separator);
+            entries.push(MenuEntry::item("Close Other Tabs", MenuAction::CloseOtherTabs(id)));
+            if idx + 1 < self.tabs.len() {
+                entries.push(MenuEntry::item(
+                    "Close Tabs to the Right",
+                    MenuAction::CloseTabsToRight(id),
+                ));
+            }
+        }
+
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        let scale = gpu.scale_factor;
+        let surface_w = gpu.surface_config.width as f32;
+        let surface_h = gpu.surface_config.height as f32;
+
+        self.dropdown.open(
+            entries,
+            MenuPosition::AtPoint(x, y),
+            None,
+            scale,
+            surface_w,
+            surface_h,
+            &mut gpu.font_system,
+            &self.theme.dropdown,
+        );
+    }
+
+    fn open_tab_color_picker(&mut self, id: PanelId) {
+        let mut entries = vec![MenuEntry::item("None", MenuAction::SetTabColor(id, None))];
+        entries.extend(TabColor::ALL.into_iter().map(|color| {
+            MenuEntry::item(color.label(), MenuAction::SetTabColor(id, Some(color)))
+        }));
+
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        let scale = gpu.scale_factor;
+        let surface_w = gpu.surface_config.width as f32;
+        let surface_h = gpu.surface_config.height as f32;
+        let (x, y) = self.cursor_position;
+
+        self.dropdown.open(
+            entries,
+            MenuPosition::AtPoint(x, y),
+            None,
+            scale,
+            surface_w,
+            surface_h,
+            &mut gpu.font_system,
+            &self.theme.dropdown,
+        );
+    }
+
+    /// Open a title editor over the tab, pre-filled and selected.
+    fn start_rename(&mut self, id: PanelId) {
+        let Some(idx) = self.tabs.iter().position(|p| p.id() == id) else { return };
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        let t = &self.theme.tab_bar;
+        let metrics = glyphon::Metrics::new(t.font_size, t.font_size * font::LINE_HEIGHT);
+        let char_width = font::measure_cell(&mut gpu.font_system).width;
+        let mut field = TextField::new(
+            "Tab title",
+            false,
+            metrics,
+            char_width,
+            t.tab_radius,
+            t.tab_padding_h,
+            &mut gpu.font_system,
+        );
+        field.set_value(self.tabs[idx].title(), &mut gpu.font_system);
+        field.select_all();
+        field.set_focused(true);
+        field.set_rect(self.tab_bar.tab_rect(idx));
+        self.renaming = Some(TabRename { panel: id, field });
+        self.hotkeys_enabled = false;
+        self.request_redraw();
+    }
+
+    /// Close the title editor; when committing, an empty title goes back to
+    /// the one the shell sets.
+    fn finish_rename(&mut self, commit: bool) {
+        let Some(rename) = self.renaming.take() else { return };
+        self.hotkeys_enabled = true;
+        if commit
+            && let Some(panel) = self.tabs.iter_mut().find(|p| p.id() == rename.panel)
+        {
+            let title = rename.field.value().trim();
+            panel.set_custom_title((!title.is_empty()).then(|| title.to_string()));
+            self.update_tab_bar();
+            self.update_window_title();
+        }
+        self.request_redraw();
+    }
+
+    fn rename_key(&mut self, event: &winit::event::KeyEvent) {
+        match event.logical_key.as_ref() {
+            Key::Named(NamedKey::Enter) => return self.finish_rename(true),
+            Key::Named(NamedKey::Escape) => return self.finish_rename(false),
+            _ => {}
+        }
+        let (Some(gpu), Some(rename)) = (self.gpu.as_mut(), self.renaming.as_mut()) else {
+            return;
+        };
+        let clipboard_mod = if cfg!(target_os = "macos") {
+            self.super_pressed
+        } else {
+            self.ctrl_pressed
+        };
+        if clipboard_mod {
+            match event.logical_key.as_ref() {
+                Key::Character("v") => {
+                    if let Ok(mut clip) = arboard::Clipboard::new()
+                        && let Ok(text) = clip.get_text()
+                    {
+                        rename.field.insert_text(&text, &mut gpu.font_system);
+                    }
+                }
+                Key::Character("a") => rename.field.select_all(),
+                _ => {}
+            }
+            return;
+        }
+        rename.field.edit_key(event, &mut gpu.font_system, self.shift_pressed);
+    }
+
+    /// Open another tab the way tab `idx` was opened, right after it.
+    fn duplicate_tab(&mut self, idx: usize) {
+        let Some(panel) = self.tabs.get(idx) else { return };
+        let (source, color) = (panel.source().clone(), panel.tab_color());
+        let count = self.tabs.len();
+        match source {
+            TabSource::None => return,
+            TabSource::Shell(shell) => self.new_tab(shell),
+            TabSource::Command(launch) => {
+                self.spawn_tab(launch.clone(), PanelStyle::default(), TabSource::Command(launch));
+            }
+            TabSource::Profile(name) => self.open_profile(&name),
+            TabSource::Ssh(config) => self.connect_ssh(config),
+            TabSource::Replay(path) => self.open_recording(&path),
+        }
+        if self.tabs.len() > count
+            && let Some(mut panel) = self.tabs.pop()
+        {
+            panel.set_tab_color(color);
+            self.tabs.insert(idx + 1, panel);
+            self.active_tab = idx + 1;
+            self.sync_tab_state();
+        }
+    }
+
+    /// Close the tabs for which `close` is true, asking first when that's
+    /// more than one.
+    fn close_tabs_where(&mut self, close: impl Fn(usize, &TerminalPanel) -> bool) {
+        let doomed: Vec<PanelId> = self
+            .tabs
+            .iter()
+            .enumerate()
+            .filter(|(i, p)| close(*i, p))
+            .map(|(_, p)| p.id())
+            .collect();
+        if doomed.is_empty()
+            || (doomed.len() > 1 && !self.confirm_close(&format!("Close {} tabs?", doomed.len())))
+        {
+            return;
+        }
+        let active = self.tabs.get(self.active_tab).map(|p| p.id());
+        self.tabs.retain(|p| !doomed.contains(&p.id()));
+        self.active_tab = active
+            .and_then(|id| self.tabs.iter().position(|p| p.id() == id))
+            .unwrap_or(self.active_tab);
+        self.clamp_active_tab();
+        self.sync_tab_state();
+    }
+
+    fn open_theme_picker(&mut self) {
+        let mut entries = vec![MenuEntry::item("Default", MenuAction::SetTheme(None))];
+        entries.extend(
+            themes::available()
+                .into_iter()
+                .map(|name| MenuEntry::item(&name, MenuAction::SetTheme(Some(name.clone())))),
+        );
+        entries.push(MenuEntry::Separator);
+        entries.push(MenuEntry::item("Import Theme...", MenuAction::ImportTheme));
+
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        let scale = gpu.scale_factor;
+        let surface_w = gpu.surface_config.width as f32;
+        let surface_h = gpu.surface_config.height as f32;
+        let (x, y) = self.cursor_position;
+
+        self.dropdown.open(
+            entries,
+            MenuPosition::AtPoint(x, y),
+            None,
+            scale,
+            surface_w,
+            surface_h,
+            &mut gpu.font_system,
+            &self.theme.dropdown,
+        );
+    }
+
+    /// Set the scheme for the current appearance (dark or light) and persist it.
+    fn set_theme(&mut self, name: Option<String>) {
+        let light = self.config.settings.is_light(self.system_light);
+        *self.config.settings.theme_mut(light) = name;
+        self.config.settings.save();
+        if let Err(error) = self.refresh_color_scheme() {
+            self.show_toast(&error);
+        }
+    }
+
+    /// Re-resolve the global scheme for the current appearance and apply it.
+    fn refresh_color_scheme(&mut self) -> Result<(), String> {
+        let light = self.config.settings.is_light(self.system_light);
+        let scheme = themes::resolve(&self.config, light)?;
+        self.apply_color_scheme(scheme);
+        Ok(())
+    }
+
+    /// Pin the window's title bar appearance when the user forces one,
+    /// otherwise let it follow the OS (which also enables `ThemeChanged`).
+    fn apply_window_appearance(&self) {
+        let Some(window) = &self.window else { return };
+        window.set_theme(match self.config.settings.appearance {
+            Appearance::System => None,
+            Appearance::Dark => Some(WindowTheme::Dark),
+            Appearance::Light => Some(WindowTheme::Light),
+        });
+    }
+
+    fn apply_color_scheme(&mut self, scheme: ColorScheme) {
+        if let Some(gpu) = self.gpu.as_mut() {
+            gpu.colors = scheme.clone();
+        }
+        self.theme.colors = scheme;
+        self.request_redraw();
+    }
+
+    /// Re-read a config file after it changed on disk and apply it live.
+    /// Parse errors keep the current config and are shown in a toast.
+    fn reload_config(&mut self, file: ConfigFile) {
+        let result = match file {
+            ConfigFile::Themes => self.refresh_color_scheme(),
+            ConfigFile::Config => Config::load().and_then(|config| self.apply_config(config)),
+        };
+        match result {
+            Ok(()) => self.request_redraw(),
+            Err(error) => self.show_toast(&error),
+        }
+    }
+
+    /// Swap in a freshly loaded config. Open tabs keep their profile; only
+    /// global settings, hotkeys, sessions and layout change.
+    fn apply_config(&mut self, config: Config) -> Result<(), String> {
+        self.hotkey_config = HotkeyConfig::with_overrides(&config.hotkeys);
+        self.hotkey_lookup = self.hotkey_config.build_lookup();
+        self.saved_sessions = SavedSessions::new(config.sessions.clone());
+        self.theme.apply_layout(&config.layout);
+        self.config = config;
+        let mut tabs = std::mem::take(&mut self.tabs);
+        for panel in &mut tabs {
+            panel.set_ligatures(self.config.settings.ligatures);
+            self.apply_scrollback(panel);
+        }
+        self.tabs = tabs;
+        self.apply_window_appearance();
+        self.update_all_viewports();
+        self.update_tab_bar();
+        self.refresh_color_scheme()
+    }
+
+    /// Save the active panel's full history; the format follows the chosen
+    /// file extension.
+    fn export_scrollback(&mut self) -> Result<(), String> {
+        let Some(panel) = self.tabs.get(self.active_tab) else { return Ok(()) };
+        let Some(path) = rfd::FileDialog::new()
+            .set_title("Export Scrollback")
+            .add_filter("Plain text", &["txt"])
+            .add_filter("ANSI text", &["ans"])
+            .add_filter("HTML", &["html"])
+            .set_file_name("scrollback.txt")
+            .save_file()
+        else {
+            return Ok(());
+        };
+        let colors = panel.color_scheme().unwrap_or(&self.theme.colors);
+        let output = panel.export(colors, ExportFormat::from_path(&path));
+        std::fs::write(&path, output).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Index of the tab a remote-control request addresses; `None` is the
+    /// active tab.
+    fn ipc_tab(&self, id: Option<u64>) -> Result<usize, String> {
+        match id {
+            Some(id) => self
+                .tabs
+                .iter()
+                .position(|p| p.id().get() == id)
+                .ok_or_else(|| format!("no tab with id {id}")),
+            None if self.tabs.is_empty() => Err("no tabs are open".into()),
+            None => Ok(self.active_tab),
+        }
+    }
+
+    fn handle_ipc(&mut self, request: Request) -> ipc::Response {
+        match request {
+            Request::List => Ok(self
+                .tabs
+                .iter()
+                .enumerate()
+                .map(|(i, panel)| {
+                    json!({
+                        "id": panel.id().get(),
+                        "title": panel.title(),
+                        "active": i == self.active_tab,
+                    })
+                })
+                .collect()),
+            Request::Open(options) => {
+                if let Some(name) = &options.profile
+                    && self.config.profile(name).is_none()
+                {
+                    return Err(format!("unknown profile \"{name}\""));
+                }
+                if self.gpu.is_none() {
+                    return Err("the window isn't open yet".into());
+                }
+                self.open_tab(options);
+                let panel = &self.tabs[self.active_tab];
+                Ok(json!({ "id": panel.id().get() }))
+            }
+            Request::Focus { tab } => {
+                self.active_tab = self.ipc_tab(Some(tab))?;
+                self.sync_tab_state();
+                if let Some(window) = &self.window {
+                    window.focus_window();
+                }
+                Ok(Value::Null)
+            }
+            Request::SendText { tab, text } => {
+                self.tabs[self.ipc_tab(tab)?].write_to_pty(text.into_bytes());
+                Ok(Value::Null)
+            }
+            Request::GetText { tab, ansi } => {
+                let panel = &self.tabs[self.ipc_tab(tab)?];
+                let colors = panel.color_scheme().unwrap_or(&self.theme.colors);
+                let format = if ansi { ExportFormat::Ansi } else { ExportFormat::Text };
+                Ok(Value::String(panel.export(colors, format)))
+            }
+            Request::SetTitle { tab, title } => {
+                let idx = self.ipc_tab(tab)?;
+                self.tabs[idx].set_custom_title(title.filter(|t| !t.is_empty()));
+                self.update_tab_bar();
+                self.update_window_title();
+                self.request_redraw();
+                Ok(Value::Null)
+            }
+            Request::Close { tab } => {
+                self.close_tab(self.ipc_tab(tab)?);
+                Ok(Value::Null)
+            }
+        }
+    }
+
+    /// Tabs that receive typed input: every broadcasting tab when the active
+    /// tab is one of them, otherwise just the active tab.
+    fn input_targets(&mut self) -> Vec<&mut TerminalPanel> {
+        let active = self.active_tab;
+        let broadcasting = self.tabs.get(active).is_some_and(|p| p.broadcast());
+        self.tabs
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, p)| *i == active || (broadcasting && p.broadcast()))
+            .map(|(_, p)| p)
+            .collect()
+    }
+
+    /// Write bytes to the input targets, as if typed.
+    fn send_input(&mut self, bytes: &[u8]) {
+        for panel in self.input_targets() {
+            panel.notify_input();
+            panel.write_to_pty(bytes.to_vec());
+        }
+    }
+
+    /// Start logging a tab into the configured log directory; failures are
+    /// reported in a toast.
+    fn start_logging(&mut self, idx: usize) {
+        let Some(dir) = self.config.logging.directory() else {
+            self.show_toast("No data directory available for session logs");
+            return;
+        };
+        let format = self.config.logging.format;
+        let Some(panel) = self.tabs.get_mut(idx) else { return };
+        if let Err(error) = panel.start_logging(&dir, format) {
+            self.show_toast(&error);
+        }
+    }
+
+    fn apply_scrollback(&self, panel: &mut TerminalPanel) {
+        let scrollback = self.config.scrollback(panel.profile());
+        panel.set_scrollback(scrollback, self.config.settings.scrollback_memory_mb);
+    }
+
+    fn show_toast(&mut self, message: &str) {
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        let toast = Toast::new(message, &mut gpu.font_system, &self.theme.toast);
+        self.toast = Some(toast);
+
+        // Wake the event loop once the toast has expired so it gets cleared.
+        let proxy = self.event_proxy_raw.clone();
+        let duration = Duration::from_secs_f32(self.theme.toast.duration_secs + 0.05);
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let _ = proxy.send_event(TerminalEvent::Wakeup);
+        });
+        self.request_redraw();
+    }
+
+    fn open_ssh_dialog(&mut self, prefill: Option<SshPrefill>) {
+        if self.ssh_dialog.is_some() {
+            return; // already open
+        }
+        let Some(gpu) = self.gpu.as_mut() else { return };
+        let scale = gpu.scale_factor;
+        let sw = gpu.surface_config.width as f32;
+        let sh = gpu.surface_config.height as f32;
+        self.ssh_dialog = Some(SshDialog::new(
+            scale,
+            &self.theme,
             &mut gpu.font_system,
             prefill.as_ref(),
             sw,
@@ -996,6 +2127,8 @@ impl App {
                 _ => None,
             },
             last_used: now_unix(),
+            theme: None,
+            color: None,
         };
         self.saved_sessions.upsert(saved);
     }
@@ -1005,6 +2138,9 @@ impl App {
             MenuAction::NewShell(shell_path) => {
                 self.new_tab(Some(shell_path.clone()));
             }
+            MenuAction::NewProfile(name) => {
+                self.open_profile(name);
+            }
             MenuAction::OpenSshDialog => {
                 self.open_ssh_dialog(None);
             }
@@ -1030,29 +2166,25 @@ impl App {
                             SavedAuthType::Agent => crate::ssh::SshAuth::Agent,
                         },
                     };
+                    let scheme = session.theme.as_deref().and_then(|n| themes::load(n).ok());
+                    let color = session.color;
                     self.saved_sessions.touch_by_key(key);
                     self.connect_ssh(config);
+                    if let Some(panel) = self.tabs.last_mut() {
+                        panel.set_color_scheme(scheme);
+                        if color.is_some() {
+                            panel.set_tab_color(color);
+                        }
+                    }
                 }
             }
             MenuAction::ConnectSshConfigHost(alias) => {
-                if let Some(host) = self.ssh_config_hosts.iter().find(|h| h.alias == *alias) {
-                    let config = crate::ssh::SshConfig {
-                        host: host.effective_host().to_string(),
-                        port: host.effective_port(),
-                        username: host
-                            .user
-                            .clone()
-                            .unwrap_or_else(current_username),
-                        auth: match &host.identity_file {
-                            Some(path) => crate::ssh::SshAuth::Key {
-                                path: path.clone(),
-                                passphrase: None,
-                            },
-                            None => crate::ssh::SshAuth::Agent,
-                        },
-                    };
-                    self.connect_ssh(config);
-                }
+                let target = SshTarget {
+                    user: None,
+                    host: alias.clone(),
+                    port: None,
+                };
+                self.connect_ssh(self.ssh_target_config(&target));
             }
             MenuAction::Copy => {
                 if let Some(panel) = self.tabs.get_mut(self.active_tab) {
@@ -1067,12 +2199,116 @@ impl App {
             MenuAction::Paste => {
                 if let Ok(mut clip) = arboard::Clipboard::new()
                     && let Ok(text) = clip.get_text()
-                    && let Some(panel) = self.tabs.get_mut(self.active_tab)
                 {
-                    panel.notify_input();
-                    panel.write_to_pty(text.into_bytes());
+                    self.send_input(text.as_bytes());
                 }
             }
+            MenuAction::ClearScrollback => {
+                if let Some(panel) = self.tabs.get_mut(self.active_tab) {
+                    panel.clear_scrollback();
+                    self.dirty = true;
+                }
+            }
+            MenuAction::ExportScrollback => {
+                if let Err(error) = self.export_scrollback() {
+                    self.show_toast(&error);
+                }
+            }
+            MenuAction::ReplayRecording => {
+                let picked = rfd::FileDialog::new()
+                    .set_title("Replay Recording")
+                    .add_filter("asciicast", &["cast"])
+                    .set_directory(self.config.logging.directory().unwrap_or_default())
+                    .pick_file();
+                if let Some(path) = picked {
+                    self.open_recording(&path);
+                }
+            }
+            MenuAction::ToggleBroadcast(panel_id) => {
+                if let Some(panel) = self.tabs.iter_mut().find(|p| p.id() == *panel_id) {
+                    panel.set_broadcast(!panel.broadcast());
+                    self.update_tab_bar();
+                }
+            }
+            MenuAction::SelectTab(panel_id) => {
+                if let Some(idx) = self.tabs.iter().position(|p| p.id() == *panel_id) {
+                    self.active_tab = idx;
+                    self.sync_tab_state();
+                }
+            }
+            MenuAction::RenameTab(panel_id) => {
+                self.start_rename(*panel_id);
+            }
+            MenuAction::ResetTabTitle(panel_id) => {
+                if let Some(panel) = self.tabs.iter_mut().find(|p| p.id() == *panel_id) {
+                    panel.set_custom_title(None);
+                    self.update_tab_bar();
+                    self.update_window_title();
+                }
+            }
+            MenuAction::DuplicateTab(panel_id) => {
+                if let Some(idx) = self.tabs.iter().position(|p| p.id() == *panel_id) {
+                    self.duplicate_tab(idx);
+                }
+            }
+            MenuAction::CloseOtherTabs(panel_id) => {
+                self.close_tabs_where(|_, p| p.id() != *panel_id);
+            }
+            MenuAction::CloseTabsToRight(panel_id) => {
+                if let Some(idx) = self.tabs.iter().position(|p| p.id() == *panel_id) {
+                    self.close_tabs_where(|i, _| i > idx);
+                }
+            }
+            MenuAction::OpenTabColorPicker(panel_id) => {
+                self.open_tab_color_picker(*panel_id);
+            }
+            MenuAction::SetTabColor(panel_id, color) => {
+                if let Some(panel) = self.tabs.iter_mut().find(|p| p.id() == *panel_id) {
+                    panel.set_tab_color(*color);
+                    self.update_tab_bar();
+                }
+            }
+            MenuAction::ToggleLogging(panel_id) => {
+                if let Some(idx) = self.tabs.iter().position(|p| p.id() == *panel_id) {
+                    if self.tabs[idx].is_logging() {
+                        self.tabs[idx].stop_logging();
+                    } else {
+                        self.start_logging(idx);
+                    }
+                    self.update_tab_bar();
+                }
+            }
+            MenuAction::OpenThemePicker => {
+                self.open_theme_picker();
+            }
+            MenuAction::SetTheme(name) => {
+                self.set_theme(name.clone());
+            }
+            MenuAction::ImportTheme => {
+                let picked = rfd::FileDialog::new()
+                    .set_title("Import Theme")
+                    .add_filter(
+                        "Color schemes",
+                        &["itermcolors", "toml", "yml", "yaml", "json"],
+                    )
+                    .pick_file();
+                if let Some(path) = picked {
+                    match themes::install(&path) {
+                        Ok(name) => self.set_theme(Some(name)),
+                        Err(error) => self.show_toast(&format!("Couldn't import theme: {error}")),
+                    }
+                }
+            }
+            MenuAction::UploadDroppedFiles => {
+                let files = std::mem::take(&mut self.dropped_files);
+                if let Some(panel) = self.tabs.get(self.active_tab) {
+                    panel.upload(files);
+                }
+            }
+            MenuAction::InsertDroppedPaths => {
+                let files = std::mem::take(&mut self.dropped_files);
+                self.insert_paths(&files);
+            }
         }
     }
 }
@@ -1095,7 +2331,19 @@ impl ApplicationHandler<TerminalEvent> for App {
                 return;
             }
         };
-        let gpu = match GpuContext::new(window.clone(), self.theme.colors.clone()) {
+        // Input methods (CJK, dead keys) deliver text through WindowEvent::Ime.
+        window.set_ime_allowed(true);
+        // Resolve the scheme for the real OS appearance before the first frame.
+        self.system_light = window.theme() == Some(WindowTheme::Light);
+        self.window = Some(window.clone());
+        self.apply_window_appearance();
+        let scheme_error = self.refresh_color_scheme().err();
+
+        let gpu = match GpuContext::new(
+            window.clone(),
+            self.theme.colors.clone(),
+            self.config.settings.color_emoji,
+        ) {
             Some(g) => g,
             None => {
                 event_loop.exit();
@@ -1103,13 +2351,16 @@ impl ApplicationHandler<TerminalEvent> for App {
             }
         };
 
-        self.window = Some(window.clone());
         self.gpu = Some(gpu);
 
         // Set up native menu bar after winit initialization
         crate::menu::setup_native_menu();
 
-        self.new_tab(None);
+        let options = self.startup.take().unwrap_or_default();
+        self.open_tab(options);
+        if let Some(error) = self.startup_error.take().or(scheme_error) {
+            self.show_toast(&error);
+        }
 
         // Render the first frame before showing the window to avoid a blank flash
         self.redraw();
@@ -1159,6 +2410,12 @@ impl ApplicationHandler<TerminalEvent> for App {
                 self.clamp_active_tab();
                 self.sync_tab_state();
             }
+            TerminalEvent::ConfigChanged(file) => {
+                self.reload_config(file);
+            }
+            TerminalEvent::Ipc(request, reply) => {
+                reply.send(self.handle_ipc(request));
+            }
         }
     }
 
@@ -1188,6 +2445,8 @@ impl ApplicationHandler<TerminalEvent> for App {
                 self.dropdown.close();
                 if let Some(gpu) = &mut self.gpu {
                     gpu.resize(new_size.width, new_size.height);
+                    let (sw, sh) = (new_size.width as f32, new_size.height as f32);
+                    self.palette.resize(gpu.scale_factor, sw, sh);
                     // Recenter SSH dialog on resize
                     if let Some(dialog) = &mut self.ssh_dialog {
                         let scale = gpu.scale_factor;
@@ -1203,6 +2462,9 @@ impl ApplicationHandler<TerminalEvent> for App {
                 self.dropdown.close();
                 if let Some(gpu) = &mut self.gpu {
                     gpu.scale_factor = scale_factor as f32;
+                    let sw = gpu.surface_config.width as f32;
+                    let sh = gpu.surface_config.height as f32;
+                    self.palette.resize(gpu.scale_factor, sw, sh);
                     // Recenter SSH dialog on scale change
                     if let Some(dialog) = &mut self.ssh_dialog {
                         let sw = gpu.surface_config.width as f32;
@@ -1220,6 +2482,15 @@ impl ApplicationHandler<TerminalEvent> for App {
                 self.request_redraw();
             }
 
+            WindowEvent::ThemeChanged(window_theme) => {
+                self.system_light = window_theme == WindowTheme::Light;
+                if self.config.settings.appearance == Appearance::System
+                    && let Err(error) = self.refresh_color_scheme()
+                {
+                    self.show_toast(&error);
+                }
+            }
+
             WindowEvent::Occluded(is_occluded) => {
                 self.occluded = is_occluded;
                 if !is_occluded {
@@ -1253,7 +2524,13 @@ impl ApplicationHandler<TerminalEvent> for App {
                 }
 
                 let now = Instant::now();
-                let has_overlay = self.dropdown.is_open() || self.ssh_dialog.is_some();
+                let has_overlay = self.dropdown.is_open()
+                    || self.palette.is_open()
+                    || self.ssh_dialog.is_some()
+                    || self.renaming.is_some()
+                    || self.tabs.get(self.active_tab).is_some_and(|p| {
+                        p.has_preedit() || p.has_upload_status()
+                    });
 
                 if self.dirty || has_overlay {
                     #[cfg(feature = "debug-fps")]
@@ -1294,6 +2571,17 @@ impl ApplicationHandler<TerminalEvent> for App {
                     return;
                 }
 
+                if self.palette.is_open() {
+                    let hover = self.palette.hit_test(cx, cy);
+                    if self.palette.set_hover(hover) {
+                        self.request_redraw();
+                    }
+                    if let Some(window) = &self.window {
+                        window.set_cursor(CursorIcon::Default);
+                    }
+                    return;
+                }
+
                 // Dropdown hover takes priority when open
                 if self.dropdown.is_open() {
                     let hover = self.dropdown.hit_test(cx, cy);
@@ -1303,6 +2591,10 @@ impl ApplicationHandler<TerminalEvent> for App {
                     return;
                 }
 
+                if self.tab_drag.is_some() {
+                    self.drag_tab(cx);
+                }
+
                 // Drag scrollbar or selection
                 if self.mouse_left_pressed {
                     if let Some(panel) = self.tabs.get_mut(self.active_tab) {
@@ -1387,22 +2679,26 @@ impl ApplicationHandler<TerminalEvent> for App {
                     return;
                 }
 
+                // A click on a palette row runs it; outside closes the palette
+                if self.palette.is_open() {
+                    if let Some(pos) = self.palette.hit_test(cx, cy)
+                        && let Some(action) = self.palette.run(pos)
+                    {
+                        self.run_palette_action(action, event_loop);
+                    } else if self.palette.is_outside(cx, cy) {
+                        self.palette.close();
+                        self.hotkeys_enabled = true;
+                    }
+                    self.request_redraw();
+                    return;
+                }
+
                 // Dropdown intercepts all clicks when open
                 if self.dropdown.is_open() {
                     match self.dropdown.hit_test(cx, cy) {
-                        DropdownElement::Item(idx) => {
-                            let action = self.dropdown.action_for(idx).cloned();
-                            self.dropdown.close();
-                            if let Some(action) = action {
-                                self.execute_menu_action(&action);
-                            }
-                        }
+                        DropdownElement::Item(idx) => self.activate_dropdown_item(idx),
                         DropdownElement::CloseButton(idx) => {
-                            if let Some(MenuAction::ConnectSavedSession(key)) =
-                                self.dropdown.action_for(idx).cloned()
-                            {
-                                self.saved_sessions.remove_by_key(&key);
-                            }
+                            self.remove_dropdown_item(idx);
                             self.dropdown.close();
                         }
                         DropdownElement::None => {
@@ -1416,6 +2712,17 @@ impl ApplicationHandler<TerminalEvent> for App {
                 }
 
                 let scale = self.gpu.as_ref().map(|g| g.scale_factor).unwrap_or(1.0);
+
+                // Clicks in the title editor move its cursor; anywhere else commits it
+                if let Some(rename) = self.renaming.as_mut() {
+                    if rename.field.contains(cx, cy) {
+                        rename.field.click(cx, scale);
+                        self.request_redraw();
+                        return;
+                    }
+                    self.finish_rename(true);
+                }
+
                 let pad = self.theme.general.panel_area_padding * scale;
                 let tab_h = TabBar::height(&self.theme.tab_bar, scale);
 
@@ -1425,6 +2732,12 @@ impl ApplicationHandler<TerminalEvent> for App {
                             if idx < self.tabs.len() {
                                 self.active_tab = idx;
                                 self.sync_tab_state();
+                                self.tab_drag = Some(TabDrag {
+                                    panel: self.tabs[idx].id(),
+                                    start_x: cx,
+                                    last_x: cx,
+                                    moved: false,
+                                });
                             }
                         }
                         TabBarElement::CloseButton(idx) => {
@@ -1434,7 +2747,11 @@ impl ApplicationHandler<TerminalEvent> for App {
                         }
                         TabBarElement::PlusButton => {
                             // Left click on "+" opens a default tab
-                            self.new_tab(None);
+                            self.new_default_tab();
+                            self.request_redraw();
+                        }
+                        TabBarElement::OverflowButton => {
+                            self.open_tab_list();
                             self.request_redraw();
                         }
                         TabBarElement::None => {}
@@ -1475,6 +2792,25 @@ impl ApplicationHandler<TerminalEvent> for App {
                     panel.stop_scrollbar_drag();
                 }
                 self.mouse_left_pressed = false;
+                self.tab_drag = None;
+            }
+
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Middle,
+                ..
+            } => {
+                if self.ssh_dialog.is_some() || self.dropdown.is_open() {
+                    return;
+                }
+                self.finish_rename(true);
+                let (cx, cy) = self.cursor_position;
+                // Middle-click closes a tab, like the close button
+                if let TabBarElement::Tab(idx) | TabBarElement::CloseButton(idx) =
+                    self.tab_bar.hit_test(cx, cy)
+                {
+                    self.close_tab(idx);
+                }
             }
 
             WindowEvent::MouseInput {
@@ -1485,15 +2821,33 @@ impl ApplicationHandler<TerminalEvent> for App {
                 if self.ssh_dialog.is_some() {
                     return; // Dialog absorbs right-clicks
                 }
+                self.finish_rename(true);
                 let (cx, cy) = self.cursor_position;
                 if self.dropdown.is_open() {
                     self.dropdown.close();
                 }
-                // Right-click on "+" opens shell/SSH picker dropdown
-                if self.tab_bar.hit_test(cx, cy) == TabBarElement::PlusButton {
-                    self.open_new_tab_dropdown();
-                } else {
-                    self.open_context_menu(cx, cy);
+                match self.tab_bar.hit_test(cx, cy) {
+                    // Right-click on "+" opens shell/SSH picker dropdown
+                    TabBarElement::PlusButton => self.open_new_tab_dropdown(),
+                    TabBarElement::OverflowButton => self.open_tab_list(),
+                    TabBarElement::Tab(idx) | TabBarElement::CloseButton(idx) => {
+                        self.open_tab_menu(idx, cx, cy);
+                    }
+                    TabBarElement::None => self.open_context_menu(cx, cy),
+                }
+                self.request_redraw();
+            }
+
+            WindowEvent::DroppedFile(path) => self.file_dropped(path),
+
+            WindowEvent::Ime(ime) => {
+                match ime {
+                    Ime::Preedit(text, cursor) => {
+                        self.ime_preedit(&text, cursor.map(|(_, end)| end));
+                    }
+                    Ime::Commit(text) => self.ime_commit(&text),
+                    Ime::Disabled => self.ime_preedit("", None),
+                    Ime::Enabled => {}
                 }
                 self.request_redraw();
             }
@@ -1536,13 +2890,45 @@ impl ApplicationHandler<TerminalEvent> for App {
                     return;
                 }
 
-                // Close dropdown on Escape
-                if self.dropdown.is_open()
-                    && event.state == ElementState::Pressed
-                    && matches!(event.physical_key, PhysicalKey::Code(KeyCode::Escape))
-                {
-                    self.dropdown.close();
-                    self.request_redraw();
+                // The tab title editor takes the keyboard while open
+                if self.renaming.is_some() {
+                    if event.state == ElementState::Pressed {
+                        self.rename_key(&event);
+                        self.request_redraw();
+                    }
+                    return;
+                }
+
+                // So does the command palette
+                if self.palette.is_open() {
+                    if event.state == ElementState::Pressed {
+                        self.palette_key(&event, event_loop);
+                        self.request_redraw();
+                    }
+                    return;
+                }
+
+                // An open dropdown takes the keyboard
+                if self.dropdown.is_open() {
+                    if event.state == ElementState::Pressed {
+                        match self.dropdown.key(&event) {
+                            DropdownKey::Handled => {}
+                            DropdownKey::Close => self.dropdown.close(),
+                            DropdownKey::Activate(idx) => self.activate_dropdown_item(idx),
+                            DropdownKey::Remove(idx) => {
+                                if self.remove_dropdown_item(idx) {
+                                    self.dropdown.remove(idx);
+                                }
+                            }
+                        }
+                        self.request_redraw();
+                    }
+                    return;
+                }
+
+                // Keys that drive an IME composition (candidate selection, Enter,
+                // Backspace) belong to the input method; the text arrives on commit
+                if self.tabs.get(self.active_tab).is_some_and(|p| p.has_preedit()) {
                     return;
                 }
 
@@ -1576,8 +2962,26 @@ impl ApplicationHandler<TerminalEvent> for App {
                     }
                 }
 
-                if let Some(panel) = self.tabs.get_mut(self.active_tab) {
-                    panel.handle_key(&event, self.ctrl_pressed, self.alt_pressed, self.shift_pressed);
+                // A press swallowed above (hotkey, menu, Cmd) must not leave the
+                // program a release it never saw pressed
+                if event.state == ElementState::Pressed {
+                    self.forwarded_keys.insert(event.physical_key);
+                } else if !self.forwarded_keys.remove(&event.physical_key) {
+                    return;
+                }
+
+                let mods = Mods {
+                    shift: self.shift_pressed,
+                    alt: self.alt_pressed,
+                    ctrl: self.ctrl_pressed,
+                    super_key: self.super_pressed,
+                };
+                let targets = self.input_targets();
+                if !targets.is_empty() {
+                    // Each tab encodes the key for its own modes (app cursor etc.).
+                    for panel in targets {
+                        panel.handle_key(&event, mods);
+                    }
                     self.dirty = true;
                     self.request_redraw();
                 }
@@ -1587,11 +2991,51 @@ impl ApplicationHandler<TerminalEvent> for App {
                 if self.ssh_dialog.is_some() {
                     return; // Dialog absorbs scroll events
                 }
-                let cell_height = self
-                    .gpu
-                    .as_ref()
-                    .map(|g| g.cell.height as f64 * g.scale_factor as f64)
-                    .unwrap_or(16.0);
+                if self.palette.is_open() {
+                    let rows = match delta {
+                        MouseScrollDelta::LineDelta(_, y) => -y.round() as isize,
+                        MouseScrollDelta::PixelDelta(p) => {
+                            let scale = self.gpu.as_ref().map_or(1.0, |g| g.scale_factor);
+                            let row = self.theme.dropdown.item_height * scale;
+                            (-p.y as f32 / row).round() as isize
+                        }
+                    };
+                    if self.palette.scroll(rows) {
+                        let (cx, cy) = self.cursor_position;
+                        let hover = self.palette.hit_test(cx, cy);
+                        self.palette.set_hover(hover);
+                        self.request_redraw();
+                    }
+                    return;
+                }
+
+                // Over an overflowing tab bar the wheel scrolls the tabs
+                let scale = self.gpu.as_ref().map(|g| g.scale_factor).unwrap_or(1.0);
+                let pad = self.theme.general.panel_area_padding * scale;
+                let tab_h = TabBar::height(&self.theme.tab_bar, scale);
+                let cy = self.cursor_position.1;
+                if cy >= pad && cy < pad + tab_h && self.tab_bar.is_overflowing() {
+                    let tabs = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => {
+                            if x.abs() > y.abs() { x } else { -y }
+                        }
+                        MouseScrollDelta::PixelDelta(p) => {
+                            let px = if p.x.abs() > p.y.abs() { p.x } else { -p.y };
+                            px as f32 / (self.theme.tab_bar.tab_min_width * scale)
+                        }
+                    };
+                    if self.tab_bar.scroll(tabs) {
+                        self.update_tab_bar();
+                        self.request_redraw();
+                    }
+                    return;
+                }
+                let cell_height = match (self.gpu.as_ref(), self.tabs.get(self.active_tab)) {
+                    (Some(g), Some(panel)) => {
+                        panel_font(g, panel).1.height as f64 * g.scale_factor as f64
+                    }
+                    _ => 16.0,
+                };
                 if let Some(panel) = self.tabs.get_mut(self.active_tab)
                     && panel.handle_scroll(delta, cell_height)
                 {
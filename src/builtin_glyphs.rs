@@ -0,0 +1,619 @@
+use crate::draw::DrawContext;
+use crate::layout::Rect;
+
+// ---------------------------------------------------------------------------
+// Procedural glyphs
+//
+// Box drawing, block elements, braille, sextants and Powerline separators are
+// drawn as flat geometry snapped to the cell grid instead of font glyphs, so
+// borders connect seamlessly at every zoom level whatever the font provides.
+// ---------------------------------------------------------------------------
+
+/// Draw `c` into `cell` if it is one of the procedural glyphs.
+/// Returns false (drawing nothing) for every other character.
+pub fn draw(
+    ctx: &mut DrawContext,
+    c: char,
+    cell: Rect,
+    color: [f32; 4],
+    clip_top: f32,
+    clip_bottom: f32,
+) -> bool {
+    let mut p = Painter {
+        ctx,
+        color,
+        clip_top,
+        clip_bottom,
+        x0: cell.x.round(),
+        y0: cell.y.round(),
+        x1: (cell.x + cell.width).round(),
+        y1: (cell.y + cell.height).round(),
+    };
+    let u = c as u32;
+    match u {
+        0x2500..=0x257F => p.box_drawing(u),
+        0x2580..=0x259F => p.block_element(u),
+        0x2800..=0x28FF => p.braille((u - 0x2800) as u8),
+        0x1FB00..=0x1FB3B => p.sextant(sextant_bits(u - 0x1FB00)),
+        0xE0B0..=0xE0BF => p.powerline(u),
+        _ => return false,
+    }
+    true
+}
+
+/// Arm weights for one box-drawing char, packed as left/right/up/down.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Arms {
+    left: u8,
+    right: u8,
+    up: u8,
+    down: u8,
+}
+
+const NONE: u8 = 0;
+const LIGHT: u8 = 1;
+const HEAVY: u8 = 2;
+const DOUBLE: u8 = 3;
+
+const fn arms(left: u8, right: u8, up: u8, down: u8) -> Arms {
+    Arms { left, right, up, down }
+}
+
+/// Arms for U+2500..U+257F. Dashes, arcs and diagonals are handled
+/// separately and left empty here.
+fn box_arms(u: u32) -> Arms {
+    const L: u8 = LIGHT;
+    const H: u8 = HEAVY;
+    const D: u8 = DOUBLE;
+    const O: u8 = NONE;
+    const TABLE: [Arms; 128] = [
+        // 2500..250F: ─ ━ │ ┃ (dashes) ┌ ┍ ┎ ┏
+        arms(L, L, O, O), arms(H, H, O, O), arms(O, O, L, L), arms(O, O, H, H),
+        arms(O, O, O, O), arms(O, O, O, O), arms(O, O, O, O), arms(O, O, O, O),
+        arms(O, O, O, O), arms(O, O, O, O), arms(O, O, O, O), arms(O, O, O, O),
+        arms(O, L, O, L), arms(O, H, O, L), arms(O, L, O, H), arms(O, H, O, H),
+        // 2510..251F: ┐ ┑ ┒ ┓ └ ┕ ┖ ┗ ┘ ┙ ┚ ┛ ├ ┝ ┞ ┟
+        arms(L, O, O, L), arms(H, O, O, L), arms(L, O, O, H), arms(H, O, O, H),
+        arms(O, L, L, O), arms(O, H, L, O), arms(O, L, H, O), arms(O, H, H, O),
+        arms(L, O, L, O), arms(H, O, L, O), arms(L, O, H, O), arms(H, O, H, O),
+        arms(O, L, L, L), arms(O, H, L, L), arms(O, L, H, L), arms(O, L, L, H),
+        // 2520..252F: ┠ ┡ ┢ ┣ ┤ ┥ ┦ ┧ ┨ ┩ ┪ ┫ ┬ ┭ ┮ ┯
+        arms(O, L, H, H), arms(O, H, H, L), arms(O, H, L, H), arms(O, H, H, H),
+        arms(L, O, L, L), arms(H, O, L, L), arms(L, O, H, L), arms(L, O, L, H),
+        arms(L, O, H, H), arms(H, O, H, L), arms(H, O, L, H), arms(H, O, H, H),
+        arms(L, L, O, L), arms(H, L, O, L), arms(L, H, O, L), arms(H, H, O, L),
+        // 2530..253F: ┰ ┱ ┲ ┳ ┴ ┵ ┶ ┷ ┸ ┹ ┺ ┻ ┼ ┽ ┾ ┿
+        arms(L, L, O, H), arms(H, L, O, H), arms(L, H, O, H), arms(H, H, O, H),
+        arms(L, L, L, O), arms(H, L, L, O), arms(L, H, L, O), arms(H, H, L, O),
+        arms(L, L, H, O), arms(H, L, H, O), arms(L, H, H, O), arms(H, H, H, O),
+        arms(L, L, L, L), arms(H, L, L, L), arms(L, H, L, L), arms(H, H, L, L),
+        // 2540..254F: ╀ ╁ ╂ ╃ ╄ ╅ ╆ ╇ ╈ ╉ ╊ ╋ (dashes)
+        arms(L, L, H, L), arms(L, L, L, H), arms(L, L, H, H), arms(H, L, H, L),
+        arms(L, H, H, L), arms(H, L, L, H), arms(L, H, L, H), arms(H, H, H, L),
+        arms(H, H, L, H), arms(H, L, H, H), arms(L, H, H, H), arms(H, H, H, H),
+        arms(O, O, O, O), arms(O, O, O, O), arms(O, O, O, O), arms(O, O, O, O),
+        // 2550..255F: ═ ║ ╒ ╓ ╔ ╕ ╖ ╗ ╘ ╙ ╚ ╛ ╜ ╝ ╞ ╟
+        arms(D, D, O, O), arms(O, O, D, D), arms(O, D, O, L), arms(O, L, O, D),
+        arms(O, D, O, D), arms(D, O, O, L), arms(L, O, O, D), arms(D, O, O, D),
+        arms(O, D, L, O), arms(O, L, D, O), arms(O, D, D, O), arms(D, O, L, O),
+        arms(L, O, D, O), arms(D, O, D, O), arms(O, D, L, L), arms(O, L, D, D),
+        // 2560..256F: ╠ ╡ ╢ ╣ ╤ ╥ ╦ ╧ ╨ ╩ ╪ ╫ ╬ (arcs)
+        arms(O, D, D, D), arms(D, O, L, L), arms(L, O, D, D), arms(D, O, D, D),
+        arms(D, D, O, L), arms(L, L, O, D), arms(D, D, O, D), arms(D, D, L, O),
+        arms(L, L, D, O), arms(D, D, D, O), arms(D, D, L, L), arms(L, L, D, D),
+        arms(D, D, D, D), arms(O, O, O, O), arms(O, O, O, O), arms(O, O, O, O),
+        // 2570..257F: (arc) (diagonals) ╴ ╵ ╶ ╷ ╸ ╹ ╺ ╻ ╼ ╽ ╾ ╿
+        arms(O, O, O, O), arms(O, O, O, O), arms(O, O, O, O), arms(O, O, O, O),
+        arms(L, O, O, O), arms(O, O, L, O), arms(O, L, O, O), arms(O, O, O, L),
+        arms(H, O, O, O), arms(O, O, H, O), arms(O, H, O, O), arms(O, O, O, H),
+        arms(L, H, O, O), arms(O, O, L, H), arms(H, L, O, O), arms(O, O, H, L),
+    ];
+    TABLE[(u - 0x2500) as usize]
+}
+
+/// Sextant index (U+1FB00 + i) → 6-bit mask, bit 0 = top-left, bit 5 =
+/// bottom-right. The block has no entries for empty, full, left half and
+/// right half, since those already exist as block elements.
+fn sextant_bits(i: u32) -> u8 {
+    let mut v = i + 1;
+    if v >= 0b010101 {
+        v += 1;
+    }
+    if v >= 0b101010 {
+        v += 1;
+    }
+    v as u8
+}
+
+struct Painter<'a> {
+    ctx: &'a mut DrawContext,
+    color: [f32; 4],
+    clip_top: f32,
+    clip_bottom: f32,
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl Painter<'_> {
+    fn width(&self) -> f32 {
+        self.x1 - self.x0
+    }
+
+    fn height(&self) -> f32 {
+        self.y1 - self.y0
+    }
+
+    /// Light stroke width, proportional to the cell so it scales with zoom.
+    fn light(&self) -> f32 {
+        (self.width() / 8.0).round().max(1.0)
+    }
+
+    fn thickness(&self, weight: u8) -> f32 {
+        match weight {
+            HEAVY => self.light() * 2.0,
+            _ => self.light(),
+        }
+    }
+
+    fn mid_x(&self) -> f32 {
+        (self.x0 + self.width() / 2.0).floor()
+    }
+
+    fn mid_y(&self) -> f32 {
+        (self.y0 + self.height() / 2.0).floor()
+    }
+
+    fn rect(&mut self, x0: f32, y0: f32, x1: f32, y1: f32) {
+        self.rect_alpha(x0, y0, x1, y1, 1.0);
+    }
+
+    fn rect_alpha(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, alpha: f32) {
+        let (x0, x1) = (x0.min(x1).round(), x0.max(x1).round());
+        let (y0, y1) = (y0.min(y1).round(), y0.max(y1).round());
+        if x1 <= x0 || y1 <= y0 {
+            return;
+        }
+        let r = Rect { x: x0, y: y0, width: x1 - x0, height: y1 - y0 };
+        if let Some(clipped) = r.clip_y(self.clip_top, self.clip_bottom) {
+            let mut color = self.color;
+            color[3] *= alpha;
+            self.ctx.flat_quad(clipped, color);
+        }
+    }
+
+    /// Fill a convex polygon, clipped to the vertical clip range.
+    fn polygon(&mut self, points: &[[f32; 2]]) {
+        let clipped = clip_polygon_y(points, self.clip_top, self.clip_bottom);
+        for i in 1..clipped.len().saturating_sub(1) {
+            self.ctx
+                .flat_triangle([clipped[0], clipped[i], clipped[i + 1]], self.color);
+        }
+    }
+
+    /// A straight stroke of width `t` between two points.
+    fn line(&mut self, a: [f32; 2], b: [f32; 2], t: f32) {
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+            return;
+        }
+        let (nx, ny) = (-dy / len * t / 2.0, dx / len * t / 2.0);
+        self.polygon(&[
+            [a[0] + nx, a[1] + ny],
+            [b[0] + nx, b[1] + ny],
+            [b[0] - nx, b[1] - ny],
+            [a[0] - nx, a[1] - ny],
+        ]);
+    }
+
+    /// Stroke an elliptical arc from angle `a0` to `a1` (radians, y down).
+    fn arc(&mut self, center: [f32; 2], radius: [f32; 2], a0: f32, a1: f32, t: f32) {
+        let steps = arc_steps(radius[0].max(radius[1]));
+        let point = |a: f32, grow: f32| {
+            [
+                center[0] + (radius[0] + grow) * a.cos(),
+                center[1] + (radius[1] + grow) * a.sin(),
+            ]
+        };
+        for i in 0..steps {
+            let s0 = a0 + (a1 - a0) * i as f32 / steps as f32;
+            let s1 = a0 + (a1 - a0) * (i + 1) as f32 / steps as f32;
+            self.polygon(&[
+                point(s0, -t / 2.0),
+                point(s0, t / 2.0),
+                point(s1, t / 2.0),
+                point(s1, -t / 2.0),
+            ]);
+        }
+    }
+
+    /// Fill an elliptical sector (pie slice) from `a0` to `a1`.
+    fn sector(&mut self, center: [f32; 2], radius: [f32; 2], a0: f32, a1: f32) {
+        let steps = arc_steps(radius[0].max(radius[1]));
+        let point = |a: f32| [center[0] + radius[0] * a.cos(), center[1] + radius[1] * a.sin()];
+        for i in 0..steps {
+            let s0 = a0 + (a1 - a0) * i as f32 / steps as f32;
+            let s1 = a0 + (a1 - a0) * (i + 1) as f32 / steps as f32;
+            self.polygon(&[center, point(s0), point(s1)]);
+        }
+    }
+
+    // --- Box drawing (U+2500..U+257F) ---
+
+    fn box_drawing(&mut self, u: u32) {
+        let (l, h) = (LIGHT, HEAVY);
+        match u {
+            // Dashed lines: (segments, weight, horizontal)
+            0x2504 => self.dashes(3, l, true),
+            0x2505 => self.dashes(3, h, true),
+            0x2506 => self.dashes(3, l, false),
+            0x2507 => self.dashes(3, h, false),
+            0x2508 => self.dashes(4, l, true),
+            0x2509 => self.dashes(4, h, true),
+            0x250A => self.dashes(4, l, false),
+            0x250B => self.dashes(4, h, false),
+            0x254C => self.dashes(2, l, true),
+            0x254D => self.dashes(2, h, true),
+            0x254E => self.dashes(2, l, false),
+            0x254F => self.dashes(2, h, false),
+            0x256D..=0x2570 => self.rounded_corner(u),
+            0x2571 => self.diagonal(false),
+            0x2572 => self.diagonal(true),
+            0x2573 => {
+                self.diagonal(false);
+                self.diagonal(true);
+            }
+            _ => self.lines(box_arms(u)),
+        }
+    }
+
+    fn dashes(&mut self, segments: u32, weight: u8, horizontal: bool) {
+        let t = self.thickness(weight);
+        let (mx, my) = (self.mid_x(), self.mid_y());
+        let span = if horizontal { self.width() } else { self.height() };
+        let seg = span / segments as f32;
+        let gap = (seg * 0.4).round().max(1.0);
+        for i in 0..segments {
+            let start = (i as f32 * seg + gap / 2.0).round();
+            let end = ((i + 1) as f32 * seg - gap / 2.0).round();
+            if horizontal {
+                self.rect(self.x0 + start, my - t / 2.0, self.x0 + end, my + t / 2.0);
+            } else {
+                self.rect(mx - t / 2.0, self.y0 + start, mx + t / 2.0, self.y0 + end);
+            }
+        }
+    }
+
+    fn diagonal(&mut self, falling: bool) {
+        let t = self.light();
+        let (top, bottom) = if falling {
+            ([self.x0, self.y0], [self.x1, self.y1])
+        } else {
+            ([self.x1, self.y0], [self.x0, self.y1])
+        };
+        self.line(top, bottom, t);
+    }
+
+    /// ╭ ╮ ╯ ╰: a quarter circle joining the two arm midpoints.
+    fn rounded_corner(&mut self, u: u32) {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        let t = self.light();
+        let (mx, my) = (self.mid_x() + t / 2.0, self.mid_y() + t / 2.0);
+        let r = ((self.width().min(self.height()) - t) / 2.0).floor().max(1.0);
+        // (horizontal arm goes right, vertical arm goes down)
+        let (right, down) = match u {
+            0x256D => (true, true),
+            0x256E => (false, true),
+            0x256F => (false, false),
+            _ => (true, false),
+        };
+        let cx = if right { mx + r } else { mx - r };
+        let cy = if down { my + r } else { my - r };
+        if right {
+            self.rect(cx, my - t / 2.0, self.x1, my + t / 2.0);
+        } else {
+            self.rect(self.x0, my - t / 2.0, cx, my + t / 2.0);
+        }
+        if down {
+            self.rect(mx - t / 2.0, cy, mx + t / 2.0, self.y1);
+        } else {
+            self.rect(mx - t / 2.0, self.y0, mx + t / 2.0, cy);
+        }
+        // Angle pointing from the arc center back at the cell center.
+        let start = match (right, down) {
+            (true, true) => PI,
+            (false, true) => -FRAC_PI_2,
+            (false, false) => 0.0,
+            (true, false) => FRAC_PI_2,
+        };
+        self.arc([cx, cy], [r, r], start, start + FRAC_PI_2, t);
+    }
+
+    /// Straight light/heavy/double arms meeting in the cell center.
+    fn lines(&mut self, a: Arms) {
+        let light = self.light();
+        let gap = light;
+        let (mx, my) = (self.mid_x() + light / 2.0, self.mid_y() + light / 2.0);
+        let thickness = |w: u8| if w == HEAVY { light * 2.0 } else { light };
+
+        // How far past the center an arm reaches, given the arms
+        // perpendicular to it (`side_a`/`side_b`) and the arm opposite it.
+        // `offset` is the line's offset from center for double arms.
+        let reach = |offset: f32, side_a: u8, side_b: u8, opposite: u8| -> f32 {
+            let perp_double = side_a == DOUBLE || side_b == DOUBLE;
+            if offset != 0.0 {
+                // One line of a double arm: the line nearer a double
+                // perpendicular arm stops at it, the farther one wraps around.
+                if !perp_double {
+                    let side = side_a.max(side_b);
+                    return if side == NONE { 0.0 } else { thickness(side) / 2.0 };
+                }
+                let near_a = offset < 0.0;
+                let towards_double = if near_a { side_a == DOUBLE } else { side_b == DOUBLE };
+                return if towards_double { -gap + light / 2.0 } else { gap + light / 2.0 };
+            }
+            if perp_double {
+                let through = side_a == DOUBLE && side_b == DOUBLE;
+                if opposite != NONE {
+                    light / 2.0
+                } else if through {
+                    -gap + light / 2.0
+                } else {
+                    gap + light / 2.0
+                }
+            } else if side_a == NONE && side_b == NONE {
+                0.0
+            } else {
+                thickness(side_a.max(side_b)) / 2.0
+            }
+        };
+
+        let offsets = |w: u8| -> &'static [f32] {
+            match w {
+                NONE => &[],
+                DOUBLE => &[-1.0, 1.0],
+                _ => &[0.0],
+            }
+        };
+
+        for &o in offsets(a.left) {
+            let t = thickness(a.left);
+            let y = my + o * gap;
+            let end = mx + reach(o, a.up, a.down, a.right);
+            self.rect(self.x0, y - t / 2.0, end, y + t / 2.0);
+        }
+        for &o in offsets(a.right) {
+            let t = thickness(a.right);
+            let y = my + o * gap;
+            let start = mx - reach(o, a.up, a.down, a.left);
+            self.rect(start, y - t / 2.0, self.x1, y + t / 2.0);
+        }
+        for &o in offsets(a.up) {
+            let t = thickness(a.up);
+            let x = mx + o * gap;
+            let end = my + reach(o, a.left, a.right, a.down);
+            self.rect(x - t / 2.0, self.y0, x + t / 2.0, end);
+        }
+        for &o in offsets(a.down) {
+            let t = thickness(a.down);
+            let x = mx + o * gap;
+            let start = my - reach(o, a.left, a.right, a.up);
+            self.rect(x - t / 2.0, start, x + t / 2.0, self.y1);
+        }
+    }
+
+    // --- Block elements (U+2580..U+259F) ---
+
+    fn block_element(&mut self, u: u32) {
+        let (x0, y0, x1, y1) = (self.x0, self.y0, self.x1, self.y1);
+        let (w, h) = (self.width(), self.height());
+        let (mx, my) = (x0 + w / 2.0, y0 + h / 2.0);
+        match u {
+            0x2580 => self.rect(x0, y0, x1, my),
+            // Lower N/8 blocks: U+2581 (1/8) .. U+2588 (full)
+            0x2581..=0x2588 => {
+                let n = (u - 0x2580) as f32 / 8.0;
+                self.rect(x0, y1 - h * n, x1, y1);
+            }
+            // Left N/8 blocks: U+2589 (7/8) .. U+258F (1/8)
+            0x2589..=0x258F => {
+                let n = (0x2590 - u) as f32 / 8.0;
+                self.rect(x0, y0, x0 + w * n, y1);
+            }
+            0x2590 => self.rect(mx, y0, x1, y1),
+            // Light / medium / dark shade
+            0x2591..=0x2593 => {
+                let alpha = (u - 0x2590) as f32 / 4.0;
+                self.rect_alpha(x0, y0, x1, y1, alpha);
+            }
+            0x2594 => self.rect(x0, y0, x1, y0 + h / 8.0),
+            0x2595 => self.rect(x1 - w / 8.0, y0, x1, y1),
+            // Quadrants: U+2596..U+259F — bitmask: TL=1, TR=2, BL=4, BR=8
+            _ => {
+                const QUAD_BITS: [u8; 10] = [4, 8, 1, 13, 9, 7, 11, 2, 6, 14];
+                let bits = QUAD_BITS[(u - 0x2596) as usize];
+                if bits & 1 != 0 {
+                    self.rect(x0, y0, mx, my);
+                }
+                if bits & 2 != 0 {
+                    self.rect(mx, y0, x1, my);
+                }
+                if bits & 4 != 0 {
+                    self.rect(x0, my, mx, y1);
+                }
+                if bits & 8 != 0 {
+                    self.rect(mx, my, x1, y1);
+                }
+            }
+        }
+    }
+
+    // --- Braille (U+2800..U+28FF) ---
+
+    fn braille(&mut self, bits: u8) {
+        // Dot bit → (column, row); dots 7 and 8 were added below the
+        // original six-dot cell, hence the out-of-order bits.
+        const DOTS: [(u8, u8); 8] = [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (0, 3), (1, 3)];
+        let col_w = self.width() / 2.0;
+        let row_h = self.height() / 4.0;
+        let dot = (col_w.min(row_h) * 0.5).round().max(1.0);
+        for (i, &(col, row)) in DOTS.iter().enumerate() {
+            if bits & (1 << i) == 0 {
+                continue;
+            }
+            let cx = (self.x0 + col_w * (col as f32 + 0.5) - dot / 2.0).round();
+            let cy = (self.y0 + row_h * (row as f32 + 0.5) - dot / 2.0).round();
+            self.rect(cx, cy, cx + dot, cy + dot);
+        }
+    }
+
+    // --- Sextants (U+1FB00..U+1FB3B) ---
+
+    fn sextant(&mut self, bits: u8) {
+        let xs = [self.x0, (self.x0 + self.width() / 2.0).round(), self.x1];
+        let ys = [
+            self.y0,
+            (self.y0 + self.height() / 3.0).round(),
+            (self.y0 + self.height() * 2.0 / 3.0).round(),
+            self.y1,
+        ];
+        for i in 0..6 {
+            if bits & (1 << i) != 0 {
+                let (col, row) = (i % 2, i / 2);
+                self.rect(xs[col], ys[row], xs[col + 1], ys[row + 1]);
+            }
+        }
+    }
+
+    // --- Powerline (U+E0B0..U+E0BF) ---
+
+    fn powerline(&mut self, u: u32) {
+        use std::f32::consts::FRAC_PI_2;
+        let (x0, y0, x1, y1) = (self.x0, self.y0, self.x1, self.y1);
+        let my = y0 + self.height() / 2.0;
+        let t = self.light();
+        let radius = [self.width(), self.height() / 2.0];
+        match u {
+            0xE0B0 => self.polygon(&[[x0, y0], [x1, my], [x0, y1]]),
+            0xE0B1 => {
+                self.line([x0, y0], [x1, my], t);
+                self.line([x1, my], [x0, y1], t);
+            }
+            0xE0B2 => self.polygon(&[[x1, y0], [x1, y1], [x0, my]]),
+            0xE0B3 => {
+                self.line([x1, y0], [x0, my], t);
+                self.line([x0, my], [x1, y1], t);
+            }
+            0xE0B4 => self.sector([x0, my], radius, -FRAC_PI_2, FRAC_PI_2),
+            0xE0B5 => {
+                let r = [radius[0] - t / 2.0, radius[1] - t / 2.0];
+                self.arc([x0, my], r, -FRAC_PI_2, FRAC_PI_2, t);
+            }
+            0xE0B6 => self.sector([x1, my], radius, FRAC_PI_2, 3.0 * FRAC_PI_2),
+            0xE0B7 => {
+                let r = [radius[0] - t / 2.0, radius[1] - t / 2.0];
+                self.arc([x1, my], r, FRAC_PI_2, 3.0 * FRAC_PI_2, t);
+            }
+            0xE0B8 => self.polygon(&[[x0, y0], [x1, y1], [x0, y1]]),
+            0xE0BA => self.polygon(&[[x1, y0], [x1, y1], [x0, y1]]),
+            0xE0BC => self.polygon(&[[x0, y0], [x1, y0], [x0, y1]]),
+            0xE0BE => self.polygon(&[[x0, y0], [x1, y0], [x1, y1]]),
+            0xE0B9 | 0xE0BF => self.line([x0, y0], [x1, y1], t),
+            _ => self.line([x0, y1], [x1, y0], t), // E0BB, E0BD
+        }
+    }
+}
+
+/// Segments for a curve of the given radius (physical px): enough that each
+/// chord stays under ~2px, capped to keep vertex counts bounded.
+fn arc_steps(radius: f32) -> u32 {
+    ((radius * std::f32::consts::FRAC_PI_2 / 2.0).ceil() as u32).clamp(4, 32)
+}
+
+/// Clip a convex polygon to the horizontal band `top..bottom`
+/// (Sutherland–Hodgman against two edges).
+fn clip_polygon_y(points: &[[f32; 2]], top: f32, bottom: f32) -> Vec<[f32; 2]> {
+    let clip = |input: Vec<[f32; 2]>, inside: &dyn Fn(f32) -> bool, edge: f32| {
+        let mut out = Vec::with_capacity(input.len() + 2);
+        for i in 0..input.len() {
+            let cur = input[i];
+            let prev = input[(i + input.len() - 1) % input.len()];
+            let cross = |a: [f32; 2], b: [f32; 2]| {
+                let t = (edge - a[1]) / (b[1] - a[1]);
+                [a[0] + (b[0] - a[0]) * t, edge]
+            };
+            match (inside(prev[1]), inside(cur[1])) {
+                (true, true) => out.push(cur),
+                (true, false) => out.push(cross(prev, cur)),
+                (false, true) => {
+                    out.push(cross(prev, cur));
+                    out.push(cur);
+                }
+                (false, false) => {}
+            }
+        }
+        out
+    };
+    let pts = clip(points.to_vec(), &|y| y >= top, top);
+    if pts.is_empty() {
+        return pts;
+    }
+    clip(pts, &|y| y <= bottom, bottom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell() -> Rect {
+        Rect { x: 0.0, y: 0.0, width: 16.0, height: 32.0 }
+    }
+
+    #[test]
+    fn sextant_mapping_skips_half_blocks() {
+        assert_eq!(sextant_bits(0), 0b000001);
+        assert_eq!(sextant_bits(19), 0b010100);
+        assert_eq!(sextant_bits(20), 0b010110);
+        assert_eq!(sextant_bits(0x3B), 0b111110);
+    }
+
+    #[test]
+    fn box_table_matches_names() {
+        assert_eq!(box_arms(0x250C), arms(NONE, LIGHT, NONE, LIGHT)); // ┌
+        assert_eq!(box_arms(0x2557), arms(DOUBLE, NONE, NONE, DOUBLE)); // ╗
+        assert_eq!(box_arms(0x254B), arms(HEAVY, HEAVY, HEAVY, HEAVY)); // ╋
+        assert_eq!(box_arms(0x257F), arms(NONE, NONE, HEAVY, LIGHT)); // ╿
+    }
+
+    #[test]
+    fn horizontal_line_spans_cell() {
+        let mut ctx = DrawContext::new();
+        assert!(draw(&mut ctx, '─', cell(), [1.0; 4], 0.0, 32.0));
+        let x_min = ctx.flat_quads.iter().map(|q| q.rect.x).fold(f32::MAX, f32::min);
+        let x_max = ctx
+            .flat_quads
+            .iter()
+            .map(|q| q.rect.x + q.rect.width)
+            .fold(f32::MIN, f32::max);
+        assert_eq!((x_min, x_max), (0.0, 16.0));
+    }
+
+    #[test]
+    fn powerline_triangle_is_clipped() {
+        let mut ctx = DrawContext::new();
+        assert!(draw(&mut ctx, '\u{E0B0}', cell(), [1.0; 4], 8.0, 32.0));
+        assert!(!ctx.flat_triangles.is_empty());
+        for tri in &ctx.flat_triangles {
+            assert!(tri.points.iter().all(|p| p[1] >= 8.0));
+        }
+    }
+
+    #[test]
+    fn regular_text_is_not_handled() {
+        let mut ctx = DrawContext::new();
+        assert!(!draw(&mut ctx, 'a', cell(), [1.0; 4], 0.0, 32.0));
+        assert!(ctx.flat_quads.is_empty());
+    }
+}
@@ -0,0 +1,367 @@
+//! Command-line arguments: what the first tab runs and how it looks, or a
+//! remote-control command for an instance that is already running.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ipc::Request;
+
+pub const USAGE: &str = "\
+Usage:
+  pfauterminal [OPTIONS] [-e COMMAND [ARGS...]]
+  pfauterminal [OPTIONS] ssh [USER@]HOST[:PORT]
+  pfauterminal [OPTIONS] ssh://[USER@]HOST[:PORT]
+  pfauterminal msg [--socket PATH] COMMAND [ARGS...]
+
+Options:
+  -e, --command COMMAND [ARGS...]  Run COMMAND instead of the shell (ends the options)
+      --working-directory DIR      Start in DIR
+      --title TITLE                Fix the tab title
+      --profile NAME               Open the tab from a config profile
+      --font-size SIZE             Font size in points
+  -h, --help                       Show this help
+  -V, --version                    Show the version
+
+Remote control (msg) commands, sent to the running instance:
+  ls                               List tabs: id and title, * marks the active one
+  open [OPTIONS] [ssh TARGET]      Open a tab as above; prints its id
+  focus ID                         Activate a tab
+  send-text [--tab ID] TEXT...     Type TEXT into a tab (the active one by default)
+  get-text [--tab ID] [--ansi]     Print a tab's scrollback and screen
+  set-title [--tab ID] [TITLE]     Fix a tab's title; without TITLE, reset it
+  close [--tab ID]                 Close a tab
+";
+
+#[derive(Debug)]
+pub enum Cli {
+    Run(Options),
+    Help,
+    Version,
+    /// Send a request to a running instance, over `socket` or the one found
+    /// by [`crate::ipc::send`].
+    Msg {
+        socket: Option<PathBuf>,
+        request: Request,
+    },
+}
+
+/// How to open the first tab. All fields empty opens the default tab.
+/// Also the body of a remote `open` request.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Options {
+    /// Program and its arguments, run instead of the shell.
+    pub command: Option<Vec<String>>,
+    pub working_directory: Option<PathBuf>,
+    pub title: Option<String>,
+    pub profile: Option<String>,
+    pub font_size: Option<f32>,
+    pub ssh: Option<SshTarget>,
+}
+
+/// An SSH destination from the command line. `host` may be an alias from
+/// `~/.ssh/config`, which supplies whatever isn't given here. Serialized as
+/// the `[user@]host[:port]` string.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SshTarget {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl SshTarget {
+    /// Parse `[user@]host[:port]` or an `ssh://` URI. IPv6 hosts go in
+    /// brackets (`[::1]:2222`).
+    pub fn parse(target: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid SSH target \"{target}\"");
+        let rest = match target.strip_prefix("ssh://") {
+            Some(uri) => uri.trim_end_matches('/'),
+            None => target,
+        };
+        let (user, rest) = match rest.rsplit_once('@') {
+            // URI user info may carry parameters (`user;fingerprint=...`).
+            Some((user, rest)) => (Some(user.split(';').next().unwrap_or(user)), rest),
+            None => (None, rest),
+        };
+        let (host, port) = match rest.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, after) = bracketed.split_once(']').ok_or_else(invalid)?;
+                match after {
+                    "" => (host, None),
+                    _ => (host, Some(after.strip_prefix(':').ok_or_else(invalid)?)),
+                }
+            }
+            None => match rest.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (rest, None),
+            },
+        };
+        if host.is_empty() || user.is_some_and(str::is_empty) {
+            return Err(invalid());
+        }
+        let port = match port {
+            Some(port) => Some(port.parse().map_err(|_| format!("invalid port \"{port}\""))?),
+            None => None,
+        };
+        Ok(Self {
+            user: user.map(str::to_string),
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+impl Options {
+    /// Reject what [`parse`] would: an empty command, a bad font size, or ssh
+    /// with a command, profile or working directory. Remote `open` requests
+    /// are checked too.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.command.as_ref().is_some_and(Vec::is_empty) {
+            return Err("--command needs a command".into());
+        }
+        if let Some(size) = self.font_size
+            && (!size.is_finite() || size <= 0.0)
+        {
+            return Err(format!("invalid font size \"{size}\""));
+        }
+        if self.ssh.is_some() && (self.command.is_some() || self.profile.is_some()) {
+            return Err("ssh can't be combined with --command or --profile".into());
+        }
+        if self.ssh.is_some() && self.working_directory.is_some() {
+            return Err("ssh can't be combined with --working-directory".into());
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for SshTarget {
+    type Error = String;
+
+    fn try_from(target: String) -> Result<Self, String> {
+        Self::parse(&target)
+    }
+}
+
+impl From<SshTarget> for String {
+    fn from(target: SshTarget) -> String {
+        let mut out = String::new();
+        if let Some(user) = &target.user {
+            out.push_str(user);
+            out.push('@');
+        }
+        match target.host.contains(':') {
+            true => out.push_str(&format!("[{}]", target.host)),
+            false => out.push_str(&target.host),
+        }
+        if let Some(port) = target.port {
+            out.push_str(&format!(":{port}"));
+        }
+        out
+    }
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+    let mut args = args.into_iter().peekable();
+    if args.peek().is_some_and(|a| a == "msg") {
+        args.next();
+        return parse_msg(args);
+    }
+    parse_run(args)
+}
+
+fn parse_run(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // `--name=value` and `--name value` are both accepted.
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{name} needs a value"))
+        };
+        match name {
+            "-h" | "--help" => return Ok(Cli::Help),
+            "-V" | "--version" => return Ok(Cli::Version),
+            "--working-directory" => {
+                let dir = PathBuf::from(value()?);
+                options.working_directory = Some(std::path::absolute(&dir).unwrap_or(dir));
+            }
+            "--title" => options.title = Some(value()?),
+            "--profile" => options.profile = Some(value()?),
+            "--font-size" => {
+                let size = value()?;
+                options.font_size = Some(
+                    size.parse()
+                        .ok()
+                        .filter(|s: &f32| *s > 0.0)
+                        .ok_or_else(|| format!("invalid font size \"{size}\""))?,
+                );
+            }
+            "-e" | "--command" => {
+                let command: Vec<String> = inline.into_iter().chain(args.by_ref()).collect();
+                if command.is_empty() {
+                    return Err(format!("{name} needs a command"));
+                }
+                options.command = Some(command);
+            }
+            "ssh" => {
+                let target = args.next().ok_or("ssh needs a destination")?;
+                options.ssh = Some(SshTarget::parse(&target)?);
+            }
+            uri if uri.starts_with("ssh://") => options.ssh = Some(SshTarget::parse(uri)?),
+            // macOS adds a process serial number when launched from Finder.
+            psn if psn.starts_with("-psn_") => {}
+            other => return Err(format!("unexpected argument \"{other}\"")),
+        }
+    }
+    options.validate()?;
+    Ok(Cli::Run(options))
+}
+
+/// `msg [--socket PATH] COMMAND [ARGS...]`.
+fn parse_msg(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+    let mut args: Vec<String> = args.into_iter().collect();
+    let mut socket = None;
+    if let Some(arg) = args.first().cloned() {
+        if let Some(path) = arg.strip_prefix("--socket=") {
+            socket = Some(PathBuf::from(path));
+            args.remove(0);
+        } else if arg == "--socket" {
+            args.remove(0);
+            if args.is_empty() {
+                return Err("--socket needs a value".into());
+            }
+            socket = Some(PathBuf::from(args.remove(0)));
+        }
+    }
+    if args.is_empty() {
+        return Err("msg needs a command".into());
+    }
+    let command = args.remove(0);
+    if command == "open" {
+        return match parse_run(args)? {
+            Cli::Run(options) => Ok(Cli::Msg { socket, request: Request::Open(options) }),
+            other => Ok(other),
+        };
+    }
+
+    // The remaining commands take `--tab ID`, a few flags and free text.
+    let mut tab = None;
+    let mut ansi = false;
+    let mut text = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let id = match arg.strip_prefix("--tab=") {
+            Some(id) => Some(id.to_string()),
+            None if arg == "--tab" => Some(args.next().ok_or("--tab needs a value")?),
+            None => None,
+        };
+        match id {
+            Some(id) => tab = Some(id.parse().map_err(|_| format!("invalid tab id \"{id}\""))?),
+            None if arg == "--ansi" && command == "get-text" => ansi = true,
+            None if arg == "--" => text.extend(args.by_ref()),
+            None => text.push(arg),
+        }
+    }
+    let no_text = |request: Request| match text.is_empty() {
+        true => Ok(request),
+        false => Err(format!("unexpected argument \"{}\"", text[0])),
+    };
+    let request = match command.as_str() {
+        "ls" => no_text(Request::List)?,
+        "focus" => match (tab, text.as_slice()) {
+            (Some(tab), []) => Request::Focus { tab },
+            (None, [id]) => Request::Focus {
+                tab: id.parse().map_err(|_| format!("invalid tab id \"{id}\""))?,
+            },
+            _ => return Err("focus needs one tab id".into()),
+        },
+        "send-text" if text.is_empty() => return Err("send-text needs text".into()),
+        "send-text" => Request::SendText { tab, text: text.join(" ") },
+        "get-text" => no_text(Request::GetText { tab, ansi })?,
+        "set-title" => Request::SetTitle {
+            tab,
+            title: (!text.is_empty()).then(|| text.join(" ")),
+        },
+        "close" => no_text(Request::Close { tab })?,
+        other => return Err(format!("unknown msg command \"{other}\"")),
+    };
+    Ok(Cli::Msg { socket, request })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<Options, String> {
+        match parse(args.iter().map(|s| s.to_string()))? {
+            Cli::Run(options) => Ok(options),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn options_and_command() {
+        let options =
+            run(&["--title=build", "--font-size", "15", "-e", "make", "-j", "--title"]).unwrap();
+        assert_eq!(options.title.as_deref(), Some("build"));
+        assert_eq!(options.font_size, Some(15.0));
+        assert_eq!(options.command, Some(vec!["make".into(), "-j".into(), "--title".into()]));
+        assert!(run(&["--profile"]).is_err());
+        assert!(run(&["--bogus"]).is_err());
+        assert!(run(&["-e"]).is_err());
+        assert_eq!(run(&["-psn_0_12345"]).unwrap(), Options::default());
+    }
+
+    #[test]
+    fn ssh_targets() {
+        let target = |s| SshTarget::parse(s).unwrap();
+        assert_eq!(
+            target("me@example.com:2222"),
+            SshTarget { user: Some("me".into()), host: "example.com".into(), port: Some(2222) }
+        );
+        assert_eq!(
+            target("ssh://me;fingerprint=abc@[::1]:22/"),
+            SshTarget { user: Some("me".into()), host: "::1".into(), port: Some(22) }
+        );
+        assert_eq!(target("devbox"), SshTarget { user: None, host: "devbox".into(), port: None });
+        assert!(SshTarget::parse("me@host:ssh").is_err());
+        assert!(SshTarget::parse("@host").is_err());
+
+        let options = run(&["ssh", "ssh://db"]).unwrap();
+        assert_eq!(options.ssh.unwrap().host, "db");
+        assert!(run(&["ssh", "db", "-e", "ls"]).is_err());
+        assert!(run(&["--working-directory", "/tmp", "ssh", "db"]).is_err());
+    }
+
+    #[test]
+    fn msg_commands() {
+        let msg = |args: &[&str]| match parse(args.iter().map(|s| s.to_string())) {
+            Ok(Cli::Msg { socket, request }) => Ok((socket, request)),
+            Ok(other) => panic!("{other:?}"),
+            Err(error) => Err(error),
+        };
+        let (socket, request) =
+            msg(&["msg", "--socket", "/tmp/s", "send-text", "ls", "-l"]).unwrap();
+        assert_eq!(socket, Some(PathBuf::from("/tmp/s")));
+        assert_eq!(request, Request::SendText { tab: None, text: "ls -l".into() });
+        assert_eq!(msg(&["msg", "focus", "4"]).unwrap().1, Request::Focus { tab: 4 });
+        assert_eq!(
+            msg(&["msg", "get-text", "--tab=2", "--ansi"]).unwrap().1,
+            Request::GetText { tab: Some(2), ansi: true }
+        );
+        let (_, request) = msg(&["msg", "open", "--title", "logs", "-e", "tail", "-f"]).unwrap();
+        let Request::Open(options) = request else { panic!() };
+        assert_eq!(options.command, Some(vec!["tail".into(), "-f".into()]));
+        assert!(msg(&["msg", "ls", "extra"]).is_err());
+        assert!(msg(&["msg", "focus"]).is_err());
+        assert!(msg(&["msg"]).is_err());
+    }
+}
@@ -1,6 +1,4 @@
 use std::fmt;
-use std::fs;
-use std::path::PathBuf;
 
 use alacritty_terminal::vte::ansi::{Color, NamedColor};
 use glyphon::Color as GlyphonColor;
@@ -25,6 +23,21 @@ impl HexColor {
         }
     }
 
+    /// Strict parse of `#RRGGBB`, `0xRRGGBB` or bare `RRGGBB[AA]`, as found in
+    /// imported theme files.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let hex = s
+            .strip_prefix('#')
+            .or_else(|| s.strip_prefix("0x"))
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s);
+        if !matches!(hex.len(), 6 | 8) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(Self::from_hex(hex))
+    }
+
     fn from_hex(hex: &str) -> Self {
         let hex = hex.strip_prefix('#').unwrap_or(hex);
         let bytes = hex.as_bytes();
@@ -43,6 +56,11 @@ impl HexColor {
         }
     }
 
+    /// Perceived brightness in 0.0..=1.0 (Rec. 709 weights on sRGB values).
+    pub fn luminance(self) -> f32 {
+        (0.2126 * self.r as f32 + 0.7152 * self.g as f32 + 0.0722 * self.b as f32) / 255.0
+    }
+
     /// Convert to linear f32 RGBA for GPU pipelines.
     pub fn to_linear_f32(self) -> [f32; 4] {
         rgba_u8_to_linear(self.r, self.g, self.b, self.a)
@@ -63,6 +81,10 @@ impl HexColor {
     pub fn to_glyphon(self) -> GlyphonColor {
         GlyphonColor::rgba(self.r, self.g, self.b, self.a)
     }
+
+    pub const fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
 }
 
 impl Serialize for HexColor {
@@ -195,15 +217,55 @@ impl Default for ColorScheme {
 }
 
 impl ColorScheme {
-    /// Load from `~/.pfauterminal/colors.json`, creating with defaults if missing.
-    pub fn load() -> Self {
-        let path = config_path();
-        if let Ok(data) = fs::read_to_string(&path) {
-            if let Ok(scheme) = serde_json::from_str::<ColorScheme>(&data) {
-                return scheme;
-            }
+    /// Built-in light scheme, used in light mode when no light theme is set.
+    pub fn light() -> Self {
+        Self {
+            background: HexColor::from_u32(0xFFFFFFFF),
+            chrome: HexColor::from_u32(0xF7F8FAFF),
+            foreground: HexColor::from_u32(0x1E1F22FF),
+            cursor: HexColor::from_u32(0x1E1F22FF),
+            black: HexColor::from_u32(0x000000FF),
+            red: HexColor::from_u32(0xCD3131FF),
+            green: HexColor::from_u32(0x00BC00FF),
+            yellow: HexColor::from_u32(0x949800FF),
+            blue: HexColor::from_u32(0x0451A5FF),
+            magenta: HexColor::from_u32(0xBC05BCFF),
+            cyan: HexColor::from_u32(0x0598BCFF),
+            white: HexColor::from_u32(0x555555FF),
+            bright_black: HexColor::from_u32(0x666666FF),
+            bright_red: HexColor::from_u32(0xCD3131FF),
+            bright_green: HexColor::from_u32(0x14CE14FF),
+            bright_yellow: HexColor::from_u32(0xB5BA00FF),
+            bright_blue: HexColor::from_u32(0x0451A5FF),
+            bright_magenta: HexColor::from_u32(0xBC05BCFF),
+            bright_cyan: HexColor::from_u32(0x0598BCFF),
+            bright_white: HexColor::from_u32(0xA5A5A5FF),
+            tab_active_fill: HexColor::from_u32(0xDFE9FCFF),
+            tab_active_stroke: HexColor::from_u32(0x8FB0F2FF),
+            tab_active_text: HexColor::from_u32(0x000000FF),
+            tab_hover_bg: HexColor::from_u32(0xDFE1E5FF),
+            tab_hover_stroke: HexColor::from_u32(0xC9CCD6FF),
+            tab_separator: HexColor::from_u32(0xDFE1E5FF),
+            selection: HexColor::from_u32(0xA6D2FFFF),
+            panel_stroke: HexColor::from_u32(0xD3D5DBFF),
+            dropdown_bg: HexColor::from_u32(0xFFFFFFFF),
+            dropdown_border: HexColor::from_u32(0xDFE1E5FF),
+            dropdown_shadow: HexColor::from_u32(0x00000030),
+            dropdown_item_hover: HexColor::from_u32(0x3574F0FF),
+            dropdown_text: HexColor::from_u32(0x1E1F22FF),
+            dropdown_text_active: HexColor::from_u32(0xFFFFFFFF),
+            field_border: HexColor::from_u32(0xC9CCD6FF),
+            field_focused: HexColor::from_u32(0x3574F0FF),
+            ok_bg: HexColor::from_u32(0x3574F0FF),
+            ok_hover_bg: HexColor::from_u32(0x4682FAFF),
+            text_dim: HexColor::from_u32(0x818594FF),
+            text_placeholder: HexColor::from_u32(0x6C707EFF),
         }
-        Self::default()
+    }
+
+    /// Whether this is a light scheme (dark text on a light background).
+    pub fn is_light(&self) -> bool {
+        self.background.luminance() > 0.5
     }
 
     fn named_to_rgb(&self, c: NamedColor) -> (u8, u8, u8) {
@@ -231,9 +293,12 @@ impl ColorScheme {
         (hc.r, hc.g, hc.b)
     }
 
-    fn color_to_rgb(&self, color: Color) -> (u8, u8, u8) {
+    /// Resolve an alacritty Color to sRGB components.
+    pub fn color_to_rgb(&self, color: Color) -> (u8, u8, u8) {
         match color {
             Color::Named(n) => self.named_to_rgb(n),
+            // The first 16 indexed colors are the scheme's ANSI palette.
+            Color::Indexed(idx) if idx < 16 => self.named_to_rgb(ansi_named(idx)),
             Color::Indexed(idx) => ansi_256(idx),
             Color::Spec(rgb) => (rgb.r, rgb.g, rgb.b),
         }
@@ -260,6 +325,52 @@ impl ColorScheme {
     }
 }
 
+/// Label color for a tab. Taken from the scheme's ANSI palette so it
+/// matches whichever theme is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TabColor {
+    Red,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Magenta,
+}
+
+impl TabColor {
+    pub const ALL: [TabColor; 6] = [
+        TabColor::Red,
+        TabColor::Yellow,
+        TabColor::Green,
+        TabColor::Cyan,
+        TabColor::Blue,
+        TabColor::Magenta,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TabColor::Red => "Red",
+            TabColor::Yellow => "Yellow",
+            TabColor::Green => "Green",
+            TabColor::Cyan => "Cyan",
+            TabColor::Blue => "Blue",
+            TabColor::Magenta => "Magenta",
+        }
+    }
+
+    pub fn resolve(self, scheme: &ColorScheme) -> HexColor {
+        match self {
+            TabColor::Red => scheme.red,
+            TabColor::Yellow => scheme.yellow,
+            TabColor::Green => scheme.green,
+            TabColor::Cyan => scheme.cyan,
+            TabColor::Blue => scheme.blue,
+            TabColor::Magenta => scheme.magenta,
+        }
+    }
+}
+
 /// Convert an sRGB component (0.0..1.0) to linear.
 fn srgb_to_linear(c: f32) -> f32 {
     if c <= 0.04045 {
@@ -279,11 +390,25 @@ pub fn rgba_u8_to_linear(r: u8, g: u8, b: u8, a: u8) -> [f32; 4] {
     ]
 }
 
-fn config_path() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("pfauterminal")
-        .join("colors.json")
+fn ansi_named(idx: u8) -> NamedColor {
+    match idx {
+        0 => NamedColor::Black,
+        1 => NamedColor::Red,
+        2 => NamedColor::Green,
+        3 => NamedColor::Yellow,
+        4 => NamedColor::Blue,
+        5 => NamedColor::Magenta,
+        6 => NamedColor::Cyan,
+        7 => NamedColor::White,
+        8 => NamedColor::BrightBlack,
+        9 => NamedColor::BrightRed,
+        10 => NamedColor::BrightGreen,
+        11 => NamedColor::BrightYellow,
+        12 => NamedColor::BrightBlue,
+        13 => NamedColor::BrightMagenta,
+        14 => NamedColor::BrightCyan,
+        _ => NamedColor::BrightWhite,
+    }
 }
 
 /// Standard 256-color palette (indices 0..=255).
@@ -0,0 +1,515 @@
+//! Command palette: a keyboard-first list of everything that can be run or
+//! opened — hotkey actions, shells, profiles, SSH hosts and open tabs —
+//! narrowed by fuzzy search as the user types.
+
+use std::collections::HashMap;
+
+use glyphon::{Buffer, FontSystem, Metrics, Shaping, TextArea};
+use winit::event::KeyEvent;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::draw::DrawContext;
+use crate::dropdown::MenuAction;
+use crate::font;
+use crate::hotkeys::HotkeyAction;
+use crate::layout::{Rect, TextSpec};
+use crate::saved_sessions::now_unix;
+use crate::theme::{DropdownTheme, PaletteTheme, Theme};
+use crate::widgets::TextField;
+
+#[derive(Debug, Clone)]
+pub enum PaletteAction {
+    Hotkey(HotkeyAction),
+    Menu(MenuAction),
+}
+
+pub struct PaletteItem {
+    pub label: String,
+    /// Shown dimmed on the right: the bound shortcut, or what kind of entry
+    /// this is.
+    pub detail: String,
+    pub action: PaletteAction,
+    /// Unix time the entry was last used, 0 if never. Among equally good
+    /// matches the most recent comes first.
+    pub last_used: u64,
+}
+
+impl PaletteItem {
+    pub fn new(label: &str, detail: &str, action: PaletteAction) -> Self {
+        Self {
+            label: label.to_string(),
+            detail: detail.to_string(),
+            action,
+            last_used: 0,
+        }
+    }
+}
+
+/// What a key press in the open palette asks of the app.
+pub enum PaletteKey {
+    /// Consumed by the palette (typing, moving the selection).
+    Handled,
+    /// The palette closed without running anything.
+    Closed,
+    /// The palette closed; run this.
+    Run(PaletteAction),
+}
+
+pub struct CommandPalette {
+    items: Vec<PaletteItem>,
+    /// Indices into `items` that match the query, best first.
+    matches: Vec<usize>,
+    /// Position in `matches` of the highlighted row.
+    selected: usize,
+    /// Position in `matches` of the first visible row.
+    first: usize,
+    /// Search field; `Some` while the palette is open.
+    field: Option<TextField>,
+    /// Label and detail buffers for each item, then the "no matches" line.
+    buffers: Vec<Buffer>,
+    /// Laid-out width of each item's detail, in logical pixels.
+    detail_widths: Vec<f32>,
+    /// When entries were last picked here, keyed by (detail, label), so
+    /// recency also ranks entries that don't track their own use.
+    used: HashMap<(String, String), u64>,
+    rect: Rect,
+    row_rects: Vec<Rect>,
+    scale: f32,
+    surface: (f32, f32),
+    dropdown: DropdownTheme,
+    palette: PaletteTheme,
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            matches: Vec::new(),
+            selected: 0,
+            first: 0,
+            field: None,
+            buffers: Vec::new(),
+            detail_widths: Vec::new(),
+            used: HashMap::new(),
+            rect: Rect::ZERO,
+            row_rects: Vec::new(),
+            scale: 1.0,
+            surface: (0.0, 0.0),
+            dropdown: DropdownTheme::default(),
+            palette: PaletteTheme::default(),
+        }
+    }
+
+    pub fn open(
+        &mut self,
+        mut items: Vec<PaletteItem>,
+        scale: f32,
+        surface_width: f32,
+        surface_height: f32,
+        font_system: &mut FontSystem,
+        theme: &Theme,
+    ) {
+        self.dropdown = theme.dropdown.clone();
+        self.palette = theme.palette.clone();
+        self.scale = scale;
+        self.surface = (surface_width, surface_height);
+
+        for item in &mut items {
+            let key = (item.detail.clone(), item.label.clone());
+            if let Some(&used) = self.used.get(&key) {
+                item.last_used = item.last_used.max(used);
+            }
+        }
+
+        let d = &self.dropdown;
+        let metrics = Metrics::new(d.font_size, d.font_size * font::LINE_HEIGHT);
+        let text_w = self.palette.width - 2.0 * (d.border_width + d.padding + d.item_padding_h);
+        let lines = items
+            .iter()
+            .flat_map(|item| [item.label.as_str(), item.detail.as_str()])
+            .chain(["No matching commands"]);
+        self.buffers.truncate(0);
+        for text in lines {
+            let mut buffer = Buffer::new(font_system, metrics);
+            buffer.set_size(font_system, Some(text_w), Some(metrics.line_height));
+            buffer.set_text(font_system, text, font::default_attrs(), Shaping::Advanced);
+            buffer.shape_until_scroll(font_system, false);
+            self.buffers.push(buffer);
+        }
+        self.detail_widths = (0..items.len())
+            .map(|i| {
+                let runs = self.buffers[2 * i + 1].layout_runs();
+                runs.fold(0.0f32, |w, run| w.max(run.line_w)).ceil()
+            })
+            .collect();
+
+        let mut field = TextField::new(
+            "Type a command, shell, host or tab",
+            false,
+            metrics,
+            font::measure_cell(font_system).width,
+            d.item_radius,
+            d.item_padding_h,
+            font_system,
+        );
+        field.set_focused(true);
+        self.field = Some(field);
+        self.items = items;
+        self.filter();
+    }
+
+    pub fn close(&mut self) {
+        self.field = None;
+        self.items.clear();
+        self.matches.clear();
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.field.is_some()
+    }
+
+    /// Handle a pressed key while the palette is open.
+    pub fn key(
+        &mut self,
+        event: &KeyEvent,
+        font_system: &mut FontSystem,
+        shift: bool,
+    ) -> PaletteKey {
+        let page = self.palette.max_rows.max(1) as isize;
+        match event.logical_key.as_ref() {
+            Key::Named(NamedKey::Escape) => {
+                self.close();
+                return PaletteKey::Closed;
+            }
+            Key::Named(NamedKey::Enter) => {
+                return match self.run(self.selected) {
+                    Some(action) => PaletteKey::Run(action),
+                    None => PaletteKey::Handled,
+                };
+            }
+            Key::Named(NamedKey::ArrowUp) => self.move_selection(-1),
+            Key::Named(NamedKey::ArrowDown) => self.move_selection(1),
+            Key::Named(NamedKey::PageUp) => self.move_selection(-page),
+            Key::Named(NamedKey::PageDown) => self.move_selection(page),
+            _ => {
+                let Some(field) = self.field.as_mut() else { return PaletteKey::Handled };
+                let before = field.value().to_string();
+                field.edit_key(event, font_system, shift);
+                if field.value() != before {
+                    self.filter();
+                }
+            }
+        }
+        PaletteKey::Handled
+    }
+
+    /// Insert pasted text into the search field.
+    pub fn insert_text(&mut self, text: &str, font_system: &mut FontSystem) {
+        let Some(field) = self.field.as_mut() else { return };
+        field.insert_text(&text.replace(['\n', '\r'], " "), font_system);
+        self.filter();
+    }
+
+    /// Show an IME composition in the search field.
+    pub fn set_preedit(&mut self, text: &str, cursor: Option<usize>, font_system: &mut FontSystem) {
+        if let Some(field) = self.field.as_mut() {
+            field.set_preedit(text, cursor, font_system);
+        }
+    }
+
+    /// The search field's caret, for placing the IME candidate window.
+    pub fn ime_area(&self) -> Option<Rect> {
+        self.field.as_ref().map(|f| f.cursor_rect(self.scale))
+    }
+
+    pub fn select_all(&mut self) {
+        if let Some(field) = self.field.as_mut() {
+            field.select_all();
+        }
+    }
+
+    /// Close the palette and return the action of the match at `pos`,
+    /// remembering it as just used.
+    pub fn run(&mut self, pos: usize) -> Option<PaletteAction> {
+        let item = self.items.get(*self.matches.get(pos)?)?;
+        self.used.insert((item.detail.clone(), item.label.clone()), now_unix());
+        let action = item.action.clone();
+        self.close();
+        Some(action)
+    }
+
+    /// Match under the pointer, as a position in the filtered list.
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<usize> {
+        let row = self.row_rects.iter().position(|r| r.contains(x, y))?;
+        Some(self.first + row).filter(|&pos| pos < self.matches.len())
+    }
+
+    pub fn is_outside(&self, x: f32, y: f32) -> bool {
+        self.is_open() && !self.rect.contains(x, y)
+    }
+
+    /// Highlight the match under the pointer. Returns true if it changed.
+    pub fn set_hover(&mut self, pos: Option<usize>) -> bool {
+        match pos {
+            Some(pos) if pos != self.selected => {
+                self.selected = pos;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Scroll the list by whole rows. Returns true if it moved.
+    pub fn scroll(&mut self, rows: isize) -> bool {
+        let max_first = self.matches.len().saturating_sub(self.palette.max_rows);
+        let first = self.first.saturating_add_signed(rows).min(max_first);
+        let changed = first != self.first;
+        self.first = first;
+        self.layout();
+        changed
+    }
+
+    /// Re-place the palette after the window changed size or scale.
+    pub fn resize(&mut self, scale: f32, surface_width: f32, surface_height: f32) {
+        self.scale = scale;
+        self.surface = (surface_width, surface_height);
+        self.layout();
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let len = self.matches.len();
+        if len == 0 {
+            return;
+        }
+        self.selected = if delta.unsigned_abs() == 1 {
+            // Single steps wrap around; page steps stop at the ends.
+            (self.selected as isize + delta).rem_euclid(len as isize) as usize
+        } else {
+            self.selected.saturating_add_signed(delta).min(len - 1)
+        };
+        let rows = self.palette.max_rows.max(1);
+        if self.selected < self.first {
+            self.first = self.selected;
+        } else if self.selected >= self.first + rows {
+            self.first = self.selected + 1 - rows;
+        }
+        self.layout();
+    }
+
+    /// Rank the items against the current query and reset the selection.
+    fn filter(&mut self) {
+        let query = self.field.as_ref().map(|f| f.value()).unwrap_or_default();
+        let mut scored: Vec<(i32, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                // The kind of entry (e.g. "ssh prod") also matches, ranked
+                // below a match in the label alone.
+                let score = fuzzy_score(query, &item.label).or_else(|| {
+                    let text = format!("{} {}", item.detail, item.label);
+                    fuzzy_score(query, &text).map(|s| s - 10)
+                })?;
+                Some((score, i))
+            })
+            .collect();
+        let items = &self.items;
+        scored.sort_by(|(sa, a), (sb, b)| {
+            sb.cmp(sa)
+                .then(items[*b].last_used.cmp(&items[*a].last_used))
+                .then(a.cmp(b))
+        });
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+        self.first = 0;
+        self.layout();
+    }
+
+    fn layout(&mut self) {
+        let s = self.scale;
+        let d = &self.dropdown;
+        let (surface_w, surface_h) = self.surface;
+        let inset = (d.border_width + d.padding) * s;
+        let item_h = d.item_height * s;
+        let field_h = self.palette.field_height * s;
+        let gap = self.palette.field_gap * s;
+        let rows = self.matches.len().clamp(1, self.palette.max_rows.max(1));
+
+        let width = (self.palette.width * s).min(surface_w - 2.0 * d.padding * s).max(0.0);
+        let height = 2.0 * inset + field_h + gap + rows as f32 * item_h;
+        self.rect = Rect {
+            x: ((surface_w - width) / 2.0).max(0.0),
+            y: (self.palette.top * s).min(surface_h - height).max(0.0),
+            width,
+            height,
+        };
+
+        let inner_x = self.rect.x + inset;
+        let inner_w = (width - 2.0 * inset).max(0.0);
+        if let Some(field) = self.field.as_mut() {
+            field.set_rect(Rect {
+                x: inner_x,
+                y: self.rect.y + inset,
+                width: inner_w,
+                height: field_h,
+            });
+        }
+        let rows_top = self.rect.y + inset + field_h + gap;
+        self.row_rects = (0..rows)
+            .map(|i| Rect {
+                x: inner_x,
+                y: rows_top + i as f32 * item_h,
+                width: inner_w,
+                height: item_h,
+            })
+            .collect();
+    }
+
+    pub fn draw<'a>(
+        &'a self,
+        ctx: &mut DrawContext,
+        text_specs: &mut Vec<TextSpec>,
+        text_areas: &mut Vec<TextArea<'a>>,
+        theme: &Theme,
+        scale: f32,
+    ) {
+        let Some(field) = &self.field else { return };
+        let t = &theme.dropdown;
+        let colors = &theme.colors;
+        let radius = t.corner_radius * scale;
+        let item_pad_h = t.item_padding_h * scale;
+        let gap = t.icon_gap * scale;
+        let line_h = t.font_size * font::LINE_HEIGHT * scale;
+
+        ctx.shadow(
+            Rect {
+                y: self.rect.y + t.shadow_offset_y * scale,
+                ..self.rect
+            },
+            colors.dropdown_shadow.to_linear_f32(),
+            radius,
+            t.shadow_spread * scale,
+        );
+        ctx.stroked_rect(
+            &self.rect,
+            colors.dropdown_border.to_linear_f32(),
+            colors.dropdown_bg.to_linear_f32(),
+            radius,
+            t.border_width * scale,
+        );
+        field.draw(ctx, text_areas, scale, colors);
+
+        if self.matches.is_empty() {
+            let rect = self.row_rects[0];
+            let left = rect.x + item_pad_h;
+            text_specs.push(TextSpec {
+                buffer_index: self.buffers.len() - 1,
+                left,
+                top: rect.y + (rect.height - line_h) / 2.0,
+                bounds: Rect { x: left, width: rect.width - 2.0 * item_pad_h, ..rect },
+                color: colors.text_dim.to_glyphon(),
+            });
+            return;
+        }
+
+        for (row, rect) in self.row_rects.iter().enumerate() {
+            let pos = self.first + row;
+            let Some(&idx) = self.matches.get(pos) else { break };
+            let selected = pos == self.selected;
+            if selected {
+                ctx.rounded_rect(
+                    *rect,
+                    colors.dropdown_item_hover.to_linear_f32(),
+                    t.item_radius * scale,
+                );
+            }
+
+            let top = rect.y + (rect.height - line_h) / 2.0;
+            let right = rect.x + rect.width - item_pad_h;
+            let detail_w = self.detail_widths[idx] * scale;
+            let detail_left = right - detail_w;
+            text_specs.push(TextSpec {
+                buffer_index: 2 * idx + 1,
+                left: detail_left,
+                top,
+                bounds: Rect { x: detail_left, width: detail_w + 1.0, ..*rect },
+                color: colors.text_dim.to_glyphon(),
+            });
+
+            let left = rect.x + item_pad_h;
+            let label_right = if detail_w > 0.0 { detail_left - gap } else { right };
+            text_specs.push(TextSpec {
+                buffer_index: 2 * idx,
+                left,
+                top,
+                bounds: Rect { x: left, width: (label_right - left).max(0.0), ..*rect },
+                color: if selected {
+                    colors.dropdown_text_active.to_glyphon()
+                } else {
+                    colors.dropdown_text.to_glyphon()
+                },
+            });
+        }
+    }
+
+    pub fn buffers(&self) -> &[Buffer] {
+        &self.buffers
+    }
+}
+
+/// Score `text` against `query`: every non-space query character must occur
+/// in order, ignoring case. Characters that start a word or continue the
+/// previous match score higher, skipped characters cost a little, and
+/// `None` means no match. An empty query matches everything equally.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+    let word_start = |i: usize| i == 0 || !text[i - 1].is_alphanumeric();
+    let mut score = 0;
+    let mut next = 0;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let q = q.to_lowercase().next().unwrap_or(q);
+        let candidates = || (next..text.len()).filter(|&i| text[i] == q);
+        // Continue the current run if possible, else jump to the next word
+        // that starts with the character, else take the first occurrence.
+        let found = if next > 0 && text.get(next) == Some(&q) {
+            next
+        } else {
+            candidates().find(|&i| word_start(i)).or_else(|| candidates().next())?
+        };
+        score += 1;
+        if word_start(found) {
+            score += 8;
+        }
+        if next > 0 && found == next {
+            score += 8;
+        }
+        score -= (found - next).min(3) as i32;
+        next = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_ranking() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert_eq!(fuzzy_score("xyz", "Next Tab"), None);
+        assert_eq!(fuzzy_score("tn", "Next Tab"), None);
+
+        // Word starts beat letters buried inside words.
+        let initials = fuzzy_score("nt", "Next Tab").unwrap();
+        let buried = fuzzy_score("nt", "Scroll Content").unwrap();
+        assert!(initials > buried, "{initials} <= {buried}");
+
+        // A contiguous prefix beats the same letters spread out.
+        let prefix = fuzzy_score("zoom", "Zoom In").unwrap();
+        let spread = fuzzy_score("zoom", "Zero Out of Memory").unwrap();
+        assert!(prefix > spread, "{prefix} <= {spread}");
+
+        assert!(fuzzy_score("PROD", "db-prod-1").is_some());
+        assert!(fuzzy_score("go 3", "Go to Tab 3").is_some());
+    }
+}
@@ -0,0 +1,473 @@
+//! The unified config file, `<config>/pfauterminal/config.json`.
+//!
+//! Everything user-editable lives here: general settings, the dark-mode
+//! color scheme, hotkey overrides, saved SSH sessions, layout overrides for
+//! the widget themes, and named launch profiles. Older releases kept these in
+//! separate files (`colors.json`, `hotkeys.json`, `sessions.json`,
+//! `settings.json`); those are folded in once on first load.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::colors::{ColorScheme, TabColor};
+use crate::font;
+use crate::hotkeys::HotkeyAction;
+use crate::saved_sessions::SavedSession;
+use crate::session_log::LogFormat;
+use crate::settings::{Scrollback, Settings};
+use crate::theme::{
+    DialogTheme, DropdownTheme, GeneralTheme, PaletteTheme, PanelTheme, TabBarTheme, ToastTheme,
+};
+use crate::themes;
+
+/// Schema version written to new files. Bump when the layout changes in a
+/// way that needs a migration step in [`Config::read`].
+pub const CONFIG_VERSION: u32 = 1;
+
+const FILE_NAME: &str = "config.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub version: u32,
+    pub settings: Settings,
+    /// Color scheme used in dark mode when no dark theme is selected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colors: Option<ColorScheme>,
+    /// Per-action overrides of the platform default bindings. An empty list
+    /// disables the hotkey.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub hotkeys: BTreeMap<HotkeyAction, Vec<String>>,
+    pub sessions: Vec<SavedSession>,
+    pub layout: LayoutConfig,
+    /// Profile used for new tabs; `None` starts the login shell.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    pub profiles: Vec<Profile>,
+    pub logging: LoggingConfig,
+    /// Color labels for SSH tabs by host name; the first match wins.
+    pub tab_colors: Vec<HostColor>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            settings: Settings::default(),
+            colors: None,
+            hotkeys: BTreeMap::new(),
+            sessions: Vec::new(),
+            layout: LayoutConfig::default(),
+            default_profile: None,
+            profiles: Vec::new(),
+            logging: LoggingConfig::default(),
+            tab_colors: vec![HostColor {
+                host: "*prod*".into(),
+                color: TabColor::Red,
+            }],
+        }
+    }
+}
+
+/// Overrides for the built-in widget metrics. Omitted sections keep the
+/// defaults; a present section may list only the fields it changes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub general: Option<GeneralTheme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tab_bar: Option<TabBarTheme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dropdown: Option<DropdownTheme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialog: Option<DialogTheme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub panel: Option<PanelTheme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toast: Option<ToastTheme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<PaletteTheme>,
+}
+
+/// Session logging: where logs go and which SSH hosts are always logged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Log directory; a leading `~` expands to the home directory. Defaults
+    /// to `logs` in the pfauterminal data directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    pub format: LogFormat,
+    /// Host names whose SSH sessions are logged from the start; `*` matches
+    /// any run of characters (`*.prod.example.com`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>,
+}
+
+impl LoggingConfig {
+    pub fn directory(&self) -> Option<PathBuf> {
+        match &self.dir {
+            Some(dir) => Some(expand_home(dir)),
+            None => dirs::data_dir().map(|d| d.join("pfauterminal").join("logs")),
+        }
+    }
+
+    pub fn logs_host(&self, host: &str) -> bool {
+        self.hosts.iter().any(|pattern| host_matches(pattern, host))
+    }
+}
+
+/// Tab color for SSH hosts matching a pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostColor {
+    /// Host name pattern; `*` matches any run of characters.
+    pub host: String,
+    pub color: TabColor,
+}
+
+/// Case-insensitive match of a host name against a pattern where `*`
+/// matches any run of characters.
+pub fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let host = host.to_ascii_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = host.strip_prefix(first) else { return false };
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            // Last segment anchors at the end (and always follows a `*`).
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    // No `*` at all: the whole host must have matched.
+    rest.is_empty()
+}
+
+/// A named way to start a tab: what to run, where, and how it looks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub name: String,
+    /// Program to run; `None` starts the default login shell.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Arguments for `command`; not allowed without it.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Working directory; a leading `~` expands to the home directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font: Option<FontConfig>,
+    /// Color scheme name (bundled or from the themes directory).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// Space around the terminal grid, in logical pixels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding: Option<f32>,
+    /// Overrides `settings.scrollback`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scrollback: Option<Scrollback>,
+    /// Log every tab opened from this profile.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub log: bool,
+    /// Tab color label.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<TabColor>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+    /// Base size in points; zooming scales it along with the window font.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<f32>,
+}
+
+impl Profile {
+    /// `cwd` with `~` expanded.
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.cwd.as_deref().map(expand_home)
+    }
+}
+
+/// Expand a leading `~` to the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(path),
+    }
+}
+
+impl Config {
+    /// Read and validate the config file. Errors carry the location of the
+    /// problem (`config.json:line:column` for syntax and type errors, a field
+    /// path such as `profiles[1].font.size` for invalid values).
+    pub fn load() -> Result<Self, String> {
+        let config = Self::read()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse the config file without validating values. Creates it on first
+    /// run, migrating the legacy per-topic files if present.
+    pub fn read() -> Result<Self, String> {
+        let Some(dir) = config_dir() else {
+            return Ok(Self::default());
+        };
+        let path = dir.join(FILE_NAME);
+        match std::fs::read_to_string(&path) {
+            Ok(data) => Self::parse(&data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let config = migrate_legacy(&dir);
+                config.save();
+                Ok(config)
+            }
+            Err(e) => Err(format!("{FILE_NAME}: {e}")),
+        }
+    }
+
+    fn parse(data: &str) -> Result<Self, String> {
+        let config: Self = serde_json::from_str(data).map_err(|e| {
+            let msg = e.to_string();
+            let msg = msg.rsplit_once(" at line ").map_or(msg.as_str(), |(m, _)| m);
+            format!("{FILE_NAME}:{}:{}: {msg}", e.line(), e.column())
+        })?;
+        if config.version > CONFIG_VERSION {
+            return Err(format!(
+                "{FILE_NAME}: version {} is newer than this build supports ({CONFIG_VERSION})",
+                config.version
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Check values serde can't: ranges, references between sections, and
+    /// theme names. All problems are reported, one per line.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+        let available = themes::available();
+        let check_theme = |path: String, name: &Option<String>, errors: &mut Vec<String>| {
+            if let Some(name) = name
+                && !available.contains(name)
+            {
+                errors.push(format!("{path}: unknown theme \"{name}\""));
+            }
+        };
+
+        check_theme("settings.dark_theme".into(), &self.settings.dark_theme, &mut errors);
+        check_theme("settings.light_theme".into(), &self.settings.light_theme, &mut errors);
+        if self.settings.scrollback_memory_mb == 0 {
+            errors.push("settings.scrollback_memory_mb: must be at least 1".into());
+        }
+
+        for (i, profile) in self.profiles.iter().enumerate() {
+            let at = if profile.name.is_empty() {
+                format!("profiles[{i}]")
+            } else {
+                format!("profiles[{i}] (\"{}\")", profile.name)
+            };
+            if profile.name.trim().is_empty() {
+                errors.push(format!("{at}.name: must not be empty"));
+            } else if self.profiles[..i].iter().any(|p| p.name == profile.name) {
+                errors.push(format!("{at}.name: duplicate profile name"));
+            }
+            if profile.command.as_deref().is_some_and(|c| c.trim().is_empty()) {
+                errors.push(format!("{at}.command: must not be empty"));
+            }
+            if profile.command.is_none() && !profile.args.is_empty() {
+                errors.push(format!("{at}.args: requires command"));
+            }
+            if let Some(size) = profile.font.as_ref().and_then(|f| f.size)
+                && !(font::MIN_FONT_SIZE..=font::MAX_FONT_SIZE).contains(&size)
+            {
+                errors.push(format!(
+                    "{at}.font.size: must be between {} and {}",
+                    font::MIN_FONT_SIZE,
+                    font::MAX_FONT_SIZE
+                ));
+            }
+            if let Some(padding) = profile.padding
+                && !(0.0..=100.0).contains(&padding)
+            {
+                errors.push(format!("{at}.padding: must be between 0 and 100"));
+            }
+            check_theme(format!("{at}.theme"), &profile.theme, &mut errors);
+        }
+
+        if let Some(name) = &self.default_profile
+            && !self.profiles.iter().any(|p| &p.name == name)
+        {
+            errors.push(format!("default_profile: no profile named \"{name}\""));
+        }
+
+        for (i, session) in self.sessions.iter().enumerate() {
+            check_theme(format!("sessions[{i}].theme"), &session.theme, &mut errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors
+                .into_iter()
+                .map(|e| format!("{FILE_NAME}: {e}"))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(dir) = config_dir() else { return };
+        let _ = std::fs::create_dir_all(&dir);
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = std::fs::write(dir.join(FILE_NAME), json);
+        }
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Scrollback for a tab opened from `profile` (`None` = no profile).
+    pub fn scrollback(&self, profile: Option<&str>) -> Scrollback {
+        profile
+            .and_then(|name| self.profile(name))
+            .and_then(|p| p.scrollback)
+            .unwrap_or(self.settings.scrollback)
+    }
+
+    /// Color label from the first `tab_colors` rule matching `host`.
+    pub fn host_color(&self, host: &str) -> Option<TabColor> {
+        self.tab_colors
+            .iter()
+            .find(|rule| host_matches(&rule.host, host))
+            .map(|rule| rule.color)
+    }
+}
+
+/// Read-modify-write one part of the config file. A file that doesn't parse
+/// is left alone rather than overwritten with defaults.
+pub fn update(f: impl FnOnce(&mut Config)) {
+    let Ok(mut config) = Config::read() else { return };
+    f(&mut config);
+    config.save();
+}
+
+fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("pfauterminal"))
+}
+
+/// Build a config from the pre-unification files. Each file that parses is
+/// folded in and renamed to `*.bak`; unreadable ones are left in place.
+fn migrate_legacy(dir: &std::path::Path) -> Config {
+    #[derive(Deserialize)]
+    struct LegacyHotkeys {
+        hotkeys: BTreeMap<HotkeyAction, Vec<String>>,
+    }
+    #[derive(Deserialize)]
+    struct LegacySessions {
+        sessions: Vec<SavedSession>,
+    }
+
+    fn take<T: serde::de::DeserializeOwned>(dir: &std::path::Path, name: &str) -> Option<T> {
+        let path = dir.join(name);
+        let value = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+        let _ = std::fs::rename(&path, path.with_extension("json.bak"));
+        Some(value)
+    }
+
+    let mut config = Config::default();
+    if let Some(colors) = take::<ColorScheme>(dir, "colors.json") {
+        config.colors = Some(colors);
+    }
+    if let Some(legacy) = take::<LegacyHotkeys>(dir, "hotkeys.json") {
+        config.hotkeys = legacy.hotkeys;
+    }
+    if let Some(legacy) = take::<LegacySessions>(dir, "sessions.json") {
+        config.sessions = legacy.sessions;
+    }
+    if let Some(settings) = take::<Settings>(dir, "settings.json") {
+        config.settings = settings;
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syntax_errors_report_line_and_column() {
+        let err = Config::parse("{\n  \"version\": 1,\n  \"bogus\": true\n}").unwrap_err();
+        assert!(err.starts_with("config.json:3:"), "{err}");
+        assert!(err.contains("bogus"), "{err}");
+    }
+
+    #[test]
+    fn validation_reports_field_paths() {
+        let config = Config::parse(
+            r#"{"default_profile": "missing",
+                "profiles": [{"name": "a", "font": {"size": 500}},
+                             {"name": "a", "args": ["-l"]}]}"#,
+        )
+        .unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("profiles[0] (\"a\").font.size"), "{err}");
+        assert!(err.contains("profiles[1] (\"a\").name: duplicate"), "{err}");
+        assert!(err.contains("profiles[1] (\"a\").args: requires command"), "{err}");
+        assert!(err.contains("default_profile"), "{err}");
+    }
+
+    #[test]
+    fn scrollback_accepts_lines_or_unlimited() {
+        let config = Config::parse(
+            r#"{"settings": {"scrollback": "unlimited"},
+                "profiles": [{"name": "a", "scrollback": 500}]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.scrollback(None), Scrollback::Unlimited);
+        assert_eq!(config.scrollback(Some("a")), Scrollback::Lines(500));
+        assert!(Config::parse(r#"{"settings": {"scrollback": "lots"}}"#).is_err());
+    }
+
+    #[test]
+    fn host_patterns_color_tabs() {
+        assert!(host_matches("*prod*", "db1.PROD.example.com"));
+        assert!(host_matches("*.example.com", "a.example.com"));
+        assert!(host_matches("web-*-1", "web-eu-1"));
+        assert!(!host_matches("web-*-1", "web-eu-2"));
+        assert!(!host_matches("db", "db2"));
+
+        let config = Config::parse("{}").unwrap();
+        assert_eq!(config.host_color("api.prod.internal"), Some(TabColor::Red));
+        assert_eq!(config.host_color("staging"), None);
+        let config = Config::parse(r#"{"tab_colors": [{"host": "stag*", "color": "yellow"}]}"#).unwrap();
+        assert_eq!(config.host_color("staging"), Some(TabColor::Yellow));
+        assert_eq!(config.host_color("prod"), None);
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        let err = Config::parse(r#"{"settings": {"ligatrues": false}}"#).unwrap_err();
+        assert!(err.contains("ligatrues"), "{err}");
+        assert!(Config::parse(r#"{"settings": {"theme": "Dracula"}}"#).is_ok());
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        assert!(Config::parse(r#"{"version": 99}"#).is_err());
+    }
+}
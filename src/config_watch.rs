@@ -0,0 +1,77 @@
+//! Watches the pfauterminal config directory and tells the event loop which
+//! config file changed, so edits apply without a restart.
+
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use winit::event_loop::EventLoopProxy;
+
+use crate::terminal_panel::TerminalEvent;
+
+/// Editors often save in several steps (truncate + write, or write to a temp
+/// file + rename). Wait for the burst to settle before reloading so a
+/// half-written file isn't reported as a parse error.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFile {
+    Config,
+    Themes,
+}
+
+impl ConfigFile {
+    fn from_path(path: &Path) -> Option<Self> {
+        if path
+            .parent()
+            .and_then(|p| p.file_name())
+            .is_some_and(|n| n == "themes")
+        {
+            return Some(ConfigFile::Themes);
+        }
+        match path.file_name()?.to_str()? {
+            "config.json" => Some(ConfigFile::Config),
+            "themes" => Some(ConfigFile::Themes),
+            _ => None,
+        }
+    }
+}
+
+/// Start watching the config directory. The returned watcher must be kept
+/// alive for as long as changes should be delivered.
+pub fn spawn(proxy: EventLoopProxy<TerminalEvent>) -> Option<RecommendedWatcher> {
+    let dir = dirs::config_dir()?.join("pfauterminal");
+    std::fs::create_dir_all(&dir).ok()?;
+
+    let (tx, rx) = mpsc::channel::<ConfigFile>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else { return };
+        if event.kind.is_access() {
+            return;
+        }
+        for file in event.paths.iter().filter_map(|p| ConfigFile::from_path(p)) {
+            let _ = tx.send(file);
+        }
+    })
+    .ok()?;
+    watcher.watch(&dir, RecursiveMode::Recursive).ok()?;
+
+    std::thread::spawn(move || {
+        while let Ok(first) = rx.recv() {
+            let mut changed = vec![first];
+            while let Ok(file) = rx.recv_timeout(DEBOUNCE) {
+                if !changed.contains(&file) {
+                    changed.push(file);
+                }
+            }
+            for file in changed {
+                if proxy.send_event(TerminalEvent::ConfigChanged(file)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Some(watcher)
+}
@@ -1,12 +1,18 @@
 use glyphon::{CustomGlyph, CustomGlyphId};
 
-use crate::layout::{BgQuad, CursorData, Rect, RoundedQuad, TextSpec};
+use crate::icons;
+use crate::layout::{BgQuad, CursorData, FlatTriangle, ImageQuad, Rect, RoundedQuad, TextSpec};
 
 pub struct DrawContext {
     pub rounded_quads: Vec<RoundedQuad>,
     pub flat_quads: Vec<BgQuad>,
+    pub flat_triangles: Vec<FlatTriangle>,
+    /// Inline images, layered around the flat quads and the text by z-index.
+    pub images: Vec<ImageQuad>,
     pub custom_glyphs: Vec<CustomGlyph>,
     pub cursor: Option<CursorData>,
+    /// Draw the light variants of icons (set per frame from the color scheme).
+    pub light_icons: bool,
 }
 
 impl Default for DrawContext {
@@ -20,14 +26,19 @@ impl DrawContext {
         Self {
             rounded_quads: Vec::new(),
             flat_quads: Vec::new(),
+            flat_triangles: Vec::new(),
+            images: Vec::new(),
             custom_glyphs: Vec::new(),
             cursor: None,
+            light_icons: false,
         }
     }
 
     pub fn clear(&mut self) {
         self.rounded_quads.clear();
         self.flat_quads.clear();
+        self.flat_triangles.clear();
+        self.images.clear();
         self.custom_glyphs.clear();
         self.cursor = None;
     }
@@ -76,9 +87,17 @@ impl DrawContext {
         self.flat_quads.push(BgQuad { rect, color });
     }
 
+    pub fn flat_triangle(&mut self, points: [[f32; 2]; 3], color: [f32; 4]) {
+        self.flat_triangles.push(FlatTriangle { points, color });
+    }
+
+    pub fn image(&mut self, quad: ImageQuad) {
+        self.images.push(quad);
+    }
+
     pub fn icon(&mut self, id: CustomGlyphId, left: f32, top: f32, size: f32) {
         self.custom_glyphs.push(CustomGlyph {
-            id,
+            id: icons::variant(id, self.light_icons),
             left,
             top,
             width: size,
@@ -1,21 +1,47 @@
 use glyphon::{Buffer, CustomGlyphId, FontSystem, Metrics, Shaping};
+use winit::event::KeyEvent;
+use winit::keyboard::{Key, NamedKey};
 
+use crate::colors::TabColor;
 use crate::draw::DrawContext;
 use crate::font;
 use crate::icons;
 use crate::layout::{update_if_changed, Rect, TextSpec};
+use crate::terminal_panel::PanelId;
 use crate::theme::{DropdownTheme, Theme};
 
 #[derive(Debug, Clone)]
 pub enum MenuAction {
     NewShell(String),
+    NewProfile(String),
     OpenSshDialog,
     ConnectSavedSession(String),
     ConnectSshConfigHost(String),
     Copy,
     Paste,
+    ClearScrollback,
+    ExportScrollback,
+    ReplayRecording,
+    ToggleLogging(PanelId),
+    ToggleBroadcast(PanelId),
+    SelectTab(PanelId),
+    RenameTab(PanelId),
+    ResetTabTitle(PanelId),
+    DuplicateTab(PanelId),
+    CloseOtherTabs(PanelId),
+    CloseTabsToRight(PanelId),
+    OpenTabColorPicker(PanelId),
+    SetTabColor(PanelId, Option<TabColor>),
+    OpenThemePicker,
+    SetTheme(Option<String>),
+    ImportTheme,
+    /// Files dropped on an SSH tab: send them to the remote host, or type
+    /// their local paths.
+    UploadDroppedFiles,
+    InsertDroppedPaths,
 }
 
+#[derive(Clone, Copy)]
 pub enum MenuPosition {
     BelowAnchor(Rect),
     AtPoint(f32, f32),
@@ -69,15 +95,39 @@ pub enum DropdownElement {
     CloseButton(usize),
 }
 
+/// What a key press in an open dropdown asks of its owner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DropdownKey {
+    /// Consumed by the menu (selection moved, filter changed) or ignored.
+    Handled,
+    Close,
+    /// Enter on an item.
+    Activate(usize),
+    /// Delete on a closeable item.
+    Remove(usize),
+}
+
 pub struct DropdownMenu {
     items: Vec<MenuItem>,
     item_buffers: Vec<Buffer>,
+    /// Per item; `Rect::ZERO` while the filter hides it.
     item_rects: Vec<Rect>,
     close_rects: Vec<Rect>,
     separator_rects: Vec<Rect>,
+    /// Items and separators in menu order; `Some(i)` is `items[i]`.
+    entries: Vec<Option<usize>>,
+    /// Type-to-filter text. While set, only items whose label contains it
+    /// are shown, without separators.
+    filter: String,
     menu_rect: Rect,
     hover: DropdownElement,
     visible: bool,
+    position: MenuPosition,
+    /// Menu width in logical pixels.
+    width: f32,
+    scale: f32,
+    surface: (f32, f32),
+    theme: DropdownTheme,
 }
 
 impl DropdownMenu {
@@ -88,9 +138,16 @@ impl DropdownMenu {
             item_rects: Vec::new(),
             close_rects: Vec::new(),
             separator_rects: Vec::new(),
+            entries: Vec::new(),
+            filter: String::new(),
             menu_rect: Rect::ZERO,
             hover: DropdownElement::None,
             visible: false,
+            position: MenuPosition::AtPoint(0.0, 0.0),
+            width: 0.0,
+            scale: 1.0,
+            surface: (0.0, 0.0),
+            theme: DropdownTheme::default(),
         }
     }
 
@@ -105,29 +162,99 @@ impl DropdownMenu {
         font_system: &mut FontSystem,
         theme: &DropdownTheme,
     ) {
-        let menu_w = width.unwrap_or(theme.width) * scale;
+        self.position = position;
+        self.width = width.unwrap_or(theme.width);
+        self.scale = scale;
+        self.surface = (surface_width, surface_height);
+        self.theme = theme.clone();
+        self.filter.clear();
+
+        let mut items = Vec::new();
+        self.entries = entries
+            .into_iter()
+            .map(|entry| match entry {
+                MenuEntry::Separator => None,
+                MenuEntry::Item(item) => {
+                    items.push(item);
+                    Some(items.len() - 1)
+                }
+            })
+            .collect();
+
+        // Create/reuse text buffers
+        let item_pad_h = theme.item_padding_h * scale;
+        let icon_size = theme.icon_size * scale;
+        let icon_gap = theme.icon_gap * scale;
+        let close_size = theme.close_size * scale;
+        let inner_w = (self.width - 2.0 * (theme.border_width + theme.padding)) * scale;
+        let metrics = Metrics::new(theme.font_size, theme.font_size * font::LINE_HEIGHT);
+        while self.item_buffers.len() < items.len() {
+            self.item_buffers.push(Buffer::new(font_system, metrics));
+        }
+        self.item_buffers.truncate(items.len());
+
+        for (i, item) in items.iter().enumerate() {
+            let buf = &mut self.item_buffers[i];
+            buf.set_metrics(font_system, metrics);
+
+            let mut text_w = inner_w - 2.0 * item_pad_h;
+            if item.icon.is_some() {
+                text_w -= icon_size + icon_gap;
+            }
+            if item.closeable {
+                text_w -= close_size + icon_gap;
+            }
+
+            let buf_width = text_w / scale;
+            buf.set_size(font_system, Some(buf_width), Some(metrics.line_height));
+            buf.set_text(
+                font_system,
+                &item.label,
+                font::default_attrs(),
+                Shaping::Advanced,
+            );
+            buf.shape_until_scroll(font_system, false);
+        }
+
+        self.items = items;
+        self.hover = DropdownElement::None;
+        self.visible = true;
+        self.layout();
+    }
+
+    /// Place the menu and its shown entries.
+    fn layout(&mut self) {
+        let theme = &self.theme;
+        let scale = self.scale;
+        let (surface_width, surface_height) = self.surface;
+        let menu_w = self.width * scale;
         let padding = theme.padding * scale;
         let item_h = theme.item_height * scale;
         let item_pad_h = theme.item_padding_h * scale;
         let border = theme.border_width * scale;
         let gap = theme.anchor_gap * scale;
         let sep_h = theme.separator_height * scale;
-        let icon_size = theme.icon_size * scale;
-        let icon_gap = theme.icon_gap * scale;
         let close_size = theme.close_size * scale;
 
-        // Calculate total content height
-        let mut content_h = 0.0;
-        for entry in &entries {
-            match entry {
-                MenuEntry::Item(_) => content_h += item_h,
-                MenuEntry::Separator => content_h += sep_h,
-            }
-        }
+        let shown: Vec<Option<usize>> = if self.filter.is_empty() {
+            self.entries.clone()
+        } else {
+            self.entries
+                .iter()
+                .flatten()
+                .filter(|&&i| self.matches_filter(i, &self.filter))
+                .map(|&i| Some(i))
+                .collect()
+        };
 
+        // Calculate total content height
+        let content_h: f32 = shown
+            .iter()
+            .map(|entry| if entry.is_some() { item_h } else { sep_h })
+            .sum();
         let menu_h = padding * 2.0 + content_h + border * 2.0;
 
-        let (mut menu_x, menu_y) = match position {
+        let (mut menu_x, menu_y) = match self.position {
             MenuPosition::BelowAnchor(anchor_rect) => {
                 let x = anchor_rect.x + (anchor_rect.width - menu_w) / 2.0;
                 let y = anchor_rect.y + anchor_rect.height + gap;
@@ -144,7 +271,7 @@ impl DropdownMenu {
         }
 
         let final_y = if menu_y + menu_h > surface_height {
-            match position {
+            match self.position {
                 MenuPosition::BelowAnchor(anchor_rect) => (anchor_rect.y - gap - menu_h).max(0.0),
                 MenuPosition::AtPoint(_, y) => (y - menu_h).max(0.0),
             }
@@ -163,16 +290,15 @@ impl DropdownMenu {
         let inner_y = final_y + border + padding;
         let inner_w = menu_w - 2.0 * (border + padding);
 
-        // Lay out entries in order
-        self.item_rects.clear();
-        self.close_rects.clear();
+        // Lay out shown entries in order; hidden items keep zero rects
+        self.item_rects = vec![Rect::ZERO; self.items.len()];
+        self.close_rects = vec![Rect::ZERO; self.items.len()];
         self.separator_rects.clear();
-        let mut items = Vec::new();
         let mut y = inner_y;
 
-        for entry in entries {
+        for entry in shown {
             match entry {
-                MenuEntry::Separator => {
+                None => {
                     self.separator_rects.push(Rect {
                         x: inner_x,
                         y,
@@ -181,64 +307,160 @@ impl DropdownMenu {
                     });
                     y += sep_h;
                 }
-                MenuEntry::Item(item) => {
-                    self.item_rects.push(Rect {
+                Some(i) => {
+                    self.item_rects[i] = Rect {
                         x: inner_x,
                         y,
                         width: inner_w,
                         height: item_h,
-                    });
+                    };
 
-                    if item.closeable {
-                        self.close_rects.push(Rect {
+                    if self.items[i].closeable {
+                        self.close_rects[i] = Rect {
                             x: inner_x + inner_w - item_pad_h - close_size,
                             y: y + (item_h - close_size) / 2.0,
                             width: close_size,
                             height: close_size,
-                        });
-                    } else {
-                        self.close_rects.push(Rect::ZERO);
+                        };
                     }
-
-                    items.push(item);
                     y += item_h;
                 }
             }
         }
+    }
 
-        // Create/reuse text buffers
-        let metrics = Metrics::new(theme.font_size, theme.font_size * font::LINE_HEIGHT);
-        while self.item_buffers.len() < items.len() {
-            self.item_buffers.push(Buffer::new(font_system, metrics));
+    fn matches_filter(&self, idx: usize, filter: &str) -> bool {
+        self.items[idx].label.to_lowercase().contains(&filter.to_lowercase())
+    }
+
+    fn is_shown(&self, idx: usize) -> bool {
+        self.item_rects.get(idx).is_some_and(|r| r.width > 0.0)
+    }
+
+    fn selected(&self) -> Option<usize> {
+        match self.hover {
+            DropdownElement::Item(idx) | DropdownElement::CloseButton(idx) => Some(idx),
+            DropdownElement::None => None,
         }
-        self.item_buffers.truncate(items.len());
+    }
 
-        for (i, item) in items.iter().enumerate() {
-            let buf = &mut self.item_buffers[i];
-            buf.set_metrics(font_system, metrics);
+    /// Shown items in menu order.
+    fn shown_items(&self) -> Vec<usize> {
+        self.entries.iter().flatten().copied().filter(|&i| self.is_shown(i)).collect()
+    }
 
-            let mut text_w = inner_w - 2.0 * item_pad_h;
-            if item.icon.is_some() {
-                text_w -= icon_size + icon_gap;
+    /// Move the selection `delta` shown items, wrapping around; with
+    /// nothing selected, down starts at the top and up at the bottom.
+    fn step(&mut self, delta: isize) {
+        let shown = self.shown_items();
+        if shown.is_empty() {
+            return;
+        }
+        let len = shown.len() as isize;
+        let next = match self.selected().and_then(|s| shown.iter().position(|&i| i == s)) {
+            Some(pos) => (pos as isize + delta).rem_euclid(len),
+            None if delta > 0 => 0,
+            None => len - 1,
+        };
+        self.hover = DropdownElement::Item(shown[next as usize]);
+    }
+
+    /// Handle a pressed key: arrows, Home and End move the selection, Enter
+    /// activates it, Delete removes a closeable item, and typed text
+    /// narrows the menu to matching items.
+    pub fn key(&mut self, event: &KeyEvent) -> DropdownKey {
+        let shown = self.shown_items();
+        match event.logical_key.as_ref() {
+            Key::Named(NamedKey::Escape) if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.layout();
             }
-            if item.closeable {
-                text_w -= close_size + icon_gap;
+            Key::Named(NamedKey::Escape) => return DropdownKey::Close,
+            Key::Named(NamedKey::ArrowDown) => self.step(1),
+            Key::Named(NamedKey::ArrowUp) => self.step(-1),
+            Key::Named(NamedKey::Home) => {
+                if let Some(&first) = shown.first() {
+                    self.hover = DropdownElement::Item(first);
+                }
+            }
+            Key::Named(NamedKey::End) => {
+                if let Some(&last) = shown.last() {
+                    self.hover = DropdownElement::Item(last);
+                }
+            }
+            Key::Named(NamedKey::Enter) => {
+                if let Some(idx) = self.selected() {
+                    return DropdownKey::Activate(idx);
+                }
+            }
+            Key::Named(NamedKey::Delete) => {
+                if let Some(idx) = self.selected().filter(|&i| self.items[i].closeable) {
+                    return DropdownKey::Remove(idx);
+                }
+            }
+            Key::Named(NamedKey::Backspace) => {
+                if self.filter.pop().is_some() {
+                    self.layout();
+                    self.select_first_shown();
+                }
+            }
+            _ => {
+                // Typing extends the filter, unless that would hide everything.
+                let Some(text) = &event.text else { return DropdownKey::Handled };
+                if text.chars().any(char::is_control) {
+                    return DropdownKey::Handled;
+                }
+                let filter = format!("{}{text}", self.filter);
+                if (0..self.items.len()).any(|i| self.matches_filter(i, &filter)) {
+                    self.filter = filter;
+                    self.layout();
+                    self.select_first_shown();
+                }
             }
-
-            let buf_width = text_w / scale;
-            buf.set_size(font_system, Some(buf_width), Some(metrics.line_height));
-            buf.set_text(
-                font_system,
-                &item.label,
-                font::default_attrs(),
-                Shaping::Advanced,
-            );
-            buf.shape_until_scroll(font_system, false);
         }
+        DropdownKey::Handled
+    }
 
-        self.items = items;
-        self.hover = DropdownElement::None;
-        self.visible = true;
+    fn select_first_shown(&mut self) {
+        self.hover = match self.shown_items().first() {
+            Some(&idx) => DropdownElement::Item(idx),
+            None => DropdownElement::None,
+        };
+    }
+
+    /// Drop an item (e.g. a deleted saved session) from the open menu,
+    /// keeping the selection in place. Closes the menu if none remain.
+    pub fn remove(&mut self, idx: usize) {
+        if idx >= self.items.len() {
+            return;
+        }
+        self.items.remove(idx);
+        self.item_buffers.remove(idx);
+        let mut entries: Vec<Option<usize>> = Vec::new();
+        for entry in self.entries.drain(..) {
+            let entry = match entry {
+                Some(i) if i == idx => continue,
+                Some(i) => Some(if i > idx { i - 1 } else { i }),
+                // No separator at the top or right after another
+                None if entries.last().is_none_or(Option::is_none) => continue,
+                None => None,
+            };
+            entries.push(entry);
+        }
+        if entries.last() == Some(&None) {
+            entries.pop();
+        }
+        self.entries = entries;
+        if self.items.is_empty() {
+            return self.close();
+        }
+        if !(0..self.items.len()).any(|i| self.matches_filter(i, &self.filter)) {
+            self.filter.clear();
+        }
+        self.layout();
+        let shown = self.shown_items();
+        let next = shown.iter().find(|&&i| i >= idx).or(shown.last());
+        self.hover = next.map_or(DropdownElement::None, |&i| DropdownElement::Item(i));
     }
 
     pub fn close(&mut self) {
@@ -261,7 +483,7 @@ impl DropdownMenu {
 
         // Close buttons first (higher priority, with padding for usability)
         for (i, rect) in self.close_rects.iter().enumerate() {
-            if self.items[i].closeable {
+            if self.items[i].closeable && self.is_shown(i) {
                 let pad = 4.0;
                 if x >= rect.x - pad
                     && x < rect.x + rect.width + pad
@@ -358,6 +580,9 @@ impl DropdownMenu {
         // Items
         let line_h = t.font_size * font::LINE_HEIGHT * scale;
         for (i, rect) in self.item_rects.iter().enumerate() {
+            if rect.width <= 0.0 {
+                continue;
+            }
             let item = &self.items[i];
             let is_hovered = matches!(
                 self.hover,
@@ -0,0 +1,317 @@
+//! Render a terminal's whole grid (scrollback + screen) to a file format:
+//! plain text, text with ANSI escapes, or a standalone HTML page.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::cell::{Cell, Flags};
+use alacritty_terminal::vte::ansi::{Color, NamedColor};
+
+use crate::colors::ColorScheme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Text,
+    Ansi,
+    Html,
+}
+
+impl ExportFormat {
+    /// Pick the format from a file extension; anything unknown is text.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase) {
+            Some(ext) if ext == "html" || ext == "htm" => ExportFormat::Html,
+            Some(ext) if ext == "ans" || ext == "ansi" => ExportFormat::Ansi,
+            _ => ExportFormat::Text,
+        }
+    }
+}
+
+/// Attributes that affect output; a new run starts when these change.
+const STYLE_FLAGS: Flags = Flags::BOLD
+    .union(Flags::DIM)
+    .union(Flags::ITALIC)
+    .union(Flags::ALL_UNDERLINES)
+    .union(Flags::INVERSE)
+    .union(Flags::HIDDEN)
+    .union(Flags::STRIKEOUT);
+
+#[derive(Clone, Copy, PartialEq)]
+struct Style {
+    fg: Color,
+    bg: Color,
+    flags: Flags,
+}
+
+impl Style {
+    const DEFAULT: Style = Style {
+        fg: Color::Named(NamedColor::Foreground),
+        bg: Color::Named(NamedColor::Background),
+        flags: Flags::empty(),
+    };
+
+    fn of(cell: &Cell) -> Self {
+        Style {
+            fg: cell.fg,
+            bg: cell.bg,
+            flags: cell.flags & STYLE_FLAGS,
+        }
+    }
+}
+
+/// One output line: runs of same-styled text, and whether it soft-wraps
+/// into the next row.
+struct Row {
+    runs: Vec<(Style, String)>,
+    wrapped: bool,
+}
+
+/// Collect every row from the oldest history line to the bottom of the
+/// screen. Trailing blank cells are dropped unless the row wraps.
+fn rows(grid: &Grid<Cell>) -> Vec<Row> {
+    let top = -(grid.history_size() as i32);
+    let bottom = grid.screen_lines() as i32;
+    let cols = grid.columns();
+
+    let mut rows: Vec<Row> = (top..bottom)
+        .map(|line| {
+            let row = &grid[Line(line)];
+            let wrapped = row[Column(cols - 1)].flags.contains(Flags::WRAPLINE);
+            let end = if wrapped {
+                cols
+            } else {
+                (0..cols)
+                    .rposition(|c| {
+                        let cell = &row[Column(c)];
+                        cell.c != ' ' || Style::of(cell) != Style::DEFAULT
+                    })
+                    .map_or(0, |c| c + 1)
+            };
+
+            let mut runs: Vec<(Style, String)> = Vec::new();
+            for c in 0..end {
+                let cell = &row[Column(c)];
+                if cell
+                    .flags
+                    .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+                {
+                    continue;
+                }
+                let style = Style::of(cell);
+                if runs.last().is_none_or(|(s, _)| *s != style) {
+                    runs.push((style, String::new()));
+                }
+                let text = &mut runs.last_mut().expect("run pushed above").1;
+                text.push(cell.c);
+                text.extend(cell.zerowidth().into_iter().flatten());
+            }
+            Row { runs, wrapped }
+        })
+        .collect();
+
+    // Blank rows below the last output (an idle prompt near the top of a
+    // fresh screen) aren't part of the session.
+    while rows.last().is_some_and(|r| r.runs.is_empty()) {
+        rows.pop();
+    }
+    rows
+}
+
+pub fn render(grid: &Grid<Cell>, colors: &ColorScheme, format: ExportFormat) -> String {
+    let rows = rows(grid);
+    match format {
+        ExportFormat::Text => render_text(&rows),
+        ExportFormat::Ansi => render_ansi(&rows),
+        ExportFormat::Html => render_html(&rows, colors),
+    }
+}
+
+fn render_text(rows: &[Row]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        for (_, text) in &row.runs {
+            out.push_str(text);
+        }
+        if !row.wrapped {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn render_ansi(rows: &[Row]) -> String {
+    let mut out = String::new();
+    let mut current = Style::DEFAULT;
+    for row in rows {
+        for (style, text) in &row.runs {
+            if *style != current {
+                out.push_str(&sgr(style));
+                current = *style;
+            }
+            out.push_str(text);
+        }
+        if !row.wrapped {
+            if current != Style::DEFAULT {
+                out.push_str("\x1b[0m");
+                current = Style::DEFAULT;
+            }
+            out.push('\n');
+        }
+    }
+    if current != Style::DEFAULT {
+        out.push_str("\x1b[0m");
+    }
+    out
+}
+
+/// Full SGR sequence for a style, starting from a reset.
+fn sgr(style: &Style) -> String {
+    let mut params = vec!["0".to_string()];
+    for (flag, code) in [
+        (Flags::BOLD, "1"),
+        (Flags::DIM, "2"),
+        (Flags::ITALIC, "3"),
+        (Flags::ALL_UNDERLINES, "4"),
+        (Flags::INVERSE, "7"),
+        (Flags::HIDDEN, "8"),
+        (Flags::STRIKEOUT, "9"),
+    ] {
+        if style.flags.intersects(flag) {
+            params.push(code.into());
+        }
+    }
+    if let Some(fg) = sgr_color(style.fg, 30) {
+        params.push(fg);
+    }
+    if let Some(bg) = sgr_color(style.bg, 40) {
+        params.push(bg);
+    }
+    format!("\x1b[{}m", params.join(";"))
+}
+
+/// SGR parameter for a color; `base` is 30 for foreground, 40 for background.
+/// `None` for the default color (already covered by the reset).
+fn sgr_color(color: Color, base: u8) -> Option<String> {
+    match color {
+        Color::Named(named) => {
+            let idx = named as usize;
+            match idx {
+                0..8 => Some((base as usize + idx).to_string()),
+                8..16 => Some((base as usize + 60 + idx - 8).to_string()),
+                _ => None,
+            }
+        }
+        Color::Indexed(idx) => Some(format!("{};5;{idx}", base + 8)),
+        Color::Spec(rgb) => Some(format!("{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b)),
+    }
+}
+
+fn render_html(rows: &[Row], colors: &ColorScheme) -> String {
+    let hex = |(r, g, b): (u8, u8, u8)| format!("#{r:02x}{g:02x}{b:02x}");
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Terminal output</title>\n\
+         </head>\n<body style=\"margin:0;background:{bg}\">\n\
+         <pre style=\"margin:0;padding:16px;color:{fg};background:{bg};\
+         font-family:ui-monospace,Menlo,Consolas,monospace;font-size:13px;line-height:1.3\">",
+        bg = hex(colors.color_to_rgb(Color::Named(NamedColor::Background))),
+        fg = hex(colors.color_to_rgb(Color::Named(NamedColor::Foreground))),
+    );
+
+    for row in rows {
+        for (style, text) in &row.runs {
+            let escaped = escape_html(text);
+            if *style == Style::DEFAULT {
+                out.push_str(&escaped);
+                continue;
+            }
+            let (mut fg, mut bg) = (style.fg, style.bg);
+            if style.flags.contains(Flags::INVERSE) {
+                std::mem::swap(&mut fg, &mut bg);
+            }
+            let mut css = format!("color:{}", hex(colors.color_to_rgb(fg)));
+            if style.flags.contains(Flags::INVERSE) || !colors.is_default_bg(bg) {
+                let _ = write!(css, ";background:{}", hex(colors.color_to_rgb(bg)));
+            }
+            for (flag, rule) in [
+                (Flags::BOLD, ";font-weight:bold"),
+                (Flags::DIM, ";opacity:0.6"),
+                (Flags::ITALIC, ";font-style:italic"),
+                (Flags::HIDDEN, ";visibility:hidden"),
+            ] {
+                if style.flags.intersects(flag) {
+                    css.push_str(rule);
+                }
+            }
+            match (
+                style.flags.intersects(Flags::ALL_UNDERLINES),
+                style.flags.contains(Flags::STRIKEOUT),
+            ) {
+                (true, true) => css.push_str(";text-decoration:underline line-through"),
+                (true, false) => css.push_str(";text-decoration:underline"),
+                (false, true) => css.push_str(";text-decoration:line-through"),
+                (false, false) => {}
+            }
+            let _ = write!(out, "<span style=\"{css}\">{escaped}</span>");
+        }
+        if !row.wrapped {
+            out.push('\n');
+        }
+    }
+
+    out.push_str("</pre>\n</body>\n</html>\n");
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::{Config, Term};
+    use alacritty_terminal::vte::ansi::{Processor, StdSyncHandler};
+
+    use crate::terminal_panel::TermSize;
+
+    fn term_with(bytes: &[u8]) -> Term<VoidListener> {
+        let mut term = Term::new(Config::default(), &TermSize::new(20, 3), VoidListener);
+        Processor::<StdSyncHandler>::new().advance(&mut term, bytes);
+        term
+    }
+
+    #[test]
+    fn text_includes_scrollback_and_trims_blanks() {
+        let term = term_with(b"one\r\ntwo\r\nthree\r\nfour  \r\n");
+        let text = render(term.grid(), &ColorScheme::default(), ExportFormat::Text);
+        assert_eq!(text, "one\ntwo\nthree\nfour\n");
+    }
+
+    #[test]
+    fn ansi_and_html_keep_colors() {
+        let term = term_with(b"\x1b[1;31mred\x1b[0m <ok>");
+        let ansi = render(term.grid(), &ColorScheme::default(), ExportFormat::Ansi);
+        assert_eq!(ansi, "\x1b[0;1;31mred\x1b[0m <ok>\n");
+
+        let colors = ColorScheme::default();
+        let html = render(term.grid(), &colors, ExportFormat::Html);
+        let red = format!("#{:02x}{:02x}{:02x}", colors.red.r, colors.red.g, colors.red.b);
+        assert!(html.contains(&format!("<span style=\"color:{red};font-weight:bold\">red</span>")));
+        assert!(html.contains(" &lt;ok&gt;"));
+    }
+}
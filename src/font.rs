@@ -14,14 +14,18 @@ pub struct CellMetrics {
     pub height: f32,
 }
 
-pub fn create_font_system() -> FontSystem {
+/// Build the font system. With `color_emoji` off, emoji faces are removed so
+/// the fallback chain lands on monochrome symbol fonts instead.
+pub fn create_font_system(color_emoji: bool) -> FontSystem {
     let mut font_system = FontSystem::new();
     let db = font_system.db_mut();
     db.load_font_data(FONT_DATA.to_vec());
     db.set_monospace_family(FONT_FAMILY);
 
-    // Remove emoji fonts so the fallback chain never picks colored emoji.
-    // Terminal text should be rendered as monochrome glyphs only.
+    if color_emoji {
+        return font_system;
+    }
+
     let emoji_ids: Vec<_> = db
         .faces()
         .filter(|f| f.post_script_name.contains("Emoji"))
@@ -51,6 +55,16 @@ pub fn default_attrs() -> Attrs<'static> {
     Attrs::new().family(Family::Monospace)
 }
 
+/// Text attributes for a named family, falling back to [`default_attrs`].
+/// Glyphs missing from the named family still fall back through the
+/// system fonts.
+pub fn family_attrs(family: Option<&str>) -> Attrs<'_> {
+    match family {
+        Some(name) => Attrs::new().family(Family::Name(name)),
+        None => default_attrs(),
+    }
+}
+
 /// Set metrics, size, text, and shape a buffer in one call.
 ///
 /// This consolidates the common pattern of:
@@ -75,10 +89,20 @@ pub fn measure_cell(font_system: &mut FontSystem) -> CellMetrics {
 }
 
 pub fn measure_cell_for_size(font_system: &mut FontSystem, font_size: f32) -> CellMetrics {
+    measure_cell_for(font_system, font_size, None)
+}
+
+/// Measure cell dimensions for a specific family (`None` = the default
+/// monospace font).
+pub fn measure_cell_for(
+    font_system: &mut FontSystem,
+    font_size: f32,
+    family: Option<&str>,
+) -> CellMetrics {
     let m = metrics_for_size(font_size);
     let mut buffer = Buffer::new(font_system, m);
     buffer.set_size(font_system, Some(200.0), Some(100.0));
-    buffer.set_text(font_system, "M", default_attrs(), Shaping::Advanced);
+    buffer.set_text(font_system, "M", family_attrs(family), Shaping::Advanced);
     buffer.shape_until_scroll(font_system, false);
 
     let width = buffer
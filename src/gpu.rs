@@ -1,4 +1,6 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Weak};
 
 use glyphon::{
     Buffer, Cache, FontSystem, Metrics, Resolution, SwashCache, TextArea, TextAtlas, TextBounds,
@@ -10,7 +12,8 @@ use crate::colors::ColorScheme;
 use crate::draw::DrawContext;
 use crate::font::{self, CellMetrics};
 use crate::icons::IconManager;
-use crate::layout::{BgQuad, CursorData, Rect, RoundedQuad, TextSpec};
+use crate::images::TermImage;
+use crate::layout::{BgQuad, CursorData, FlatTriangle, ImageQuad, Rect, RoundedQuad, TextSpec};
 
 /// Max rounded rects: panel islands + tab bar elements + SSH dialog overlay
 const MAX_ROUNDED_RECTS: usize = 160;
@@ -116,8 +119,9 @@ fn init_text_resources(
     device: &Device,
     queue: &Queue,
     render_format: TextureFormat,
+    color_emoji: bool,
 ) -> TextResources {
-    let font_system = font::create_font_system();
+    let font_system = font::create_font_system(color_emoji);
     let swash_cache = SwashCache::new();
     let cache = Cache::new(device);
     let viewport = Viewport::new(device, &cache);
@@ -459,6 +463,275 @@ impl CursorPipeline {
     }
 }
 
+// ---------------------------------------------------------------------------
+// ImagePipeline
+// ---------------------------------------------------------------------------
+
+/// Inline image vertex.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ImageVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+}
+
+/// Where an image goes among the cells, by kitty's z-index rules.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ImageLayer {
+    UnderBackground,
+    UnderText,
+    OverText,
+}
+
+impl ImageLayer {
+    fn of(z: i32) -> Self {
+        if z < i32::MIN / 2 {
+            ImageLayer::UnderBackground
+        } else if z < 0 {
+            ImageLayer::UnderText
+        } else {
+            ImageLayer::OverText
+        }
+    }
+}
+
+struct ImageTexture {
+    /// Dead once the image's tab has evicted it, which drops the texture.
+    image: Weak<TermImage>,
+    bind_group: BindGroup,
+}
+
+/// Textured quads for inline terminal images. Each image is uploaded the
+/// first time it is drawn and kept until its tab lets go of it.
+struct ImagePipeline {
+    pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+    vertex_buffer: wgpu::Buffer,
+    textures: HashMap<u64, ImageTexture>,
+    /// Texture, vertex range and layer of each image in the last full
+    /// frame, in z order.
+    draws: Vec<(u64, Range<u32>, ImageLayer)>,
+}
+
+impl ImagePipeline {
+    fn new(device: &Device, render_format: TextureFormat) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("image bind group layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("image sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("image shader"),
+            source: ShaderSource::Wgsl(IMAGE_SHADER.into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("image pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("image pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ImageVertex>() as BufferAddress,
+                    step_mode: VertexStepMode::Vertex,
+                    attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32x2],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format: render_format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let vertex_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("image vertex buffer"),
+            size: 64 * 1024,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            vertex_buffer,
+            textures: HashMap::new(),
+            draws: Vec::new(),
+        }
+    }
+
+    /// Upload new textures and this frame's vertices; drop textures of
+    /// images no tab holds anymore.
+    fn prepare(&mut self, device: &Device, queue: &Queue, quads: &[ImageQuad], w: f32, h: f32) {
+        self.textures.retain(|_, t| t.image.strong_count() > 0);
+        self.draws.clear();
+
+        let mut sorted: Vec<&ImageQuad> = quads.iter().collect();
+        sorted.sort_by_key(|quad| quad.z);
+        let mut verts = Vec::with_capacity(quads.len() * 6);
+        for quad in sorted {
+            let r = quad.rect;
+            let c = quad.clip;
+            let (x0, y0) = (r.x.max(c.x), r.y.max(c.y));
+            let x1 = (r.x + r.width).min(c.x + c.width);
+            let y1 = (r.y + r.height).min(c.y + c.height);
+            if x0 >= x1 || y0 >= y1 || r.width <= 0.0 || r.height <= 0.0 {
+                continue;
+            }
+            let image = &quad.image;
+            if !self.textures.contains_key(&image.id) {
+                let texture = self.upload(device, queue, image);
+                self.textures.insert(image.id, texture);
+            }
+
+            // Texture coordinates of the visible part.
+            let [su0, sv0, su1, sv1] = quad.uv;
+            let u = |x: f32| su0 + (x - r.x) / r.width * (su1 - su0);
+            let v = |y: f32| sv0 + (y - r.y) / r.height * (sv1 - sv0);
+            let (u0, v0, u1, v1) = (u(x0), v(y0), u(x1), v(y1));
+            let (nx0, ny0) = (x0 / w * 2.0 - 1.0, 1.0 - y0 / h * 2.0);
+            let (nx1, ny1) = (x1 / w * 2.0 - 1.0, 1.0 - y1 / h * 2.0);
+            let start = verts.len() as u32;
+            verts.extend_from_slice(&[
+                ImageVertex { position: [nx0, ny0], uv: [u0, v0] },
+                ImageVertex { position: [nx1, ny0], uv: [u1, v0] },
+                ImageVertex { position: [nx0, ny1], uv: [u0, v1] },
+                ImageVertex { position: [nx0, ny1], uv: [u0, v1] },
+                ImageVertex { position: [nx1, ny0], uv: [u1, v0] },
+                ImageVertex { position: [nx1, ny1], uv: [u1, v1] },
+            ]);
+            let end = verts.len() as u32;
+            let layer = ImageLayer::of(quad.z);
+            // Placeholder cells come as many quads of one image in a row.
+            match self.draws.last_mut() {
+                Some((id, range, last)) if *id == image.id && *last == layer => range.end = end,
+                _ => self.draws.push((image.id, start..end, layer)),
+            }
+        }
+        if verts.is_empty() {
+            return;
+        }
+
+        let needed = std::mem::size_of_val(verts.as_slice()) as u64;
+        if needed > self.vertex_buffer.size() {
+            self.vertex_buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("image vertex buffer"),
+                size: needed.next_power_of_two(),
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+        }
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&verts));
+    }
+
+    fn upload(&self, device: &Device, queue: &Queue, image: &Arc<TermImage>) -> ImageTexture {
+        let size = Extent3d {
+            width: image.width,
+            height: image.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("inline image"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            texture.as_image_copy(),
+            &image.rgba,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * image.width),
+                rows_per_image: Some(image.height),
+            },
+            size,
+        );
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("image bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+        ImageTexture {
+            image: Arc::downgrade(image),
+            bind_group,
+        }
+    }
+
+    fn draw(&self, pass: &mut wgpu::RenderPass, layer: ImageLayer) {
+        if !self.draws.iter().any(|(_, _, l)| *l == layer) {
+            return;
+        }
+        pass.set_pipeline(&self.pipeline);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        for (id, range, _) in self.draws.iter().filter(|(_, _, l)| *l == layer) {
+            if let Some(texture) = self.textures.get(id) {
+                pass.set_bind_group(0, &texture.bind_group, &[]);
+                pass.draw(range.clone(), 0..1);
+            }
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Uniform types
 // ---------------------------------------------------------------------------
@@ -521,6 +794,9 @@ pub struct GpuContext {
     // Cursor animation pipeline
     cursor_pipeline: CursorPipeline,
 
+    // Inline terminal images
+    image_pipeline: ImagePipeline,
+
     // Cached carrier buffers for custom glyphs (icons) — avoids per-frame allocation
     icon_carrier: Buffer,
     overlay_icon_carrier: Buffer,
@@ -542,14 +818,18 @@ pub struct GpuContext {
 }
 
 impl GpuContext {
-    pub fn new(window: Arc<winit::window::Window>, colors: ColorScheme) -> Option<Self> {
+    pub fn new(
+        window: Arc<winit::window::Window>,
+        colors: ColorScheme,
+        color_emoji: bool,
+    ) -> Option<Self> {
         let scale_factor = window.scale_factor() as f32;
 
         let gpu = init_gpu(
             window,
             TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC | TextureUsages::COPY_DST,
         )?;
-        let mut text = init_text_resources(&gpu.device, &gpu.queue, gpu.render_format);
+        let mut text = init_text_resources(&gpu.device, &gpu.queue, gpu.render_format, color_emoji);
         let rounded_rect =
             RoundedRectPipeline::new(&gpu.device, gpu.render_format, MAX_ROUNDED_RECTS);
 
@@ -612,6 +892,7 @@ impl GpuContext {
         });
 
         let cursor_pipeline = CursorPipeline::new(&gpu.device, gpu.render_format);
+        let image_pipeline = ImagePipeline::new(&gpu.device, gpu.render_format);
 
         // Content texture: caches non-cursor content for blink-only frames
         let content_texture = gpu.device.create_texture(&TextureDescriptor {
@@ -656,6 +937,7 @@ impl GpuContext {
             quad_pipeline,
             quad_vertex_buffer,
             cursor_pipeline,
+            image_pipeline,
             icon_carrier,
             overlay_icon_carrier,
             content_texture,
@@ -795,8 +1077,22 @@ impl GpuContext {
             for bq in &scene.flat_quads {
                 push_quad(&mut self.reuse_quad_verts, bq, w, h);
             }
+            for tri in &scene.flat_triangles {
+                push_triangle(&mut self.reuse_quad_verts, tri, w, h);
+            }
 
             quad_vertex_count = self.reuse_quad_verts.len() as u32;
+            // Screens full of braille or shaded cells can outgrow the
+            // initial allocation — grow to the next power of two.
+            let needed = std::mem::size_of_val(self.reuse_quad_verts.as_slice()) as u64;
+            if needed > self.quad_vertex_buffer.size() {
+                self.quad_vertex_buffer = self.device.create_buffer(&BufferDescriptor {
+                    label: Some("quad vertex buffer"),
+                    size: needed.next_power_of_two(),
+                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+            }
             if !self.reuse_quad_verts.is_empty() {
                 self.queue.write_buffer(
                     &self.quad_vertex_buffer,
@@ -805,6 +1101,8 @@ impl GpuContext {
                 );
             }
 
+            self.image_pipeline.prepare(&self.device, &self.queue, &scene.images, w, h);
+
             // Cache counts for blink-only frames
             self.cached_scene_rr_count = scene_rr_count;
             self.cached_total_rr_count = total_rounded_rects;
@@ -936,6 +1234,7 @@ impl GpuContext {
 
                 // === Scene layer only ===
                 self.rounded_rect.draw_range(&mut pass, 0, scene_rr_count);
+                self.image_pipeline.draw(&mut pass, ImageLayer::UnderBackground);
 
                 if quad_vertex_count > 0 {
                     pass.set_pipeline(&self.quad_pipeline);
@@ -943,9 +1242,13 @@ impl GpuContext {
                     pass.draw(0..quad_vertex_count, 0..1);
                 }
 
+                self.image_pipeline.draw(&mut pass, ImageLayer::UnderText);
+
                 self.text_renderer
                     .render(&self.atlas, &self.viewport, &mut pass)
                     .ok();
+
+                self.image_pipeline.draw(&mut pass, ImageLayer::OverText);
             }
         }
 
@@ -1147,6 +1450,15 @@ fn push_quad(verts: &mut Vec<QuadVertex>, bq: &BgQuad, surface_w: f32, surface_h
     ]);
 }
 
+fn push_triangle(verts: &mut Vec<QuadVertex>, tri: &FlatTriangle, surface_w: f32, surface_h: f32) {
+    for p in tri.points {
+        verts.push(QuadVertex {
+            position: [(p[0] / surface_w) * 2.0 - 1.0, 1.0 - (p[1] / surface_h) * 2.0],
+            color: tri.color,
+        });
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Shaders
 // ---------------------------------------------------------------------------
@@ -1176,6 +1488,34 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 }
 "#;
 
+const IMAGE_SHADER: &str = r#"
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0) @binding(0) var image: texture_2d<f32>;
+@group(0) @binding(1) var image_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(in.position, 0.0, 1.0);
+    out.uv = in.uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(image, image_sampler, in.uv);
+}
+"#;
+
 const ROUNDED_RECT_SHADER: &str = r#"
 struct Uniforms {
     rect_bounds: vec4<f32>,
@@ -1,16 +1,32 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
 use winit::keyboard::KeyCode;
 
 // ---------------------------------------------------------------------------
 // Action — hotkey IDs
 // ---------------------------------------------------------------------------
 
-/// All supported hotkey actions.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
-pub enum HotkeyAction {
+/// Declares [`HotkeyAction`] together with [`HotkeyAction::ALL`], so a new
+/// action can't be left out of the list.
+macro_rules! hotkey_actions {
+    ($($action:ident,)*) => {
+        /// All supported hotkey actions.
+        #[derive(
+            Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+        )]
+        #[serde(rename_all = "kebab-case")]
+        pub enum HotkeyAction {
+            $($action,)*
+        }
+
+        impl HotkeyAction {
+            /// Every action, in declaration order.
+            pub const ALL: &[HotkeyAction] = &[$(HotkeyAction::$action,)*];
+        }
+    };
+}
+
+hotkey_actions! {
     // Tab management
     NewTab,
     CloseTab,
@@ -36,8 +52,10 @@ pub enum HotkeyAction {
     ZoomIn,
     ZoomOut,
     ResetZoom,
+    ToggleLigatures,
     Search,
     CtrlC,
+    ToggleBroadcast,
 
     // Line editing
     Home,
@@ -52,6 +70,7 @@ pub enum HotkeyAction {
     ToggleFullscreen,
     NewWindow,
     Settings,
+    CommandPalette,
 
     // Scrolling
     ScrollToTop,
@@ -62,6 +81,58 @@ pub enum HotkeyAction {
     ScrollToBottom,
 }
 
+impl HotkeyAction {
+    /// Name shown in the command palette.
+    pub fn label(self) -> &'static str {
+        use HotkeyAction::*;
+        match self {
+            NewTab => "New Tab",
+            CloseTab => "Close Tab",
+            ReopenTab => "Reopen Closed Tab",
+            NextTab => "Next Tab",
+            PreviousTab => "Previous Tab",
+            Tab1 => "Go to Tab 1",
+            Tab2 => "Go to Tab 2",
+            Tab3 => "Go to Tab 3",
+            Tab4 => "Go to Tab 4",
+            Tab5 => "Go to Tab 5",
+            Tab6 => "Go to Tab 6",
+            Tab7 => "Go to Tab 7",
+            Tab8 => "Go to Tab 8",
+            Tab9 => "Go to Tab 9",
+            Tab10 => "Go to Tab 10",
+            Copy => "Copy",
+            Paste => "Paste",
+            SelectAll => "Select All",
+            Clear => "Clear Screen",
+            ZoomIn => "Zoom In",
+            ZoomOut => "Zoom Out",
+            ResetZoom => "Reset Zoom",
+            ToggleLigatures => "Toggle Ligatures",
+            Search => "Find",
+            CtrlC => "Copy or Interrupt",
+            ToggleBroadcast => "Toggle Broadcast Input",
+            Home => "Cursor to Line Start",
+            End => "Cursor to Line End",
+            PreviousWord => "Cursor to Previous Word",
+            NextWord => "Cursor to Next Word",
+            DeletePreviousWord => "Delete Previous Word",
+            DeleteNextWord => "Delete Next Word",
+            DeleteLine => "Delete Line",
+            ToggleFullscreen => "Toggle Full Screen",
+            NewWindow => "New Window",
+            Settings => "Settings",
+            CommandPalette => "Command Palette",
+            ScrollToTop => "Scroll to Top",
+            ScrollPageUp => "Scroll Page Up",
+            ScrollUp => "Scroll Up",
+            ScrollDown => "Scroll Down",
+            ScrollPageDown => "Scroll Page Down",
+            ScrollToBottom => "Scroll to Bottom",
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Parsed keybinding from config string
 // ---------------------------------------------------------------------------
@@ -139,33 +210,22 @@ pub struct HotkeyConfig {
 }
 
 impl HotkeyConfig {
-    /// Load platform defaults, then overlay user config per-action.
-    /// User config replaces the binding array for each action it defines.
-    /// An empty array `[]` disables the hotkey.
-    pub fn load() -> Self {
+    /// Platform defaults with the config file's overrides laid over them.
+    /// An override replaces the binding array for its action; an empty
+    /// array `[]` disables the hotkey.
+    pub fn with_overrides(overrides: &BTreeMap<HotkeyAction, Vec<String>>) -> Self {
         let mut cfg = Self::platform_defaults();
-        if let Some(path) = config_path() {
-            if let Ok(data) = std::fs::read_to_string(&path) {
-                if let Ok(user) = serde_json::from_str::<HotkeyConfig>(&data) {
-                    for (action, bindings) in user.hotkeys {
-                        cfg.hotkeys.insert(action, bindings);
-                    }
-                }
-            }
+        for (action, bindings) in overrides {
+            cfg.hotkeys.insert(*action, bindings.clone());
         }
         cfg
     }
 
-    /// Save current config to disk.
+    /// Save the full mapping into the config file.
     pub fn save(&self) {
-        if let Some(path) = config_path() {
-            if let Some(parent) = path.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            if let Ok(json) = serde_json::to_string_pretty(self) {
-                let _ = std::fs::write(path, json);
-            }
-        }
+        crate::config::update(|config| {
+            config.hotkeys = self.hotkeys.iter().map(|(a, b)| (*a, b.clone())).collect();
+        });
     }
 
     /// Build a lookup table for fast runtime matching.
@@ -231,8 +291,10 @@ impl HotkeyConfig {
             (ZoomIn, vec!["⌘-=".into(), "⌘-Shift-=".into()]),
             (ZoomOut, vec!["⌘--".into(), "⌘-Shift--".into()]),
             (ResetZoom, vec!["⌘-0".into()]),
+            (ToggleLigatures, vec!["⌘-Shift-L".into()]),
             (Search, vec!["⌘-F".into()]),
             (CtrlC, vec!["Ctrl-C".into()]),
+            (ToggleBroadcast, vec!["⌘-Shift-B".into()]),
             // Line editing
             (Home, vec!["⌘-Left".into()]),
             (End, vec!["⌘-Right".into()]),
@@ -245,6 +307,7 @@ impl HotkeyConfig {
             (ToggleFullscreen, vec!["Ctrl+⌘+F".into(), "F11".into()]),
             (NewWindow, vec!["⌘-N".into()]),
             (Settings, vec!["⌘-,".into()]),
+            (CommandPalette, vec!["⌘-Shift-P".into()]),
             // Scrolling
             (ScrollToTop, vec!["Shift-PageUp".into()]),
             (ScrollPageUp, vec!["⌥-PageUp".into()]),
@@ -284,8 +347,10 @@ impl HotkeyConfig {
             (ZoomIn, vec!["Ctrl-=".into(), "Ctrl-Shift-=".into()]),
             (ZoomOut, vec!["Ctrl--".into(), "Ctrl-Shift--".into()]),
             (ResetZoom, vec!["Ctrl-0".into()]),
+            (ToggleLigatures, vec!["Ctrl-Shift-L".into()]),
             (Search, vec!["Ctrl-Shift-F".into()]),
             (CtrlC, vec!["Ctrl-C".into()]),
+            (ToggleBroadcast, vec!["Ctrl-Shift-B".into()]),
             // Line editing
             (PreviousWord, vec!["Ctrl-Left".into()]),
             (NextWord, vec!["Ctrl-Right".into()]),
@@ -296,6 +361,7 @@ impl HotkeyConfig {
             (ToggleFullscreen, vec!["F11".into(), "Alt-Enter".into()]),
             (NewWindow, vec!["Ctrl-Shift-N".into()]),
             (Settings, vec!["Ctrl-,".into()]),
+            (CommandPalette, vec!["Ctrl-Shift-P".into()]),
             // Scrolling
             (ScrollToTop, vec!["Ctrl-PageUp".into()]),
             (ScrollPageUp, vec!["Alt-PageUp".into()]),
@@ -308,10 +374,6 @@ impl HotkeyConfig {
     }
 }
 
-fn config_path() -> Option<PathBuf> {
-    dirs::config_dir().map(|d| d.join("pfauterminal").join("hotkeys.json"))
-}
-
 // ---------------------------------------------------------------------------
 // HotkeyLookup — dual physical/logical matching
 // ---------------------------------------------------------------------------
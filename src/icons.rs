@@ -5,7 +5,11 @@ pub const ICON_ADD: CustomGlyphId = 2;
 pub const ICON_CLOSE: CustomGlyphId = 3;
 pub const ICON_CLOSE_HOVERED: CustomGlyphId = 4;
 
-const ICONS: [(CustomGlyphId, &str); 4] = [
+/// Light variants are registered under `id + LIGHT_VARIANT`, so the glyph
+/// atlas caches both and switching appearance doesn't need an atlas flush.
+const LIGHT_VARIANT: CustomGlyphId = 0x100;
+
+const ICONS: [(CustomGlyphId, &str); 8] = [
     (ICON_TERMINAL, include_str!("../icons/terminal_dark.svg")),
     (ICON_ADD, include_str!("../icons/add_dark.svg")),
     (ICON_CLOSE, include_str!("../icons/closeSmall_dark.svg")),
@@ -13,8 +17,26 @@ const ICONS: [(CustomGlyphId, &str); 4] = [
         ICON_CLOSE_HOVERED,
         include_str!("../icons/closeSmallHovered_dark.svg"),
     ),
+    (
+        ICON_TERMINAL + LIGHT_VARIANT,
+        include_str!("../icons/terminal_light.svg"),
+    ),
+    (ICON_ADD + LIGHT_VARIANT, include_str!("../icons/add_light.svg")),
+    (
+        ICON_CLOSE + LIGHT_VARIANT,
+        include_str!("../icons/closeSmall_light.svg"),
+    ),
+    (
+        ICON_CLOSE_HOVERED + LIGHT_VARIANT,
+        include_str!("../icons/closeSmallHovered_light.svg"),
+    ),
 ];
 
+/// Map an icon to its variant for the current appearance.
+pub fn variant(id: CustomGlyphId, light: bool) -> CustomGlyphId {
+    if light { id + LIGHT_VARIANT } else { id }
+}
+
 pub struct IconManager {
     trees: Vec<(CustomGlyphId, resvg::usvg::Tree)>,
 }
@@ -0,0 +1,928 @@
+//! Inline images: Sixel, iTerm2's `OSC 1337 ; File=` and kitty's `APC G`
+//! sequences are cut out of a tab's output, decoded on the reader thread
+//! into the tab's [`Images`] store, and replaced by anchor cells that the
+//! terminal keeps like any other text.
+//!
+//! An anchor is a space carrying an OSC 8 hyperlink that names the
+//! placement and the row it sits in, one per row at the placement's left
+//! edge. The anchors scroll, reflow and get erased with the grid, so the
+//! panel only has to find them to know where to draw, and a placement whose
+//! anchors are all gone (cleared, or pushed out of the scrollback) can be
+//! dropped.
+//!
+//! The filter runs before the terminal parses the output and can't see the
+//! grid, which gives anchors limits:
+//! - each one replaces the character in the placement's left column on its
+//!   row, even under an image drawn over text;
+//! - it takes on the current SGR attributes, which the panel ignores;
+//! - the hyperlink it sets closes the program's own, which is reopened
+//!   after the anchors;
+//! - in the last column, the pending wrap makes the backspace land one
+//!   column left, so later rows' anchors (and the cursor) sit one further
+//!   left; the image still hangs from its first row's anchor.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use alacritty_terminal::event::EventListener;
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::Line;
+use alacritty_terminal::term::cell::Cell;
+use base64::Engine;
+
+use crate::kitty_graphics::KittyGraphics;
+use crate::sixel;
+
+/// Hyperlink URI prefix of anchor cells: `pfauterminal-image:<id>:<row>`.
+const ANCHOR_PREFIX: &str = "pfauterminal-image:";
+
+/// Decoded pixels a tab may hold before its oldest images are dropped.
+const MAX_BYTES: usize = 320 * 1024 * 1024;
+
+/// Longest image sequence collected; longer ones are discarded.
+const MAX_PAYLOAD: usize = 64 * 1024 * 1024;
+
+/// Widest and tallest a placement may be, in screens; bigger ones are
+/// shrunk to fit, as every row costs an anchor in the output.
+const MAX_SCREENS: f32 = 4.0;
+
+/// How often the panel looks for placements that lost their anchors.
+/// Younger ones are kept regardless, as their anchors may not be parsed yet.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Image and placement ids come from one counter, unique across tabs so the
+/// GPU can cache textures by id and so ids order by age.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A decoded image.
+pub struct TermImage {
+    pub id: u64,
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl TermImage {
+    pub fn new(width: u32, height: u32, rgba: Vec<u8>) -> Self {
+        Self { id: NEXT_ID.fetch_add(1, Ordering::Relaxed), width, height, rgba }
+    }
+}
+
+/// An image as placed in the grid, drawn from its anchors.
+pub struct Placement {
+    pub image: Arc<TermImage>,
+    /// Size in cells when it was placed; it is drawn over that many cells,
+    /// so it zooms with the font.
+    pub cells: (f32, f32),
+    /// The part of the image shown, as `[u0, v0, u1, v1]`.
+    pub uv: [f32; 4],
+    /// Offset from the anchor cell's top left corner, in cells.
+    pub offset: (f32, f32),
+    /// Kitty's z-index: negative goes under the text.
+    pub z: i32,
+    /// Kitty image and placement ids; `None` for Sixel and iTerm2 images.
+    pub kitty: Option<(u32, u32)>,
+}
+
+impl Placement {
+    /// A whole image under the text, as Sixel and iTerm2 images are drawn.
+    fn whole(image: TermImage, cells: (f32, f32)) -> Self {
+        let image = Arc::new(image);
+        Self { image, cells, uv: [0.0, 0.0, 1.0, 1.0], offset: (0.0, 0.0), z: -1, kitty: None }
+    }
+
+    /// Grid cells covered, counting from the anchor.
+    fn extent(&self) -> (usize, usize) {
+        let cols = (self.offset.0 + self.cells.0).ceil() as usize;
+        let rows = (self.offset.1 + self.cells.1).ceil() as usize;
+        (cols.max(1), rows.max(1))
+    }
+}
+
+/// A kitty placement shown through Unicode placeholder cells rather than
+/// anchors: the image is fitted into `cols` x `rows` cells and each
+/// placeholder cell draws its piece.
+#[derive(Clone, Copy)]
+pub struct VirtualPlacement {
+    pub cols: u32,
+    pub rows: u32,
+    pub z: i32,
+}
+
+/// Which kitty placements a delete command (`a=d`) removes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delete {
+    /// `a`: every placement on screen.
+    Visible,
+    /// `i`: an image's placements, or only the given one.
+    Image(u32, Option<u32>),
+    /// `n`: the same for the newest image with this number.
+    Number(u32, Option<u32>),
+    /// `c`: placements under the cursor.
+    Cursor,
+    /// `p`/`q`: placements over a cell (1-based), `q` only at one z-index.
+    Cell(u32, u32, Option<i32>),
+    /// `x`: placements crossing a column (1-based).
+    Column(u32),
+    /// `y`: placements crossing a row (1-based).
+    Row(u32),
+    /// `r`: placements of images with ids in the range.
+    Range(u32, u32),
+    /// `z`: placements at a z-index.
+    Z(i32),
+}
+
+impl Delete {
+    /// Whether it needs the screen, so has to wait for the panel.
+    fn by_position(self) -> bool {
+        matches!(
+            self,
+            Delete::Visible | Delete::Cursor | Delete::Cell(..) | Delete::Column(_) | Delete::Row(_)
+        )
+    }
+}
+
+/// A tab's images, written by its reader thread and drawn by the panel.
+/// Windows PTYs can't be tapped, so local tabs there never get any.
+#[derive(Clone, Default)]
+pub struct Images(Arc<Mutex<ImageStore>>);
+
+#[derive(Default)]
+struct ImageStore {
+    /// Placements by anchor id, with when they were made.
+    placements: HashMap<u64, (Arc<Placement>, Instant)>,
+    /// Kitty images by id, with their number; kept until deleted, placed
+    /// or not.
+    kitty: HashMap<u32, (Arc<TermImage>, u32)>,
+    /// Kitty virtual placements by image and placement id.
+    virtuals: HashMap<(u32, u32), VirtualPlacement>,
+    /// Deletes by position, for the panel to apply with the screen at hand,
+    /// each with the first id too new for it.
+    screen_deletes: Vec<(Delete, bool, u64)>,
+    /// The first id made after the panel saw the alternate screen, while
+    /// it is up.
+    alt_since: Option<u64>,
+    bytes: usize,
+    /// Cell size in physical pixels and grid size, for sizing new images.
+    cell: (f32, f32),
+    grid: (usize, usize),
+    last_sweep: Option<Instant>,
+}
+
+impl Images {
+    pub fn set_geometry(&self, cell: (f32, f32), cols: usize, rows: usize) {
+        let mut store = self.lock();
+        store.cell = (cell.0.max(1.0), cell.1.max(1.0));
+        store.grid = (cols, rows);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let store = self.lock();
+        store.placements.is_empty() && store.virtuals.is_empty()
+    }
+
+    pub fn get(&self, anchor: u64) -> Option<Arc<Placement>> {
+        self.lock().placements.get(&anchor).map(|(placement, _)| placement.clone())
+    }
+
+    /// Every so often, drop placements whose anchors are all gone from
+    /// `grid`, the active screen's. The other screen's grid is out of reach,
+    /// so on the alternate screen only placements made since switching to it
+    /// are checked; once back, those have no anchors left and go too.
+    pub fn sweep(&self, grid: &Grid<Cell>, alt_screen: bool) {
+        let wanted: HashSet<u64> = {
+            let mut store = self.lock();
+            match (alt_screen, store.alt_since) {
+                (true, None) => store.alt_since = Some(NEXT_ID.load(Ordering::Relaxed)),
+                (false, Some(_)) => store.alt_since = None,
+                _ => {}
+            }
+            let due = store.last_sweep.is_none_or(|t| t.elapsed() >= SWEEP_INTERVAL);
+            if store.placements.is_empty() || !due {
+                return;
+            }
+            store.last_sweep = Some(Instant::now());
+            let since = store.alt_since.unwrap_or(0);
+            let placements = store.placements.iter();
+            let checked = placements.filter(|(id, (_, added))| {
+                **id >= since && added.elapsed() >= SWEEP_INTERVAL
+            });
+            checked.map(|(&id, _)| id).collect()
+        };
+        if wanted.is_empty() {
+            return;
+        }
+        let live = live_anchors(grid, &wanted);
+        if live.len() < wanted.len() {
+            let mut store = self.lock();
+            store.placements.retain(|id, _| !wanted.contains(id) || live.contains(id));
+            store.recount();
+        }
+    }
+
+    /// The image and size of a virtual placement; placement id 0 takes any
+    /// of the image's.
+    pub fn virtual_placement(
+        &self,
+        image: u32,
+        placement: u32,
+    ) -> Option<(Arc<TermImage>, VirtualPlacement)> {
+        let store = self.lock();
+        let virt = match placement {
+            0 => store.virtuals.iter().find(|((i, _), _)| *i == image).map(|(_, v)| v),
+            _ => store.virtuals.get(&(image, placement)),
+        };
+        Some((store.kitty.get(&image)?.0.clone(), *virt?))
+    }
+
+    pub fn has_screen_deletes(&self) -> bool {
+        !self.lock().screen_deletes.is_empty()
+    }
+
+    /// Apply the queued deletes by position against the active screen.
+    pub fn apply_screen_deletes(&self, grid: &Grid<Cell>) {
+        let mut store = self.lock();
+        let deletes = std::mem::take(&mut store.screen_deletes);
+        // Top left cell of each kitty placement on screen.
+        let mut origins = HashMap::new();
+        for line in 0..grid.screen_lines() as i32 {
+            for (col, cell) in grid[Line(line)].into_iter().enumerate() {
+                if let Some((id, row)) = anchor(cell) {
+                    origins.entry(id).or_insert((line - row as i32, col as i32));
+                }
+            }
+        }
+        let cursor = grid.cursor.point;
+        let cursor = (cursor.line.0, cursor.column.0 as i32);
+        for (delete, free, before) in deletes {
+            let doomed: HashSet<u64> = origins
+                .iter()
+                .filter(|&(&id, _)| id < before)
+                .filter_map(|(&id, &(top, left))| {
+                    let (placement, _) = store.placements.get(&id)?;
+                    placement.kitty?;
+                    let (cols, rows) = placement.extent();
+                    let (bottom, right) = (top + rows as i32, left + cols as i32);
+                    let covers = |line: i32, col: i32| {
+                        (top..bottom).contains(&line) && (left..right).contains(&col)
+                    };
+                    let hit = match delete {
+                        Delete::Visible => true,
+                        Delete::Cursor => covers(cursor.0, cursor.1),
+                        Delete::Cell(x, y, z) => {
+                            covers(y as i32 - 1, x as i32 - 1)
+                                && z.is_none_or(|z| z == placement.z)
+                        }
+                        Delete::Column(x) => (left..right).contains(&(x as i32 - 1)),
+                        Delete::Row(y) => (top..bottom).contains(&(y as i32 - 1)),
+                        _ => false,
+                    };
+                    hit.then_some(id)
+                })
+                .collect();
+            store.remove(|id, _| doomed.contains(&id), |_, _| false, Vec::new(), free);
+        }
+    }
+
+    /// Store a placement and write its anchors at the cursor. A kitty
+    /// placement replaces any earlier one with the same nonzero ids.
+    pub fn place(&self, out: &mut Vec<u8>, mut placement: Placement, after: CursorAfter) {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let (cols, rows) = {
+            let mut store = self.lock();
+            placement.cells = fit_grid(placement.cells, store.grid);
+            let extent = placement.extent();
+            if let Some(ids @ (_, p)) = placement.kitty
+                && p != 0
+            {
+                store.placements.retain(|_, (old, _)| old.kitty != Some(ids));
+            }
+            let image = placement.image.id;
+            store.placements.insert(id, (Arc::new(placement), Instant::now()));
+            store.recount();
+            store.evict(image);
+            extent
+        };
+        // `C=1` must never scroll, and the cursor's row isn't known here, so
+        // only the top row is anchored; the image still hangs from it.
+        let anchored = if matches!(after, CursorAfter::Stay) { 1 } else { rows };
+        for row in 0..anchored {
+            if row > 0 {
+                out.push(b'\n');
+            }
+            // Backspace returns to the anchor's column for the next row.
+            let _ = write!(out, "\x1b]8;;{ANCHOR_PREFIX}{id}:{row}\x1b\\ \x1b]8;;\x1b\\\x08");
+        }
+        match after {
+            CursorAfter::Below => out.push(b'\n'),
+            CursorAfter::Right => {
+                let _ = write!(out, "\x1b[{cols}C");
+            }
+            CursorAfter::Stay => {}
+        }
+    }
+
+    /// Store a kitty image, replacing any with the same id along with its
+    /// placements.
+    pub fn add_kitty(&self, id: u32, number: u32, image: TermImage) {
+        let mut store = self.lock();
+        store.placements.retain(|_, (p, _)| p.kitty.is_none_or(|(i, _)| i != id));
+        store.virtuals.retain(|&(i, _), _| i != id);
+        let image = Arc::new(image);
+        let image_id = image.id;
+        store.kitty.insert(id, (image, number));
+        store.recount();
+        store.evict(image_id);
+    }
+
+    pub fn kitty_image(&self, id: u32) -> Option<Arc<TermImage>> {
+        self.lock().kitty.get(&id).map(|(image, _)| image.clone())
+    }
+
+    /// The id of the newest kitty image with this number.
+    pub fn kitty_id(&self, number: u32) -> Option<u32> {
+        self.lock().newest(number)
+    }
+
+    /// An image id the program isn't using, for images sent by number or
+    /// without any id.
+    pub fn unused_kitty_id(&self) -> u32 {
+        let store = self.lock();
+        // Kitty hands these out from the top of the range as well.
+        (1..u32::MAX).rev().find(|id| !store.kitty.contains_key(id)).unwrap_or(u32::MAX)
+    }
+
+    pub fn set_virtual(&self, image: u32, placement: u32, virt: VirtualPlacement) {
+        self.lock().virtuals.insert((image, placement), virt);
+    }
+
+    /// Remove kitty placements, and with `free` the images left without
+    /// any. Deletes by position are queued for the panel.
+    pub fn delete(&self, delete: Delete, free: bool) {
+        let mut store = self.lock();
+        if delete.by_position() {
+            let before = NEXT_ID.load(Ordering::Relaxed);
+            store.screen_deletes.push((delete, free, before));
+            return;
+        }
+        let named = match delete {
+            Delete::Number(number, placement) => {
+                let Some(id) = store.newest(number) else { return };
+                Some((id, placement))
+            }
+            Delete::Image(id, placement) => Some((id, placement)),
+            _ => None,
+        };
+        let hit = |image: u32, placement: u32, z: i32| match delete {
+            Delete::Range(from, to) => (from..=to).contains(&image),
+            Delete::Z(target) => z == target,
+            _ => named.is_some_and(|(id, p)| id == image && p.is_none_or(|p| p == placement)),
+        };
+        // Images named outright go even if nothing shows them.
+        let targets = match (named, delete) {
+            (Some((id, None)), _) => vec![id],
+            (_, Delete::Range(from, to)) => {
+                store.kitty.keys().copied().filter(|i| (from..=to).contains(i)).collect()
+            }
+            _ => Vec::new(),
+        };
+        store.remove(
+            |_, p| p.kitty.is_some_and(|(i, pid)| hit(i, pid, p.z)),
+            |&(i, pid), v| hit(i, pid, v.z),
+            targets,
+            free,
+        );
+    }
+
+    /// Cell size in physical pixels and grid size.
+    pub fn geometry(&self) -> ((f32, f32), (usize, usize)) {
+        let store = self.lock();
+        (store.cell, store.grid)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ImageStore> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ImageStore {
+    fn newest(&self, number: u32) -> Option<u32> {
+        let numbered = self.kitty.iter().filter(|(_, (_, n))| *n == number);
+        numbered.max_by_key(|(_, (image, _))| image.id).map(|(&id, _)| id)
+    }
+
+    /// Drop the placements and virtual placements that match, then with
+    /// `free` every kitty image they showed, or named in `targets`, that
+    /// nothing shows anymore.
+    fn remove(
+        &mut self,
+        placement: impl Fn(u64, &Placement) -> bool,
+        virt: impl Fn(&(u32, u32), &VirtualPlacement) -> bool,
+        mut targets: Vec<u32>,
+        free: bool,
+    ) {
+        self.placements.retain(|&id, (p, _)| {
+            let hit = placement(id, p);
+            if hit && let Some((image, _)) = p.kitty {
+                targets.push(image);
+            }
+            !hit
+        });
+        self.virtuals.retain(|key, v| {
+            let hit = virt(key, v);
+            if hit {
+                targets.push(key.0);
+            }
+            !hit
+        });
+        if free {
+            for image in targets {
+                let mut placed = self.placements.values().filter_map(|(p, _)| p.kitty);
+                let mut virtuals = self.virtuals.keys();
+                if !placed.any(|(i, _)| i == image) && !virtuals.any(|&(i, _)| i == image) {
+                    self.kitty.remove(&image);
+                }
+            }
+        }
+        self.recount();
+    }
+
+    /// Total the pixels of every image held, each counted once.
+    fn recount(&mut self) {
+        let mut sizes = HashMap::new();
+        for (placement, _) in self.placements.values() {
+            sizes.insert(placement.image.id, placement.image.rgba.len());
+        }
+        for (image, _) in self.kitty.values() {
+            sizes.insert(image.id, image.rgba.len());
+        }
+        self.bytes = sizes.values().sum();
+    }
+
+    /// Drop the oldest Sixel/iTerm2 placements and kitty images until under
+    /// the memory cap, sparing the image `keep` just added.
+    fn evict(&mut self, keep: u64) {
+        while self.bytes > MAX_BYTES {
+            let placement = self
+                .placements
+                .iter()
+                .filter(|(_, (p, _))| p.kitty.is_none() && p.image.id != keep)
+                .map(|(&id, _)| id)
+                .min();
+            let kitty = self
+                .kitty
+                .iter()
+                .filter(|(_, (image, _))| image.id != keep)
+                .min_by_key(|(_, (image, _))| image.id)
+                .map(|(&id, (image, _))| (id, image.id));
+            match (placement, kitty) {
+                (Some(p), Some((_, age))) if p < age => self.placements.remove(&p).map(drop),
+                (_, Some((id, _))) => {
+                    self.placements.retain(|_, (p, _)| p.kitty.is_none_or(|(i, _)| i != id));
+                    self.virtuals.retain(|&(i, _), _| i != id);
+                    self.kitty.remove(&id).map(drop)
+                }
+                (Some(p), None) => self.placements.remove(&p).map(drop),
+                (None, None) => break,
+            };
+            self.recount();
+        }
+    }
+}
+
+/// Shrink a size in cells to at most `MAX_SCREENS` grids either way,
+/// keeping its aspect ratio.
+fn fit_grid(cells: (f32, f32), (cols, rows): (usize, usize)) -> (f32, f32) {
+    let limit = (cols.max(1) as f32 * MAX_SCREENS, rows.max(1) as f32 * MAX_SCREENS);
+    let scale = (limit.0 / cells.0).min(limit.1 / cells.1).min(1.0);
+    (cells.0 * scale, cells.1 * scale)
+}
+
+/// The placement and row an anchor cell stands for.
+pub fn anchor(cell: &Cell) -> Option<(u64, usize)> {
+    cell.extra.as_ref()?;
+    let link = cell.hyperlink()?;
+    let (id, row) = link.uri().strip_prefix(ANCHOR_PREFIX)?.split_once(':')?;
+    Some((id.parse().ok()?, row.parse().ok()?))
+}
+
+/// Which of `wanted` still have an anchor in `grid`, scrollback included.
+/// The scan goes up from the bottom, where recent images are, and stops as
+/// soon as all are found, so it only reads the whole grid when one is gone.
+fn live_anchors(grid: &Grid<Cell>, wanted: &HashSet<u64>) -> HashSet<u64> {
+    let mut live = HashSet::new();
+    for line in (grid.topmost_line().0..=grid.bottommost_line().0).rev() {
+        for cell in &grid[Line(line)] {
+            if let Some((id, _)) = anchor(cell)
+                && wanted.contains(&id)
+            {
+                live.insert(id);
+            }
+        }
+        if live.len() == wanted.len() {
+            break;
+        }
+    }
+    live
+}
+
+/// An image ready to place, with its size in cells.
+struct Decoded {
+    image: TermImage,
+    cells: (f32, f32),
+}
+
+/// Where the cursor goes once an image is placed.
+#[derive(Clone, Copy)]
+pub enum CursorAfter {
+    /// The line below the image, as xterm does for sixels.
+    Below,
+    /// Just right of the image on its last row, as iTerm2 and kitty do.
+    Right,
+    /// Where it was, for kitty's `C=1`.
+    Stay,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum Scan {
+    #[default]
+    Ground,
+    Escape,
+    /// `ESC P` and parameters; a `q` makes it a sixel.
+    DcsParams,
+    /// `ESC ]` and as much of `1337;File=` as has matched.
+    OscPrefix,
+    /// `ESC _`; a `G` makes it a kitty graphics command.
+    Apc,
+    Sixel,
+    SixelEscape,
+    ITerm,
+    ITermEscape,
+    Kitty,
+    KittyEscape,
+    /// An OSC 8 hyperlink, passed on once its link is noted.
+    Link,
+    LinkEscape,
+    /// The rest of a sequence too long to keep, swallowed up to its end.
+    Discard,
+    DiscardEscape,
+}
+
+const ITERM_PREFIX: &[u8] = b"1337;File=";
+
+/// Longest OSC 8 whose link is tracked.
+const MAX_LINK: usize = 4096;
+
+/// Cuts image sequences out of a tab's output and stores the images,
+/// leaving anchors in their place. Everything else passes through as is.
+pub struct ImageFilter {
+    images: Images,
+    kitty: KittyGraphics,
+    state: Scan,
+    /// Bytes of a sequence that may still turn out to be an image.
+    held: Vec<u8>,
+    payload: Vec<u8>,
+    /// Parameters and URI of the hyperlink the program has open, which
+    /// anchors have to close.
+    link: Option<Vec<u8>>,
+}
+
+impl ImageFilter {
+    /// `replies` gets kitty's responses to write back to the program;
+    /// `local_files` lets kitty images be read from files and shared memory,
+    /// which only makes sense when the program runs on this machine.
+    pub fn new(
+        images: Images,
+        replies: impl EventListener + Send + 'static,
+        local_files: bool,
+    ) -> Self {
+        Self {
+            kitty: KittyGraphics::new(Box::new(replies), local_files),
+            images,
+            state: Scan::Ground,
+            held: Vec::new(),
+            payload: Vec::new(),
+            link: None,
+        }
+    }
+
+    pub fn filter(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        let mut rest = input;
+        while let Some((&b, tail)) = rest.split_first() {
+            if self.state == Scan::Ground && b != 0x1b {
+                // Copy plain output up to the next escape in one go.
+                let plain = rest.iter().position(|&b| b == 0x1b).unwrap_or(rest.len());
+                out.extend_from_slice(&rest[..plain]);
+                rest = &rest[plain..];
+                continue;
+            }
+            if self.in_payload() && b != 0x1b && b != 0x07 {
+                // Likewise for image data up to a possible terminator.
+                let run = rest.iter().position(|&b| b == 0x1b || b == 0x07).unwrap_or(rest.len());
+                self.collect(&rest[..run]);
+                rest = &rest[run..];
+                continue;
+            }
+            self.byte(b, out);
+            rest = tail;
+        }
+    }
+
+    fn byte(&mut self, b: u8, out: &mut Vec<u8>) {
+        match self.state {
+            Scan::Ground if b == 0x1b => {
+                self.held.push(b);
+                self.state = Scan::Escape;
+            }
+            Scan::Ground => out.push(b),
+            Scan::Escape if b == b'P' => {
+                self.held.push(b);
+                self.state = Scan::DcsParams;
+            }
+            Scan::Escape if b == b']' => {
+                self.held.push(b);
+                self.state = Scan::OscPrefix;
+            }
+            Scan::Escape if b == b'_' => {
+                self.held.push(b);
+                self.state = Scan::Apc;
+            }
+            Scan::DcsParams if b == b'q' => {
+                self.held.clear();
+                self.payload.clear();
+                self.state = Scan::Sixel;
+            }
+            Scan::DcsParams if (b.is_ascii_digit() || b == b';') && self.held.len() < 32 => {
+                self.held.push(b);
+            }
+            Scan::OscPrefix if self.held.len() == 2 && b == b'8' => {
+                self.held.push(b);
+                self.state = Scan::Link;
+            }
+            Scan::OscPrefix if ITERM_PREFIX.get(self.held.len() - 2) == Some(&b) => {
+                self.held.push(b);
+                if self.held.len() - 2 == ITERM_PREFIX.len() {
+                    self.held.clear();
+                    self.payload.clear();
+                    self.state = Scan::ITerm;
+                }
+            }
+            Scan::Apc if b == b'G' => {
+                self.held.clear();
+                self.payload.clear();
+                self.state = Scan::Kitty;
+            }
+            Scan::Escape | Scan::DcsParams | Scan::OscPrefix | Scan::Apc => {
+                // Not an image after all: let the terminal have it.
+                out.append(&mut self.held);
+                self.state = Scan::Ground;
+                self.byte(b, out);
+            }
+            Scan::Sixel | Scan::ITerm | Scan::Kitty if b == 0x1b => {
+                self.state = match self.state {
+                    Scan::Sixel => Scan::SixelEscape,
+                    Scan::ITerm => Scan::ITermEscape,
+                    _ => Scan::KittyEscape,
+                };
+            }
+            Scan::ITerm if b == 0x07 => self.finish(out),
+            Scan::Sixel | Scan::ITerm | Scan::Kitty => self.collect(&[b]),
+            Scan::Discard if b == 0x1b => self.state = Scan::DiscardEscape,
+            Scan::Discard if b == 0x07 => self.state = Scan::Ground,
+            Scan::Discard => {}
+            Scan::DiscardEscape if b == b'\\' => self.state = Scan::Ground,
+            Scan::SixelEscape | Scan::ITermEscape | Scan::KittyEscape if b == b'\\' => {
+                self.finish(out);
+            }
+            Scan::Link if b == 0x07 => self.finish_link(out, b"\x07"),
+            Scan::Link if b == 0x1b => self.state = Scan::LinkEscape,
+            Scan::Link if self.held.len() < MAX_LINK => self.held.push(b),
+            Scan::LinkEscape if b == b'\\' => self.finish_link(out, b"\x1b\\"),
+            Scan::Link | Scan::LinkEscape => {
+                // Too long to keep, or cut short: pass it on untracked.
+                let escape = self.state == Scan::LinkEscape;
+                out.append(&mut self.held);
+                self.state = Scan::Ground;
+                if escape {
+                    self.byte(0x1b, out);
+                }
+                self.byte(b, out);
+            }
+            Scan::SixelEscape | Scan::ITermEscape | Scan::KittyEscape | Scan::DiscardEscape => {
+                // An escape sequence cut the image short; drop it.
+                self.payload.clear();
+                self.held.push(0x1b);
+                self.state = Scan::Escape;
+                self.byte(b, out);
+            }
+        }
+    }
+
+    fn in_payload(&self) -> bool {
+        matches!(self.state, Scan::Sixel | Scan::ITerm | Scan::Kitty | Scan::Discard)
+    }
+
+    /// Add image data, giving up on the image once it is too long.
+    fn collect(&mut self, data: &[u8]) {
+        if self.state == Scan::Discard {
+            return;
+        }
+        if self.payload.len() + data.len() <= MAX_PAYLOAD {
+            self.payload.extend_from_slice(data);
+        } else {
+            self.payload = Vec::new();
+            self.state = Scan::Discard;
+        }
+    }
+
+    /// An image sequence ended: act on it, then reopen the program's
+    /// hyperlink, which the anchors closed.
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        let start = out.len();
+        let state = std::mem::replace(&mut self.state, Scan::Ground);
+        let payload = std::mem::take(&mut self.payload);
+        match state {
+            Scan::SixelEscape => self.finish_sixel(&payload, out),
+            Scan::ITerm | Scan::ITermEscape => self.finish_iterm(&payload, out),
+            _ => self.kitty.command(&payload, &self.images, out),
+        }
+        if out.len() > start && let Some(link) = &self.link {
+            out.extend_from_slice(b"\x1b]8");
+            out.extend_from_slice(link);
+            out.extend_from_slice(b"\x1b\\");
+        }
+    }
+
+    /// Pass an OSC 8 on, remembering the link it opens, if any.
+    fn finish_link(&mut self, out: &mut Vec<u8>, terminator: &[u8]) {
+        // `ESC ] 8 ; params ; uri`: an empty URI closes the link.
+        let body = &self.held[3..];
+        let uri = body.splitn(3, |&b| b == b';').nth(2).unwrap_or_default();
+        self.link = (!uri.is_empty()).then(|| body.to_vec());
+        out.append(&mut self.held);
+        out.extend_from_slice(terminator);
+        self.state = Scan::Ground;
+    }
+
+    fn finish_sixel(&self, payload: &[u8], out: &mut Vec<u8>) {
+        let Some((width, height, rgba)) = sixel::decode(payload) else { return };
+        let ((cell_w, cell_h), _) = self.images.geometry();
+        let cells = (width as f32 / cell_w, height as f32 / cell_h);
+        let placement = Placement::whole(TermImage::new(width, height, rgba), cells);
+        self.images.place(out, placement, CursorAfter::Below);
+    }
+
+    fn finish_iterm(&self, payload: &[u8], out: &mut Vec<u8>) {
+        let geometry = self.images.geometry();
+        if let Some(Decoded { image, cells }) = iterm_image(payload, geometry) {
+            self.images.place(out, Placement::whole(image, cells), CursorAfter::Right);
+        }
+    }
+}
+
+/// Decode a PNG, JPEG or GIF file, shrunk to fit `sixel::MAX_SIDE`.
+pub fn decode_file(bytes: &[u8]) -> Option<TermImage> {
+    let mut image = image::load_from_memory(bytes).ok()?;
+    let side = sixel::MAX_SIDE as u32;
+    if image.width() > side || image.height() > side {
+        image = image.thumbnail(side, side);
+    }
+    let image = image.into_rgba8();
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return None;
+    }
+    Some(TermImage::new(width, height, image.into_raw()))
+}
+
+/// Decode an iTerm2 `File=` payload (`key=value;…:base64`) sized as its
+/// `width`/`height` arguments ask: cells, `px`, `%` of the terminal, or
+/// `auto`. Downloads (no `inline=1`) aren't supported and are dropped.
+fn iterm_image(
+    payload: &[u8],
+    ((cell_w, cell_h), (cols, rows)): ((f32, f32), (usize, usize)),
+) -> Option<Decoded> {
+    let colon = payload.iter().position(|&b| b == b':')?;
+    let args = std::str::from_utf8(&payload[..colon]).ok()?;
+    let args: HashMap<&str, &str> = args.split(';').filter_map(|a| a.split_once('=')).collect();
+    if args.get("inline") != Some(&"1") {
+        return None;
+    }
+    let data: Vec<u8> =
+        payload[colon + 1..].iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    let bytes = base64::engine::general_purpose::STANDARD.decode(data).ok()?;
+    let image = decode_file(&bytes)?;
+
+    let (area_w, area_h) = (cols as f32 * cell_w, rows as f32 * cell_h);
+    let dimension = |key: &str, cell: f32, area: f32| {
+        let value = args.get(key).copied().unwrap_or("auto");
+        if let Some(px) = value.strip_suffix("px") {
+            px.parse::<f32>().ok()
+        } else if let Some(pct) = value.strip_suffix('%') {
+            pct.parse::<f32>().ok().map(|p| p / 100.0 * area)
+        } else {
+            value.parse::<f32>().ok().map(|n| n * cell)
+        }
+        .filter(|v| *v > 0.0)
+    };
+    let (w, h) = (image.width as f32, image.height as f32);
+    let requested = (dimension("width", cell_w, area_w), dimension("height", cell_h, area_h));
+    let (mut draw_w, mut draw_h) = match requested {
+        (None, None) => (w, h),
+        (Some(dw), None) => (dw, h * dw / w),
+        (None, Some(dh)) => (w * dh / h, dh),
+        (Some(dw), Some(dh)) if args.get("preserveAspectRatio") == Some(&"0") => (dw, dh),
+        (Some(dw), Some(dh)) => {
+            let fit = (dw / w).min(dh / h);
+            (w * fit, h * fit)
+        }
+    };
+    // Never wider than the terminal.
+    if area_w > 0.0 && draw_w > area_w {
+        draw_h *= area_w / draw_w;
+        draw_w = area_w;
+    }
+    Some(Decoded { image, cells: (draw_w / cell_w, draw_h / cell_h) })
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::event::VoidListener;
+
+    use super::*;
+
+    #[test]
+    fn images_become_anchors() {
+        let images = Images::default();
+        images.set_geometry((10.0, 20.0), 80, 24);
+        let mut filter = ImageFilter::new(images.clone(), VoidListener, false);
+        let mut out = Vec::new();
+        // A 3x30 sixel (two cells tall), split mid-sequence, between text;
+        // other escapes pass through untouched.
+        filter.filter(b"a\x1b[1mb\x1bPq\"1;1;3;30#0!3~", &mut out);
+        filter.filter(b"\x1b\\c\x1b]0;title\x07", &mut out);
+
+        let id = *images.lock().placements.keys().next().unwrap();
+        let placement = images.get(id).unwrap();
+        assert_eq!((placement.image.width, placement.image.height), (3, 30));
+        assert_eq!(placement.cells, (0.3, 1.5));
+        let anchor = |row| format!("\x1b]8;;{ANCHOR_PREFIX}{id}:{row}\x1b\\ \x1b]8;;\x1b\\\x08");
+        let expected = format!("a\x1b[1mb{}\n{}\nc\x1b]0;title\x07", anchor(0), anchor(1));
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        // Anything cut short by another escape is dropped.
+        let mut out = Vec::new();
+        filter.filter(b"\x1b]1337;File=inline=1:AAAA\x1b[2J", &mut out);
+        assert_eq!(out, b"\x1b[2J");
+    }
+
+    #[test]
+    fn anchors_reopen_the_programs_link() {
+        let images = Images::default();
+        images.set_geometry((10.0, 20.0), 80, 24);
+        let mut filter = ImageFilter::new(images.clone(), VoidListener, false);
+        let mut out = Vec::new();
+        let link = "\x1b]8;id=1;https://example.com\x07";
+        filter.filter(format!("{link}a\x1bPq#0!3~\x1b\\b").as_bytes(), &mut out);
+        let id = *images.lock().placements.keys().next().unwrap();
+        // One anchor row; the space in it replaces whatever was there.
+        let anchor = format!("\x1b]8;;{ANCHOR_PREFIX}{id}:0\x1b\\ \x1b]8;;\x1b\\\x08");
+        let reopen = "\x1b]8;id=1;https://example.com\x1b\\";
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{link}a{anchor}\n{reopen}b"));
+
+        // Closed links stay closed.
+        let mut out = Vec::new();
+        filter.filter(b"\x1b]8;;\x1b\\\x1bPq#0!3~\x1b\\", &mut out);
+        assert!(out.ends_with(b"\x08\n"));
+    }
+
+    #[test]
+    fn oversized_sequences_are_swallowed_whole() {
+        let mut filter = ImageFilter::new(Images::default(), VoidListener, false);
+        let mut out = Vec::new();
+        filter.filter(b"a\x1b_Ga=T;", &mut out);
+        filter.filter(&vec![b'A'; MAX_PAYLOAD + 1], &mut out);
+        filter.filter(b"AAAA\x1b\\b", &mut out);
+        assert_eq!(out, b"ab");
+    }
+
+    #[test]
+    fn huge_images_are_shrunk_to_the_grid() {
+        let images = Images::default();
+        images.set_geometry((10.0, 20.0), 80, 24);
+        let mut filter = ImageFilter::new(images.clone(), VoidListener, false);
+        let mut png = Vec::new();
+        let mut cursor = std::io::Cursor::new(&mut png);
+        image::RgbaImage::new(1, 1).write_to(&mut cursor, image::ImageFormat::Png).unwrap();
+        let png = base64::engine::general_purpose::STANDARD.encode(png);
+        let mut out = Vec::new();
+        let args = "inline=1;width=1;height=999999999px;preserveAspectRatio=0";
+        let sequence = format!("\x1b]1337;File={args}:{png}\x07");
+        filter.filter(sequence.as_bytes(), &mut out);
+        let rows = out.iter().filter(|&&b| b == b'\n').count() + 1;
+        assert_eq!(rows, 4 * 24);
+    }
+}
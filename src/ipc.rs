@@ -0,0 +1,358 @@
+//! Remote control: a local socket (a named pipe on Windows) that lets
+//! scripts and `pfauterminal msg` drive the running terminal.
+//!
+//! Each connection carries one JSON request line and gets one JSON reply
+//! line back: `{"ok": true, "data": ...}` or `{"ok": false, "error": "..."}`.
+//! Requests are handed to the event loop as [`TerminalEvent::Ipc`]; the
+//! connection waits for the app to answer through the attached [`Reply`].
+//!
+//! Anything that can connect can type into every tab, so the socket lives
+//! in a directory only the user can open (a local-only pipe on Windows):
+//! every process running as the user has that power. `remote_control` in
+//! the settings turns the socket off.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use winit::event_loop::EventLoopProxy;
+
+use crate::cli::Options;
+use crate::terminal_panel::TerminalEvent;
+
+/// Set in every local tab, so `pfauterminal msg` run inside a tab talks to
+/// the instance that owns it.
+pub const SOCKET_ENV: &str = "PFAUTERMINAL_SOCKET";
+
+/// Longest request accepted; `send-text` payloads are the only big ones.
+const MAX_REQUEST: u64 = 16 * 1024 * 1024;
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long to wait for the event loop to answer (it may be busy with a
+/// modal dialog).
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A remote-control command. Tabs are addressed by the ids `list` reports;
+/// an omitted `tab` means the active one.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Request {
+    /// Every tab in order, with its id and title.
+    List,
+    /// Open a tab like the command line does; replies with its id.
+    Open(Options),
+    /// Activate a tab and raise the window.
+    Focus { tab: u64 },
+    /// Write text to a tab's shell or SSH channel as if typed.
+    SendText { tab: Option<u64>, text: String },
+    /// A tab's scrollback and screen, as plain text or with ANSI colors.
+    GetText {
+        tab: Option<u64>,
+        #[serde(default)]
+        ansi: bool,
+    },
+    /// Pin a tab title; `None` goes back to the title the shell sets.
+    SetTitle { tab: Option<u64>, title: Option<String> },
+    Close { tab: Option<u64> },
+}
+
+pub type Response = Result<Value, String>;
+
+/// Where the event loop sends its answer to a request.
+#[derive(Debug)]
+pub struct Reply(mpsc::Sender<Response>);
+
+impl Reply {
+    pub fn send(self, response: Response) {
+        let _ = self.0.send(response);
+    }
+}
+
+/// The listening socket. The socket file is removed when this is dropped.
+pub struct Server {
+    path: PathBuf,
+}
+
+impl Server {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Start listening for remote-control requests on a socket named after
+/// this process.
+pub fn spawn(proxy: EventLoopProxy<TerminalEvent>) -> Result<Server, String> {
+    let path = socket_path(std::process::id())?;
+    listen(&path, proxy).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(Server { path })
+}
+
+/// Parse a request line, pass it to the event loop and encode its answer.
+fn respond(line: &str, proxy: &EventLoopProxy<TerminalEvent>) -> String {
+    let response = (|| {
+        let request = parse_request(line)?;
+        let (tx, rx) = mpsc::channel();
+        proxy
+            .send_event(TerminalEvent::Ipc(request, Reply(tx)))
+            .map_err(|_| "the terminal is shutting down".to_string())?;
+        rx.recv_timeout(REPLY_TIMEOUT)
+            .map_err(|_| "the terminal did not answer".to_string())?
+    })();
+    let reply = match response {
+        Ok(data) => json!({ "ok": true, "data": data }),
+        Err(error) => json!({ "ok": false, "error": error }),
+    };
+    format!("{reply}\n")
+}
+
+fn parse_request(line: &str) -> Result<Request, String> {
+    let request: Request =
+        serde_json::from_str(line.trim()).map_err(|e| format!("invalid request: {e}"))?;
+    if let Request::Open(options) = &request {
+        options.validate().map_err(|e| format!("invalid request: {e}"))?;
+    }
+    Ok(request)
+}
+
+/// The user's runtime directory, or a per-user one in the shared temp
+/// directory where there is none.
+#[cfg(unix)]
+fn socket_dir() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("pfauterminal"),
+        // SAFETY: getuid has no preconditions and can't fail.
+        None => std::env::temp_dir().join(format!("pfauterminal-{}", unsafe { libc::getuid() })),
+    }
+}
+
+#[cfg(unix)]
+fn socket_path(pid: u32) -> Result<PathBuf, String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Only the owner may connect: anyone who can could type into the shells.
+    let dir = socket_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))
+        .map_err(|e| format!("{}: {e}", dir.display()))?;
+    Ok(dir.join(format!("{pid}.sock")))
+}
+
+#[cfg(unix)]
+fn listen(path: &Path, proxy: EventLoopProxy<TerminalEvent>) -> std::io::Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    // Left behind by an earlier process that had the same pid.
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            if reader.by_ref().take(MAX_REQUEST).read_line(&mut line).is_err() {
+                continue;
+            }
+            let _ = reader.get_mut().write_all(respond(&line, &proxy).as_bytes());
+        }
+    });
+    Ok(())
+}
+
+/// Sockets of running instances, newest first.
+#[cfg(unix)]
+fn candidates() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(socket_dir()) else { return Vec::new() };
+    let mut sockets: Vec<_> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "sock"))
+        .map(|e| (e.metadata().and_then(|m| m.modified()).ok(), e.path()))
+        .collect();
+    sockets.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    sockets.into_iter().map(|(_, path)| path).collect()
+}
+
+#[cfg(unix)]
+fn connect(path: &Path) -> std::io::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(path)
+}
+
+#[cfg(windows)]
+const PIPE_PREFIX: &str = r"\\.\pipe\pfauterminal-";
+
+#[cfg(windows)]
+fn socket_path(pid: u32) -> Result<PathBuf, String> {
+    Ok(PathBuf::from(format!("{PIPE_PREFIX}{pid}")))
+}
+
+#[cfg(windows)]
+fn listen(path: &Path, proxy: EventLoopProxy<TerminalEvent>) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+
+    async fn read_request(pipe: &NamedPipeServer) -> std::io::Result<String> {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        while !data.contains(&b'\n') && (data.len() as u64) < MAX_REQUEST {
+            pipe.readable().await?;
+            match pipe.try_read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => data.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(String::from_utf8_lossy(&data).into_owned())
+    }
+
+    async fn write_all(pipe: &NamedPipeServer, mut data: &[u8]) -> std::io::Result<()> {
+        while !data.is_empty() {
+            pipe.writable().await?;
+            match pipe.try_write(data) {
+                Ok(n) => data = &data[n..],
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    let name = path.as_os_str().to_owned();
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    // The first instance is created here so a name clash reaches the caller;
+    // `reject_remote_clients` (the default) keeps the pipe local.
+    let first = {
+        let _guard = runtime.enter();
+        ServerOptions::new().first_pipe_instance(true).create(&name)?
+    };
+    std::thread::spawn(move || {
+        runtime.block_on(async move {
+            let mut pipe = first;
+            loop {
+                let connected = pipe.connect().await;
+                // Open the next instance before serving, so a second client
+                // never finds the name missing.
+                let Ok(next) = ServerOptions::new().create(&name) else { return };
+                let client = std::mem::replace(&mut pipe, next);
+                if connected.is_err() {
+                    continue;
+                }
+                let Ok(Ok(line)) =
+                    tokio::time::timeout(READ_TIMEOUT, read_request(&client)).await
+                else {
+                    continue;
+                };
+                let _ = write_all(&client, respond(&line, &proxy).as_bytes()).await;
+            }
+        });
+    });
+    Ok(())
+}
+
+#[cfg(windows)]
+fn candidates() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(r"\\.\pipe\") else { return Vec::new() };
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with("pfauterminal-"))
+        .map(|e| PathBuf::from(r"\\.\pipe\").join(e.file_name()))
+        .collect()
+}
+
+#[cfg(windows)]
+fn connect(path: &Path) -> std::io::Result<std::fs::File> {
+    std::fs::OpenOptions::new().read(true).write(true).open(path)
+}
+
+/// Send one request to a running instance and return its answer. Without an
+/// explicit socket, `$PFAUTERMINAL_SOCKET` is used, then the most recently
+/// started instance that accepts the connection.
+pub fn send(socket: Option<PathBuf>, request: &Request) -> Response {
+    let line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    let paths = match socket.or_else(|| std::env::var_os(SOCKET_ENV).map(PathBuf::from)) {
+        Some(path) => vec![path],
+        None => candidates(),
+    };
+    let mut error = "no running pfauterminal found".to_string();
+    for path in paths {
+        match connect(&path) {
+            Ok(stream) => return exchange(stream, &line),
+            Err(e) => error = format!("{}: {e}", path.display()),
+        }
+    }
+    Err(error)
+}
+
+fn exchange(mut stream: impl Read + Write, line: &str) -> Response {
+    stream
+        .write_all(format!("{line}\n").as_bytes())
+        .and_then(|_| stream.flush())
+        .map_err(|e| e.to_string())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).map_err(|e| e.to_string())?;
+    let reply: Value =
+        serde_json::from_str(&reply).map_err(|_| "the terminal sent no answer".to_string())?;
+    if reply["ok"] == true {
+        Ok(reply["data"].clone())
+    } else {
+        Err(reply["error"].as_str().unwrap_or("request failed").to_string())
+    }
+}
+
+/// Run a `pfauterminal msg` command and return what to print.
+pub fn run_client(socket: Option<PathBuf>, request: Request) -> Result<String, String> {
+    let data = send(socket, &request)?;
+    Ok(match request {
+        Request::List => data
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|tab| {
+                let marker = if tab["active"] == true { '*' } else { ' ' };
+                let title = tab["title"].as_str().unwrap_or_default();
+                format!("{marker} {}\t{title}\n", tab["id"])
+            })
+            .collect(),
+        Request::Open(_) => format!("{}\n", data["id"]),
+        Request::GetText { .. } => data.as_str().unwrap_or_default().to_string(),
+        _ => String::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_json() {
+        let request: Request =
+            serde_json::from_str(r#"{"cmd": "send-text", "text": "ls\n"}"#).unwrap();
+        assert_eq!(request, Request::SendText { tab: None, text: "ls\n".into() });
+
+        let request: Request =
+            serde_json::from_str(r#"{"cmd": "open", "ssh": "me@db:2222", "title": "db"}"#)
+                .unwrap();
+        let Request::Open(options) = request else { panic!() };
+        assert_eq!(options.ssh.unwrap().port, Some(2222));
+        assert_eq!(options.title.as_deref(), Some("db"));
+
+        let request = Request::GetText { tab: Some(3), ansi: true };
+        let line = serde_json::to_string(&request).unwrap();
+        assert_eq!(serde_json::from_str::<Request>(&line).unwrap(), request);
+        assert!(serde_json::from_str::<Request>(r#"{"cmd": "focus"}"#).is_err());
+
+        // Typos and what `parse` would reject don't get through either.
+        assert!(parse_request(r#"{"cmd": "close", "tba": 3}"#).is_err());
+        assert!(parse_request(r#"{"cmd": "open", "titel": "db"}"#).is_err());
+        assert!(parse_request(r#"{"cmd": "open", "ssh": "db", "command": ["ls"]}"#).is_err());
+        assert!(parse_request(r#"{"cmd": "open", "command": []}"#).is_err());
+    }
+}
@@ -0,0 +1,684 @@
+//! The kitty graphics protocol: `ESC _ G <key>=<value>,… ; <payload> ESC \`
+//! commands that transmit images (inline base64, possibly in chunks, or
+//! from files and shared memory on local tabs), place them at the cursor or
+//! through Unicode placeholder cells, and delete them. The image filter
+//! hands every command here; what they make goes into the tab's [`Images`].
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use alacritty_terminal::event::{Event, EventListener};
+use alacritty_terminal::vte::ansi::Color;
+use base64::Engine;
+use flate2::read::ZlibDecoder;
+
+use crate::images::{self, CursorAfter, Delete, Images, Placement, TermImage, VirtualPlacement};
+use crate::sixel::MAX_SIDE;
+
+/// The character of a Unicode placeholder cell.
+pub const PLACEHOLDER: char = '\u{10EEEE}';
+
+/// Most image data accepted, before and after decompression.
+const MAX_DATA: usize = MAX_SIDE * MAX_SIDE * 4;
+
+/// One command's keys. Numbers left out are 0, as the protocol has it.
+#[derive(Clone, Default)]
+struct Command {
+    /// `a`: transmit, transmit and put, query, put or delete.
+    action: u8,
+    /// `t`: direct, file, temporary file or shared memory.
+    medium: u8,
+    /// `f`: 24 (RGB), 32 (RGBA) or 100 (PNG).
+    format: u32,
+    /// `o=z`: zlib compressed.
+    compressed: bool,
+    /// `m=1`: more chunks follow.
+    more: bool,
+    /// `q`: 1 silences OK replies, 2 errors as well.
+    quiet: u32,
+    /// `i`, `I` and `p`: image id, image number and placement id.
+    id: u32,
+    number: u32,
+    placement: u32,
+    /// `s` and `v`: size of raw pixel data.
+    width: u32,
+    height: u32,
+    /// `S` and `O`: how much to read from a file, and from where.
+    size: usize,
+    offset: usize,
+    /// `x`, `y`, `w` and `h`: the part of the image to show. Deletes use
+    /// `x` and `y` for cells and ranges.
+    source: [u32; 4],
+    /// `c` and `r`: columns and rows to fit the image into.
+    cols: u32,
+    rows: u32,
+    /// `X` and `Y`: pixel offset within the first cell.
+    pixel_offset: (u32, u32),
+    /// `z`: negative goes under the text.
+    z: i32,
+    /// `C=1`: leave the cursor where it is.
+    stay: bool,
+    /// `U=1`: a virtual placement, shown by placeholder cells.
+    virtual_placement: bool,
+    /// `d`: what to delete; uppercase frees the image data too.
+    delete: u8,
+}
+
+impl Command {
+    fn parse(keys: &[u8]) -> Self {
+        let mut cmd =
+            Command { action: b't', medium: b'd', format: 32, delete: b'a', ..Default::default() };
+        for pair in keys.split(|&b| b == b',') {
+            let [key, b'=', value @ ..] = pair else { continue };
+            let text = std::str::from_utf8(value).unwrap_or_default();
+            let number = text.parse::<u32>().unwrap_or(0);
+            let first = value.first().copied().unwrap_or(0);
+            match key {
+                b'a' => cmd.action = first,
+                b't' => cmd.medium = first,
+                b'f' => cmd.format = number,
+                b'o' => cmd.compressed = first == b'z',
+                b'm' => cmd.more = number == 1,
+                b'q' => cmd.quiet = number,
+                b'i' => cmd.id = number,
+                b'I' => cmd.number = number,
+                b'p' => cmd.placement = number,
+                b's' => cmd.width = number,
+                b'v' => cmd.height = number,
+                b'S' => cmd.size = number as usize,
+                b'O' => cmd.offset = number as usize,
+                b'x' => cmd.source[0] = number,
+                b'y' => cmd.source[1] = number,
+                b'w' => cmd.source[2] = number,
+                b'h' => cmd.source[3] = number,
+                b'c' => cmd.cols = number,
+                b'r' => cmd.rows = number,
+                b'X' => cmd.pixel_offset.0 = number,
+                b'Y' => cmd.pixel_offset.1 = number,
+                b'z' => cmd.z = text.parse().unwrap_or(0),
+                b'C' => cmd.stay = number == 1,
+                b'U' => cmd.virtual_placement = number == 1,
+                b'd' => cmd.delete = first,
+                _ => {}
+            }
+        }
+        cmd
+    }
+}
+
+/// A tab's side of the protocol: replies to the program and the chunked
+/// transmission in progress.
+pub struct KittyGraphics {
+    replies: Box<dyn EventListener + Send>,
+    local_files: bool,
+    /// The first command of a chunked transmission and the data so far;
+    /// `Err` once a chunk was bad.
+    chunked: Option<(Command, Result<Vec<u8>, String>)>,
+}
+
+impl KittyGraphics {
+    pub fn new(replies: Box<dyn EventListener + Send>, local_files: bool) -> Self {
+        Self { replies, local_files, chunked: None }
+    }
+
+    /// Run one command: everything between `ESC _ G` and `ESC \`.
+    pub fn command(&mut self, body: &[u8], images: &Images, out: &mut Vec<u8>) {
+        let (keys, payload) = match body.iter().position(|&b| b == b';') {
+            Some(semicolon) => (&body[..semicolon], &body[semicolon + 1..]),
+            None => (body, &[][..]),
+        };
+        let cmd = Command::parse(keys);
+        let chunk = decode_base64(payload);
+
+        // Chunks after the first carry nothing but `m` and `q`.
+        if let Some((first, data)) = self.chunked.take() {
+            let data = data.and_then(|mut data| {
+                data.extend(chunk?);
+                match data.len() {
+                    len if len > MAX_DATA => Err("EFBIG:too much image data".to_string()),
+                    _ => Ok(data),
+                }
+            });
+            match cmd.more {
+                true => self.chunked = Some((first, data)),
+                false => self.run(first, data, images, out),
+            }
+        } else if cmd.more && matches!(cmd.action, b't' | b'T' | b'q') {
+            self.chunked = Some((cmd, chunk));
+        } else {
+            self.run(cmd, chunk, images, out);
+        }
+    }
+
+    fn run(
+        &self,
+        mut cmd: Command,
+        data: Result<Vec<u8>, String>,
+        images: &Images,
+        out: &mut Vec<u8>,
+    ) {
+        let result = match cmd.action {
+            b't' | b'T' | b'q' => self.transmit(&mut cmd, data, images, out),
+            b'p' => put(&mut cmd, images, out),
+            b'd' => {
+                delete(&cmd, images);
+                return;
+            }
+            _ => Err("EINVAL:animation is not supported".to_string()),
+        };
+        self.reply(&cmd, result);
+    }
+
+    /// Load an image and store it (`a=t`), store and put it (`a=T`), or
+    /// only check that it loads (`a=q`).
+    fn transmit(
+        &self,
+        cmd: &mut Command,
+        data: Result<Vec<u8>, String>,
+        images: &Images,
+        out: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        let image = self.load(cmd, data?)?;
+        if cmd.action == b'q' {
+            return Ok(());
+        }
+        // Images sent by number get a fresh id and are told it; those sent
+        // with neither can only be shown right away.
+        let id = match (cmd.id, cmd.number) {
+            (0, 0) => images.unused_kitty_id(),
+            (0, _) => {
+                cmd.id = images.unused_kitty_id();
+                cmd.id
+            }
+            (id, _) => id,
+        };
+        images.add_kitty(id, cmd.number, image);
+        if cmd.action == b'T' {
+            let image = images.kitty_image(id).ok_or("ENOENT:image was dropped")?;
+            place(cmd, id, image, images, out)?;
+        }
+        Ok(())
+    }
+
+    /// The image a transmission describes, read from wherever it is.
+    fn load(&self, cmd: &Command, data: Vec<u8>) -> Result<TermImage, String> {
+        let data = match cmd.medium {
+            b'd' => data,
+            b'f' | b't' | b's' if !self.local_files => {
+                return Err("EBADF:only direct transmission works here".to_string());
+            }
+            b'f' | b't' | b's' => {
+                let name = String::from_utf8(data).map_err(|_| "EINVAL:bad file name")?;
+                match cmd.medium {
+                    b's' => read_shared_memory(&name, cmd.offset, cmd.size)?,
+                    medium => read_file(Path::new(&name), medium == b't', cmd.offset, cmd.size)?,
+                }
+            }
+            _ => return Err("EINVAL:unknown transmission medium".to_string()),
+        };
+        let data = if cmd.compressed { inflate(&data)? } else { data };
+
+        let channels = match cmd.format {
+            100 => return images::decode_file(&data).ok_or_else(|| "EBADPNG:bad image".into()),
+            24 => 3,
+            32 => 4,
+            _ => return Err("EINVAL:unknown format".to_string()),
+        };
+        let (width, height) = (cmd.width as usize, cmd.height as usize);
+        if !(1..=MAX_SIDE).contains(&width) || !(1..=MAX_SIDE).contains(&height) {
+            return Err("EINVAL:bad image size".to_string());
+        }
+        let Some(pixels) = data.get(..width * height * channels) else {
+            return Err("ENODATA:not enough image data".to_string());
+        };
+        let rgba = match channels {
+            3 => pixels.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+            _ => pixels.to_vec(),
+        };
+        Ok(TermImage::new(cmd.width, cmd.height, rgba))
+    }
+
+    /// Answer a command that named an image, unless `q` says not to.
+    fn reply(&self, cmd: &Command, result: Result<(), String>) {
+        let wanted = match result {
+            Ok(()) => cmd.quiet == 0,
+            Err(_) => cmd.quiet < 2,
+        };
+        if !wanted || (cmd.id == 0 && cmd.number == 0) {
+            return;
+        }
+        let mut keys = format!("i={}", cmd.id);
+        if cmd.number != 0 {
+            let _ = write!(keys, ",I={}", cmd.number);
+        }
+        if cmd.placement != 0 {
+            let _ = write!(keys, ",p={}", cmd.placement);
+        }
+        let message = result.err().unwrap_or_else(|| "OK".to_string());
+        self.replies.send_event(Event::PtyWrite(format!("\x1b_G{keys};{message}\x1b\\")));
+    }
+}
+
+/// Put a stored image (`a=p`), named by id or by number.
+fn put(cmd: &mut Command, images: &Images, out: &mut Vec<u8>) -> Result<(), String> {
+    if cmd.id == 0 && cmd.number != 0 {
+        cmd.id = images.kitty_id(cmd.number).ok_or("ENOENT:no image with this number")?;
+    }
+    let image = images.kitty_image(cmd.id).ok_or("ENOENT:no image with this id")?;
+    place(cmd, cmd.id, image, images, out)
+}
+
+/// Place an image at the cursor, or set up its virtual placement.
+fn place(
+    cmd: &Command,
+    id: u32,
+    image: std::sync::Arc<TermImage>,
+    images: &Images,
+    out: &mut Vec<u8>,
+) -> Result<(), String> {
+    let ((cell_w, cell_h), _) = images.geometry();
+    if cmd.virtual_placement {
+        let fit = |cells: u32, px: u32, cell: f32| match cells {
+            0 => (px as f32 / cell).ceil() as u32,
+            n => n,
+        };
+        let cols = fit(cmd.cols, image.width, cell_w).max(1);
+        let rows = fit(cmd.rows, image.height, cell_h).max(1);
+        images.set_virtual(id, cmd.placement, VirtualPlacement { cols, rows, z: cmd.z });
+        return Ok(());
+    }
+
+    let (offset_x, offset_y) = (cmd.pixel_offset.0 as f32, cmd.pixel_offset.1 as f32);
+    if offset_x >= cell_w || offset_y >= cell_h {
+        return Err("EINVAL:offset must be within the first cell".to_string());
+    }
+    let [x, y, w, h] = cmd.source;
+    let (x, y) = (x.min(image.width), y.min(image.height));
+    let w = if w == 0 { image.width - x } else { w.min(image.width - x) };
+    let h = if h == 0 { image.height - y } else { h.min(image.height - y) };
+    if w == 0 || h == 0 {
+        return Err("EINVAL:empty source rectangle".to_string());
+    }
+    let natural = (w as f32 / cell_w, h as f32 / cell_h);
+    let cells = match (cmd.cols, cmd.rows) {
+        (0, 0) => natural,
+        (c, 0) => (c as f32, c as f32 * natural.1 / natural.0),
+        (0, r) => (r as f32 * natural.0 / natural.1, r as f32),
+        (c, r) => (c as f32, r as f32),
+    };
+    let (iw, ih) = (image.width as f32, image.height as f32);
+    let placement = Placement {
+        uv: [x as f32 / iw, y as f32 / ih, (x + w) as f32 / iw, (y + h) as f32 / ih],
+        image,
+        cells,
+        offset: (offset_x / cell_w, offset_y / cell_h),
+        z: cmd.z,
+        kitty: Some((id, cmd.placement)),
+    };
+    let after = if cmd.stay { CursorAfter::Stay } else { CursorAfter::Right };
+    images.place(out, placement, after);
+    Ok(())
+}
+
+fn delete(cmd: &Command, images: &Images) {
+    let [x, y, ..] = cmd.source;
+    let placement = (cmd.placement != 0).then_some(cmd.placement);
+    let delete = match cmd.delete.to_ascii_lowercase() {
+        b'a' => Delete::Visible,
+        b'i' => Delete::Image(cmd.id, placement),
+        b'n' => Delete::Number(cmd.number, placement),
+        b'c' => Delete::Cursor,
+        b'p' => Delete::Cell(x, y, None),
+        b'q' => Delete::Cell(x, y, Some(cmd.z)),
+        b'x' => Delete::Column(x),
+        b'y' => Delete::Row(y),
+        b'r' => Delete::Range(x, y),
+        b'z' => Delete::Z(cmd.z),
+        // Animation frames; there are none.
+        _ => return,
+    };
+    images.delete(delete, cmd.delete.is_ascii_uppercase());
+}
+
+fn decode_base64(payload: &[u8]) -> Result<Vec<u8>, String> {
+    let data: Vec<u8> = payload.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    let engine = &base64::engine::general_purpose::STANDARD;
+    engine.decode(data).map_err(|_| "EINVAL:bad base64 data".to_string())
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut decoder = ZlibDecoder::new(data).take(MAX_DATA as u64 + 1);
+    decoder.read_to_end(&mut out).map_err(|e| format!("EINVAL:{e}"))?;
+    if out.len() > MAX_DATA {
+        return Err("EFBIG:too much image data".to_string());
+    }
+    Ok(out)
+}
+
+/// Read an image file (`t=f`), or a temporary one that is deleted after
+/// (`t=t`). Temporary files must be in a temp directory and have
+/// `tty-graphics-protocol` in their name, so nothing else gets deleted.
+fn read_file(path: &Path, temporary: bool, offset: usize, size: usize) -> Result<Vec<u8>, String> {
+    let path = path.canonicalize().map_err(|e| format!("ENOENT:{e}"))?;
+    if ["/proc", "/sys", "/dev"].iter().any(|dir| path.starts_with(dir)) {
+        return Err("EPERM:not an image file".to_string());
+    }
+    if temporary {
+        let temp_dirs = [std::env::temp_dir(), "/tmp".into(), "/dev/shm".into()];
+        let in_temp = temp_dirs
+            .iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .any(|dir| path.starts_with(dir));
+        let named = path.to_string_lossy().contains("tty-graphics-protocol");
+        if !in_temp || !named {
+            return Err("EPERM:not a temporary file".to_string());
+        }
+    }
+    // Opening a FIFO would wait for a writer and stall the tab's output, so
+    // check first, and open without blocking in case the path changes.
+    if !fs::metadata(&path).is_ok_and(|m| m.is_file()) {
+        return Err("EINVAL:not a regular file".to_string());
+    }
+    let mut options = fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, libc::O_NONBLOCK);
+    let mut file = options.open(&path).map_err(|e| format!("EBADF:{e}"))?;
+    if !file.metadata().is_ok_and(|m| m.is_file()) {
+        return Err("EINVAL:not a regular file".to_string());
+    }
+    let limit = if size > 0 { size.min(MAX_DATA) } else { MAX_DATA };
+    let mut data = Vec::new();
+    file.seek(SeekFrom::Start(offset as u64)).map_err(|e| format!("EINVAL:{e}"))?;
+    file.take(limit as u64).read_to_end(&mut data).map_err(|e| format!("EBADF:{e}"))?;
+    if temporary {
+        let _ = fs::remove_file(&path);
+    }
+    Ok(data)
+}
+
+/// Read a POSIX shared memory object (`t=s`) and unlink it, as the program
+/// expects the terminal to.
+#[cfg(unix)]
+fn read_shared_memory(name: &str, offset: usize, size: usize) -> Result<Vec<u8>, String> {
+    let name = std::ffi::CString::new(name).map_err(|_| "EINVAL:bad shared memory name")?;
+    // SAFETY: the object is mapped read-only, copied out and unmapped
+    // before its descriptor is closed.
+    unsafe {
+        #[cfg(target_os = "macos")]
+        let fd = libc::shm_open(name.as_ptr(), libc::O_RDONLY);
+        #[cfg(not(target_os = "macos"))]
+        let fd = libc::shm_open(name.as_ptr(), libc::O_RDONLY, 0);
+        if fd < 0 {
+            return Err(format!("ENOENT:{}", std::io::Error::last_os_error()));
+        }
+        libc::shm_unlink(name.as_ptr());
+        let mut stat: libc::stat = std::mem::zeroed();
+        let len = if libc::fstat(fd, &mut stat) == 0 { stat.st_size as usize } else { 0 };
+        let end = if size > 0 { offset.saturating_add(size).min(len) } else { len };
+        let result = if offset >= end || end - offset > MAX_DATA {
+            Err("ENODATA:bad shared memory size".to_string())
+        } else {
+            let (protection, flags) = (libc::PROT_READ, libc::MAP_SHARED);
+            let map = libc::mmap(std::ptr::null_mut(), end, protection, flags, fd, 0);
+            if map == libc::MAP_FAILED {
+                Err(format!("EBADF:{}", std::io::Error::last_os_error()))
+            } else {
+                let bytes = std::slice::from_raw_parts(map.cast::<u8>().add(offset), end - offset);
+                let data = bytes.to_vec();
+                libc::munmap(map, end);
+                Ok(data)
+            }
+        };
+        libc::close(fd);
+        result
+    }
+}
+
+#[cfg(not(unix))]
+fn read_shared_memory(_name: &str, _offset: usize, _size: usize) -> Result<Vec<u8>, String> {
+    Err("EINVAL:shared memory is not supported here".to_string())
+}
+
+/// A placeholder cell as the panel found it on screen.
+pub struct PlaceholderCell {
+    pub line: i32,
+    pub col: usize,
+    pub fg: Color,
+    pub underline: Option<Color>,
+    pub marks: Vec<char>,
+}
+
+/// The piece of an image a placeholder cell shows: the image, placement
+/// and the row and column of the placement's cell grid.
+pub struct ImagePiece {
+    pub line: i32,
+    pub col: usize,
+    pub image: u32,
+    pub placement: u32,
+    pub row: u32,
+    pub column: u32,
+}
+
+/// Work out what each placeholder cell (in screen order) shows. The image
+/// id is the foreground color, the placement id the underline color, and
+/// combining marks give the row, the column and the id's high byte; marks
+/// left off carry on from the cell to the left.
+pub fn image_pieces(cells: &[PlaceholderCell]) -> Vec<ImagePiece> {
+    let mut pieces: Vec<ImagePiece> = Vec::new();
+    for cell in cells {
+        let Some(image) = color_id(cell.fg) else { continue };
+        let placement = cell.underline.and_then(color_id).unwrap_or(0);
+        let mut marks = cell.marks.iter().filter_map(|&c| diacritic_index(c));
+        let (row, column, high) = (marks.next(), marks.next(), marks.next());
+        let left = pieces.last().filter(|p| {
+            p.line == cell.line
+                && p.col + 1 == cell.col
+                && p.placement == placement
+                && p.image & 0xff_ffff == image
+        });
+        let (row, column, high) = match left {
+            Some(left) if row.is_none_or(|r| r == left.row) => {
+                (left.row, column.unwrap_or(left.column + 1), high.unwrap_or(left.image >> 24))
+            }
+            _ => (row.unwrap_or(0), column.unwrap_or(0), high.unwrap_or(0)),
+        };
+        let image = image | high << 24;
+        pieces.push(ImagePiece { line: cell.line, col: cell.col, image, placement, row, column });
+    }
+    pieces
+}
+
+/// The id a placeholder's color encodes: 24-bit colors carry it as RGB,
+/// 256-color ones as the index.
+fn color_id(color: Color) -> Option<u32> {
+    match color {
+        Color::Spec(rgb) => Some(u32::from(rgb.r) << 16 | u32::from(rgb.g) << 8 | u32::from(rgb.b)),
+        Color::Indexed(index) => Some(u32::from(index)),
+        Color::Named(_) => None,
+    }
+}
+
+fn diacritic_index(c: char) -> Option<u32> {
+    DIACRITICS.binary_search(&u32::from(c)).ok().map(|i| i as u32)
+}
+
+/// Combining marks numbering placeholder rows and columns, in order, from
+/// kitty's `rowcolumn-diacritics.txt`.
+const DIACRITICS: [u32; 297] = [
+    0x0305, 0x030D, 0x030E, 0x0310, 0x0312, 0x033D, 0x033E, 0x033F,
+    0x0346, 0x034A, 0x034B, 0x034C, 0x0350, 0x0351, 0x0352, 0x0357,
+    0x035B, 0x0363, 0x0364, 0x0365, 0x0366, 0x0367, 0x0368, 0x0369,
+    0x036A, 0x036B, 0x036C, 0x036D, 0x036E, 0x036F, 0x0483, 0x0484,
+    0x0485, 0x0486, 0x0487, 0x0592, 0x0593, 0x0594, 0x0595, 0x0597,
+    0x0598, 0x0599, 0x059C, 0x059D, 0x059E, 0x059F, 0x05A0, 0x05A1,
+    0x05A8, 0x05A9, 0x05AB, 0x05AC, 0x05AF, 0x05C4, 0x0610, 0x0611,
+    0x0612, 0x0613, 0x0614, 0x0615, 0x0616, 0x0617, 0x0657, 0x0658,
+    0x0659, 0x065A, 0x065B, 0x065D, 0x065E, 0x06D6, 0x06D7, 0x06D8,
+    0x06D9, 0x06DA, 0x06DB, 0x06DC, 0x06DF, 0x06E0, 0x06E1, 0x06E2,
+    0x06E4, 0x06E7, 0x06E8, 0x06EB, 0x06EC, 0x0730, 0x0732, 0x0733,
+    0x0735, 0x0736, 0x073A, 0x073D, 0x073F, 0x0740, 0x0741, 0x0743,
+    0x0745, 0x0747, 0x0749, 0x074A, 0x07EB, 0x07EC, 0x07ED, 0x07EE,
+    0x07EF, 0x07F0, 0x07F1, 0x07F3, 0x0816, 0x0817, 0x0818, 0x0819,
+    0x081B, 0x081C, 0x081D, 0x081E, 0x081F, 0x0820, 0x0821, 0x0822,
+    0x0823, 0x0825, 0x0826, 0x0827, 0x0829, 0x082A, 0x082B, 0x082C,
+    0x082D, 0x0951, 0x0953, 0x0954, 0x0F82, 0x0F83, 0x0F86, 0x0F87,
+    0x135D, 0x135E, 0x135F, 0x17DD, 0x193A, 0x1A17, 0x1A75, 0x1A76,
+    0x1A77, 0x1A78, 0x1A79, 0x1A7A, 0x1A7B, 0x1A7C, 0x1B6B, 0x1B6D,
+    0x1B6E, 0x1B6F, 0x1B70, 0x1B71, 0x1B72, 0x1B73, 0x1CD0, 0x1CD1,
+    0x1CD2, 0x1CDA, 0x1CDB, 0x1CE0, 0x1DC0, 0x1DC1, 0x1DC3, 0x1DC4,
+    0x1DC5, 0x1DC6, 0x1DC7, 0x1DC8, 0x1DC9, 0x1DCB, 0x1DCC, 0x1DD1,
+    0x1DD2, 0x1DD3, 0x1DD4, 0x1DD5, 0x1DD6, 0x1DD7, 0x1DD8, 0x1DD9,
+    0x1DDA, 0x1DDB, 0x1DDC, 0x1DDD, 0x1DDE, 0x1DDF, 0x1DE0, 0x1DE1,
+    0x1DE2, 0x1DE3, 0x1DE4, 0x1DE5, 0x1DE6, 0x1DFE, 0x20D0, 0x20D1,
+    0x20D4, 0x20D5, 0x20D6, 0x20D7, 0x20DB, 0x20DC, 0x20E1, 0x20E7,
+    0x20E9, 0x20F0, 0x2CEF, 0x2CF0, 0x2CF1, 0x2DE0, 0x2DE1, 0x2DE2,
+    0x2DE3, 0x2DE4, 0x2DE5, 0x2DE6, 0x2DE7, 0x2DE8, 0x2DE9, 0x2DEA,
+    0x2DEB, 0x2DEC, 0x2DED, 0x2DEE, 0x2DEF, 0x2DF0, 0x2DF1, 0x2DF2,
+    0x2DF3, 0x2DF4, 0x2DF5, 0x2DF6, 0x2DF7, 0x2DF8, 0x2DF9, 0x2DFA,
+    0x2DFB, 0x2DFC, 0x2DFD, 0x2DFE, 0x2DFF, 0xA66F, 0xA67C, 0xA67D,
+    0xA6F0, 0xA6F1, 0xA8E0, 0xA8E1, 0xA8E2, 0xA8E3, 0xA8E4, 0xA8E5,
+    0xA8E6, 0xA8E7, 0xA8E8, 0xA8E9, 0xA8EA, 0xA8EB, 0xA8EC, 0xA8ED,
+    0xA8EE, 0xA8EF, 0xA8F0, 0xA8F1, 0xAAB0, 0xAAB2, 0xAAB3, 0xAAB7,
+    0xAAB8, 0xAABE, 0xAABF, 0xAAC1, 0xFE20, 0xFE21, 0xFE22, 0xFE23,
+    0xFE24, 0xFE25, 0xFE26, 0x10A0F, 0x10A38, 0x1D185, 0x1D186, 0x1D187,
+    0x1D188, 0x1D189, 0x1D1AA, 0x1D1AB, 0x1D1AC, 0x1D1AD, 0x1D242, 0x1D243,
+    0x1D244,
+];
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use alacritty_terminal::vte::ansi::Rgb;
+
+    use super::*;
+
+    /// Collects replies as the program would read them.
+    #[derive(Clone, Default)]
+    struct Replies(Arc<Mutex<Vec<String>>>);
+
+    impl EventListener for Replies {
+        fn send_event(&self, event: Event) {
+            if let Event::PtyWrite(text) = event {
+                self.0.lock().unwrap().push(text);
+            }
+        }
+    }
+
+    #[test]
+    fn transmits_in_chunks_replies_and_deletes() {
+        let images = Images::default();
+        images.set_geometry((10.0, 20.0), 80, 24);
+        let replies = Replies::default();
+        let mut kitty = KittyGraphics::new(Box::new(replies.clone()), false);
+        let mut out = Vec::new();
+        // A black and a white RGB pixel, sent in two chunks and put at once.
+        kitty.command(b"a=T,f=24,s=2,v=1,i=7,m=1;AAAA", &images, &mut out);
+        assert!(out.is_empty());
+        kitty.command(b"m=0;////", &images, &mut out);
+        assert_eq!(images.kitty_image(7).unwrap().rgba, [0, 0, 0, 255, 255, 255, 255, 255]);
+        assert_eq!(replies.0.lock().unwrap()[0], "\x1b_Gi=7;OK\x1b\\");
+        // One anchor, then the cursor steps past the image.
+        assert!(out.ends_with(b"\x08\x1b[1C"));
+
+        // Files are off limits here; q=2 keeps the second failure quiet.
+        kitty.command(b"a=t,t=f,i=8;L2V0Yy9wYXNzd2Q=", &images, &mut out);
+        kitty.command(b"a=t,t=f,i=9,q=2;L2V0Yy9wYXNzd2Q=", &images, &mut out);
+        let replies = replies.0.lock().unwrap();
+        assert_eq!(replies[1], "\x1b_Gi=8;EBADF:only direct transmission works here\x1b\\");
+        assert_eq!(replies.len(), 2);
+
+        kitty.command(b"a=d,d=I,i=7", &images, &mut out);
+        assert!(images.kitty_image(7).is_none());
+    }
+
+    #[test]
+    fn sizes_and_offsets_are_bounded() {
+        let images = Images::default();
+        images.set_geometry((10.0, 20.0), 80, 24);
+        let replies = Replies::default();
+        let mut kitty = KittyGraphics::new(Box::new(replies.clone()), false);
+        let mut out = Vec::new();
+        // A billion rows would be a billion anchors; it gets four screens.
+        kitty.command(b"a=T,f=24,s=1,v=1,i=3,r=1000000000;AAAA", &images, &mut out);
+        assert_eq!(out.iter().filter(|&&b| b == b'\n').count() + 1, 4 * 24);
+
+        out.clear();
+        kitty.command(b"a=p,i=3,Y=20", &images, &mut out);
+        assert!(out.is_empty());
+        let replies = replies.0.lock().unwrap();
+        assert_eq!(replies[1], "\x1b_Gi=3;EINVAL:offset must be within the first cell\x1b\\");
+    }
+
+    #[test]
+    fn staying_placements_never_scroll() {
+        use alacritty_terminal::event::VoidListener;
+        use alacritty_terminal::grid::Dimensions;
+        use alacritty_terminal::index::{Column, Line};
+        use alacritty_terminal::term::{Config, Term};
+        use alacritty_terminal::vte::ansi::{Processor, StdSyncHandler};
+
+        use crate::terminal_panel::TermSize;
+
+        let images = Images::default();
+        images.set_geometry((10.0, 20.0), 80, 24);
+        let mut kitty = KittyGraphics::new(Box::new(Replies::default()), false);
+        let mut out = b"top\x1b[24;5H".to_vec();
+        // Five rows tall, put on the last row with the cursor left alone.
+        kitty.command(b"a=T,f=24,s=1,v=1,i=5,r=5,C=1,q=2;AAAA", &images, &mut out);
+        let mut term = Term::new(Config::default(), &TermSize::new(80, 24), VoidListener);
+        Processor::<StdSyncHandler>::new().advance(&mut term, &out);
+
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, 't');
+        let cursor = term.grid().cursor.point;
+        assert_eq!((cursor.line, cursor.column), (Line(term.screen_lines() as i32 - 1), Column(4)));
+        assert!(images::anchor(&term.grid()[Line(23)][Column(4)]).is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fifos_are_refused_without_blocking() {
+        let dir = std::env::temp_dir().join(format!("pfauterminal-fifo-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let fifo = dir.join("image");
+        let c_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        // SAFETY: a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+        let images = Images::default();
+        images.set_geometry((10.0, 20.0), 80, 24);
+        let replies = Replies::default();
+        let mut kitty = KittyGraphics::new(Box::new(replies.clone()), true);
+        let path = base64::engine::general_purpose::STANDARD.encode(fifo.to_str().unwrap());
+        let mut out = Vec::new();
+        kitty.command(format!("a=t,t=f,i=4;{path}").as_bytes(), &images, &mut out);
+        let _ = fs::remove_dir_all(&dir);
+        let replies = replies.0.lock().unwrap();
+        assert_eq!(replies[0], "\x1b_Gi=4;EINVAL:not a regular file\x1b\\");
+    }
+
+    #[test]
+    fn placeholder_marks_carry_on_from_the_left() {
+        let fg = Color::Spec(Rgb { r: 0, g: 0, b: 42 });
+        let cell = |col, marks: &[char]| PlaceholderCell {
+            line: 3,
+            col,
+            fg,
+            underline: Some(Color::Indexed(5)),
+            marks: marks.to_vec(),
+        };
+        // Row 1 column 0; a bare cell; then row 1 column 5 with high byte 2.
+        let cells = [
+            cell(0, &['\u{030D}', '\u{0305}']),
+            cell(1, &[]),
+            cell(2, &['\u{030D}', '\u{033D}', '\u{030E}']),
+        ];
+        let pieces: Vec<_> =
+            image_pieces(&cells).iter().map(|p| (p.image, p.placement, p.row, p.column)).collect();
+        assert_eq!(pieces, [(42, 5, 1, 0), (42, 5, 1, 1), (42 | 2 << 24, 5, 1, 5)]);
+    }
+}
@@ -0,0 +1,418 @@
+//! Key encoding for the kitty keyboard protocol.
+//!
+//! The terminal tracks the progressive enhancement flags pushed by apps
+//! (CSI > u / CSI < u / CSI = u) and answers CSI ? u itself; this module turns a
+//! key event into the bytes those flags ask for. Keys the active flags leave
+//! alone come back as `None` and go through the legacy encoder.
+
+use alacritty_terminal::term::TermMode;
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::{Key, KeyCode, NamedKey, PhysicalKey};
+use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
+
+/// Modifier state as the protocol numbers it, before the +1 offset.
+#[derive(Clone, Copy, Default)]
+pub struct Mods {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+    /// Cmd on macOS, the Windows/logo key elsewhere.
+    pub super_key: bool,
+}
+
+impl Mods {
+    fn bits(self) -> u32 {
+        self.shift as u32
+            | (self.alt as u32) << 1
+            | (self.ctrl as u32) << 2
+            | (self.super_key as u32) << 3
+    }
+
+    /// The modifier parameter as sent: 1 plus the bits.
+    pub fn param(self) -> u32 {
+        self.bits() + 1
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventType {
+    Press = 1,
+    Repeat = 2,
+    Release = 3,
+}
+
+/// How a key is spelled on the wire.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KittyKey {
+    /// `CSI code[:shifted[:base]] ; mods ; text u`
+    Unicode { code: u32, shifted: Option<u32>, base: Option<u32> },
+    /// `CSI 1 ; mods X` — arrows, Home/End, F1, F2 and F4.
+    Letter(u8),
+    /// `CSI num ; mods ~`
+    Tilde(u32),
+}
+
+/// What the protocol needs to know about one key event.
+#[derive(Clone, Copy, Debug)]
+pub struct KeyInfo<'a> {
+    pub key: KittyKey,
+    pub event: EventType,
+    /// Text the key would type, for the legacy fallback and associated text.
+    pub text: Option<&'a str>,
+    /// Enter, Tab and Backspace keep their legacy bytes unless modified.
+    pub legacy_control: bool,
+    pub modifier: bool,
+}
+
+/// Encode `event` for the kitty flags in `mode`, or `None` to fall back to
+/// the legacy encoding (which ignores releases).
+pub fn encode(event: &KeyEvent, mods: Mods, mode: TermMode) -> Option<Vec<u8>> {
+    let info = key_info(event)?;
+    encode_info(&info, mods, mode)
+}
+
+pub fn encode_info(info: &KeyInfo, mods: Mods, mode: TermMode) -> Option<Vec<u8>> {
+    let all_keys = mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC);
+    let event_types = mode.contains(TermMode::REPORT_EVENT_TYPES);
+    if !all_keys && info.modifier {
+        return None;
+    }
+    if !all_keys && info.event != EventType::Release {
+        // Text typed with at most Shift stays plain text, and unmodified
+        // Enter/Tab/Backspace and cursor keys keep their legacy bytes.
+        let unmodified = mods.bits() == 0;
+        let legacy = (info.text.is_some() && !mods.alt && !mods.ctrl && !mods.super_key)
+            || (unmodified && info.legacy_control)
+            || (unmodified && !matches!(info.key, KittyKey::Unicode { .. }));
+        if legacy {
+            return None;
+        }
+    }
+    if info.event == EventType::Release && !event_types {
+        return None;
+    }
+    // Releases of Enter, Tab and Backspace only with all keys as escapes,
+    // so a shell doesn't see a stray sequence after a crashed app.
+    if info.event == EventType::Release && info.legacy_control && !all_keys {
+        return None;
+    }
+
+    let mut modifiers = String::new();
+    let event = if event_types { info.event } else { EventType::Press };
+    if mods.bits() != 0 || event != EventType::Press {
+        modifiers = (mods.bits() + 1).to_string();
+        if event != EventType::Press {
+            modifiers.push_str(&format!(":{}", event as u8));
+        }
+    }
+
+    let out = match info.key {
+        KittyKey::Unicode { code, shifted, base } => {
+            let mut key = code.to_string();
+            if mode.contains(TermMode::REPORT_ALTERNATE_KEYS) {
+                let shifted = shifted.filter(|_| mods.shift);
+                if shifted.is_some() || base.is_some() {
+                    key.push(':');
+                    if let Some(s) = shifted {
+                        key.push_str(&s.to_string());
+                    }
+                }
+                if let Some(b) = base {
+                    key.push_str(&format!(":{b}"));
+                }
+            }
+            let text = info
+                .text
+                .filter(|_| {
+                    all_keys
+                        && mode.contains(TermMode::REPORT_ASSOCIATED_TEXT)
+                        && info.event != EventType::Release
+                })
+                .map(|t| {
+                    t.chars()
+                        .filter(|c| !c.is_control())
+                        .map(|c| (c as u32).to_string())
+                        .collect::<Vec<_>>()
+                        .join(":")
+                })
+                .filter(|t| !t.is_empty());
+            match (modifiers.is_empty(), text) {
+                (_, Some(text)) => format!("\x1b[{key};{modifiers};{text}u"),
+                (true, None) => format!("\x1b[{key}u"),
+                (false, None) => format!("\x1b[{key};{modifiers}u"),
+            }
+        }
+        KittyKey::Letter(c) if modifiers.is_empty() => format!("\x1b[{}", c as char),
+        KittyKey::Letter(c) => format!("\x1b[1;{modifiers}{}", c as char),
+        KittyKey::Tilde(n) if modifiers.is_empty() => format!("\x1b[{n}~"),
+        KittyKey::Tilde(n) => format!("\x1b[{n};{modifiers}~"),
+    };
+    Some(out.into_bytes())
+}
+
+fn key_info(event: &KeyEvent) -> Option<KeyInfo<'_>> {
+    let event_type = match (event.state, event.repeat) {
+        (ElementState::Released, _) => EventType::Release,
+        (ElementState::Pressed, true) => EventType::Repeat,
+        (ElementState::Pressed, false) => EventType::Press,
+    };
+    let code = match event.physical_key {
+        PhysicalKey::Code(code) => Some(code),
+        PhysicalKey::Unidentified(_) => None,
+    };
+    let text = event.text.as_deref().filter(|t| !t.is_empty());
+    let mut info = KeyInfo {
+        key: KittyKey::Tilde(0),
+        event: event_type,
+        text: None,
+        legacy_control: false,
+        modifier: false,
+    };
+
+    // Keypad keys get their own numbers so apps can tell them apart.
+    if let Some(kp) = code.and_then(keypad_code) {
+        info.key = unicode(kp);
+        info.text = text.filter(|t| t.chars().all(|c| !c.is_control()));
+        return Some(info);
+    }
+
+    let named = match event.key_without_modifiers() {
+        Key::Named(named) => Some(named),
+        Key::Character(c) => {
+            let mut chars = c.chars();
+            let ch = chars.next()?;
+            let ch = if chars.next().is_none() { ch.to_lowercase().next()? } else { ch };
+            let shifted = match event.logical_key.as_ref() {
+                Key::Character(s) => s.chars().next().map(|c| c as u32),
+                _ => None,
+            };
+            let base = code.and_then(base_key).map(|b| b as u32);
+            info.key = KittyKey::Unicode {
+                code: ch as u32,
+                shifted: shifted.filter(|&s| s != ch as u32),
+                base: base.filter(|&b| b != ch as u32),
+            };
+            info.text = text;
+            return Some(info);
+        }
+        _ => None,
+    };
+
+    info.key = match named? {
+        NamedKey::Escape => unicode(27),
+        NamedKey::Enter => {
+            info.legacy_control = true;
+            unicode(13)
+        }
+        NamedKey::Tab => {
+            info.legacy_control = true;
+            unicode(9)
+        }
+        NamedKey::Backspace => {
+            info.legacy_control = true;
+            unicode(127)
+        }
+        NamedKey::Space => {
+            info.text = text;
+            KittyKey::Unicode { code: 32, shifted: None, base: None }
+        }
+        NamedKey::Insert => KittyKey::Tilde(2),
+        NamedKey::Delete => KittyKey::Tilde(3),
+        NamedKey::PageUp => KittyKey::Tilde(5),
+        NamedKey::PageDown => KittyKey::Tilde(6),
+        NamedKey::ArrowUp => KittyKey::Letter(b'A'),
+        NamedKey::ArrowDown => KittyKey::Letter(b'B'),
+        NamedKey::ArrowRight => KittyKey::Letter(b'C'),
+        NamedKey::ArrowLeft => KittyKey::Letter(b'D'),
+        NamedKey::Home => KittyKey::Letter(b'H'),
+        NamedKey::End => KittyKey::Letter(b'F'),
+        NamedKey::F1 => KittyKey::Letter(b'P'),
+        NamedKey::F2 => KittyKey::Letter(b'Q'),
+        NamedKey::F3 => KittyKey::Tilde(13),
+        NamedKey::F4 => KittyKey::Letter(b'S'),
+        NamedKey::F5 => KittyKey::Tilde(15),
+        NamedKey::F6 => KittyKey::Tilde(17),
+        NamedKey::F7 => KittyKey::Tilde(18),
+        NamedKey::F8 => KittyKey::Tilde(19),
+        NamedKey::F9 => KittyKey::Tilde(20),
+        NamedKey::F10 => KittyKey::Tilde(21),
+        NamedKey::F11 => KittyKey::Tilde(23),
+        NamedKey::F12 => KittyKey::Tilde(24),
+        NamedKey::CapsLock | NamedKey::ScrollLock | NamedKey::NumLock => {
+            // Lock keys are reported like modifiers, only with all keys as escapes.
+            info.modifier = true;
+            match named? {
+                NamedKey::CapsLock => unicode(57358),
+                NamedKey::ScrollLock => unicode(57359),
+                _ => unicode(57360),
+            }
+        }
+        NamedKey::PrintScreen => unicode(57361),
+        NamedKey::Pause => unicode(57362),
+        NamedKey::ContextMenu => unicode(57363),
+        NamedKey::MediaPlay => unicode(57428),
+        NamedKey::MediaPause => unicode(57429),
+        NamedKey::MediaPlayPause => unicode(57430),
+        NamedKey::MediaStop => unicode(57432),
+        NamedKey::MediaFastForward => unicode(57433),
+        NamedKey::MediaRewind => unicode(57434),
+        NamedKey::MediaTrackNext => unicode(57435),
+        NamedKey::MediaTrackPrevious => unicode(57436),
+        NamedKey::MediaRecord => unicode(57437),
+        NamedKey::AudioVolumeDown => unicode(57438),
+        NamedKey::AudioVolumeUp => unicode(57439),
+        NamedKey::AudioVolumeMute => unicode(57440),
+        named => {
+            // F13–F35 are numbered consecutively.
+            if let Some(n) = function_key_number(named).filter(|&n| n >= 13) {
+                unicode(57376 + n - 13)
+            } else {
+                info.modifier = true;
+                unicode(modifier_code(code?)?)
+            }
+        }
+    };
+    Some(info)
+}
+
+fn unicode(code: u32) -> KittyKey {
+    KittyKey::Unicode { code, shifted: None, base: None }
+}
+
+fn function_key_number(key: NamedKey) -> Option<u32> {
+    use NamedKey::*;
+    let keys = [
+        F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24, F25, F26, F27, F28, F29,
+        F30, F31, F32, F33, F34, F35,
+    ];
+    keys.iter().position(|&k| k == key).map(|i| i as u32 + 13)
+}
+
+fn modifier_code(code: KeyCode) -> Option<u32> {
+    Some(match code {
+        KeyCode::ShiftLeft => 57441,
+        KeyCode::ControlLeft => 57442,
+        KeyCode::AltLeft => 57443,
+        KeyCode::SuperLeft => 57444,
+        KeyCode::ShiftRight => 57447,
+        KeyCode::ControlRight => 57448,
+        KeyCode::AltRight => 57449,
+        KeyCode::SuperRight => 57450,
+        _ => return None,
+    })
+}
+
+fn keypad_code(code: KeyCode) -> Option<u32> {
+    Some(match code {
+        KeyCode::Numpad0 => 57399,
+        KeyCode::Numpad1 => 57400,
+        KeyCode::Numpad2 => 57401,
+        KeyCode::Numpad3 => 57402,
+        KeyCode::Numpad4 => 57403,
+        KeyCode::Numpad5 => 57404,
+        KeyCode::Numpad6 => 57405,
+        KeyCode::Numpad7 => 57406,
+        KeyCode::Numpad8 => 57407,
+        KeyCode::Numpad9 => 57408,
+        KeyCode::NumpadDecimal => 57409,
+        KeyCode::NumpadDivide => 57410,
+        KeyCode::NumpadMultiply => 57411,
+        KeyCode::NumpadSubtract => 57412,
+        KeyCode::NumpadAdd => 57413,
+        KeyCode::NumpadEnter => 57414,
+        KeyCode::NumpadEqual => 57415,
+        KeyCode::NumpadComma => 57416,
+        _ => return None,
+    })
+}
+
+/// The key at the same spot on a US PC-101 layout, reported as the base
+/// layout key so shortcuts work on non-Latin layouts.
+fn base_key(code: KeyCode) -> Option<char> {
+    Some(match code {
+        KeyCode::KeyA => 'a', KeyCode::KeyB => 'b', KeyCode::KeyC => 'c',
+        KeyCode::KeyD => 'd', KeyCode::KeyE => 'e', KeyCode::KeyF => 'f',
+        KeyCode::KeyG => 'g', KeyCode::KeyH => 'h', KeyCode::KeyI => 'i',
+        KeyCode::KeyJ => 'j', KeyCode::KeyK => 'k', KeyCode::KeyL => 'l',
+        KeyCode::KeyM => 'm', KeyCode::KeyN => 'n', KeyCode::KeyO => 'o',
+        KeyCode::KeyP => 'p', KeyCode::KeyQ => 'q', KeyCode::KeyR => 'r',
+        KeyCode::KeyS => 's', KeyCode::KeyT => 't', KeyCode::KeyU => 'u',
+        KeyCode::KeyV => 'v', KeyCode::KeyW => 'w', KeyCode::KeyX => 'x',
+        KeyCode::KeyY => 'y', KeyCode::KeyZ => 'z',
+        KeyCode::Digit0 => '0', KeyCode::Digit1 => '1', KeyCode::Digit2 => '2',
+        KeyCode::Digit3 => '3', KeyCode::Digit4 => '4', KeyCode::Digit5 => '5',
+        KeyCode::Digit6 => '6', KeyCode::Digit7 => '7', KeyCode::Digit8 => '8',
+        KeyCode::Digit9 => '9',
+        KeyCode::Minus => '-', KeyCode::Equal => '=', KeyCode::BracketLeft => '[',
+        KeyCode::BracketRight => ']', KeyCode::Backslash => '\\', KeyCode::Semicolon => ';',
+        KeyCode::Quote => '\'', KeyCode::Backquote => '`', KeyCode::Comma => ',',
+        KeyCode::Period => '.', KeyCode::Slash => '/',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: u32, event: EventType, text: Option<&str>) -> KeyInfo<'_> {
+        KeyInfo {
+            key: KittyKey::Unicode { code, shifted: None, base: None },
+            event,
+            text,
+            legacy_control: false,
+            modifier: false,
+        }
+    }
+
+    fn enc(info: &KeyInfo, mods: Mods, mode: TermMode) -> Option<String> {
+        encode_info(info, mods, mode).map(|b| String::from_utf8(b).unwrap())
+    }
+
+    #[test]
+    fn kitty_encoding() {
+        let disambiguate = TermMode::DISAMBIGUATE_ESC_CODES;
+        let none = Mods::default();
+        let ctrl = Mods { ctrl: true, ..Default::default() };
+        let ctrl_shift = Mods { ctrl: true, shift: true, ..Default::default() };
+        let a = key('a' as u32, EventType::Press, Some("a"));
+
+        // Plain text and unmodified cursor keys stay legacy.
+        assert_eq!(enc(&a, none, disambiguate), None);
+        let up = KeyInfo { key: KittyKey::Letter(b'A'), ..a };
+        assert_eq!(enc(&up, none, disambiguate), None);
+        assert_eq!(enc(&up, ctrl, disambiguate).as_deref(), Some("\x1b[1;5A"));
+
+        // Ctrl+Shift+letter and Escape are distinguishable.
+        assert_eq!(enc(&a, ctrl_shift, disambiguate).as_deref(), Some("\x1b[97;6u"));
+        let super_key = Mods { super_key: true, ..Default::default() };
+        assert_eq!(enc(&a, super_key, disambiguate).as_deref(), Some("\x1b[97;9u"));
+        let esc = key(27, EventType::Press, None);
+        assert_eq!(enc(&esc, none, disambiguate).as_deref(), Some("\x1b[27u"));
+
+        // Releases only with event types, and never for a bare Enter.
+        let release = key('a' as u32, EventType::Release, Some("a"));
+        assert_eq!(enc(&release, ctrl, disambiguate), None);
+        let events = disambiguate | TermMode::REPORT_EVENT_TYPES;
+        assert_eq!(enc(&release, ctrl, events).as_deref(), Some("\x1b[97;5:3u"));
+        let enter = KeyInfo { legacy_control: true, ..key(13, EventType::Release, None) };
+        assert_eq!(enc(&enter, none, events), None);
+
+        // All keys as escapes, with alternates and associated text.
+        let all = TermMode::REPORT_ALL_KEYS_AS_ESC
+            | TermMode::REPORT_ALTERNATE_KEYS
+            | TermMode::REPORT_ASSOCIATED_TEXT;
+        let shift_a = KeyInfo {
+            key: KittyKey::Unicode { code: 'a' as u32, shifted: Some('A' as u32), base: None },
+            ..key('a' as u32, EventType::Press, Some("A"))
+        };
+        let shift = Mods { shift: true, ..Default::default() };
+        assert_eq!(enc(&shift_a, shift, all).as_deref(), Some("\x1b[97:65;2;65u"));
+        assert_eq!(enc(&a, none, all).as_deref(), Some("\x1b[97;;97u"));
+        let cyrillic = KeyInfo {
+            key: KittyKey::Unicode { code: 'ф' as u32, shifted: None, base: Some('a' as u32) },
+            ..key(0, EventType::Press, None)
+        };
+        assert_eq!(enc(&cyrillic, ctrl, all).as_deref(), Some("\x1b[1092::97;5u"));
+    }
+}
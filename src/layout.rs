@@ -1,11 +1,33 @@
+use std::sync::Arc;
+
 use glyphon::{Color as GlyphonColor, TextBounds};
 
+use crate::images::TermImage;
+
 /// Background quad for flat-color rendering.
 pub struct BgQuad {
     pub rect: Rect,
     pub color: [f32; 4],
 }
 
+/// Flat-color triangle, for procedural glyphs that aren't axis-aligned.
+pub struct FlatTriangle {
+    pub points: [[f32; 2]; 3],
+    pub color: [f32; 4],
+}
+
+/// An inline terminal image drawn over `rect`, cut to `clip`.
+pub struct ImageQuad {
+    pub image: Arc<TermImage>,
+    pub rect: Rect,
+    pub clip: Rect,
+    /// The part of the image stretched over `rect`, as `[u0, v0, u1, v1]`.
+    pub uv: [f32; 4],
+    /// Kitty's z-index: negative goes under the text, and below
+    /// `i32::MIN / 2` under cell backgrounds too.
+    pub z: i32,
+}
+
 /// Shared text spec used by all UI components for glyphon rendering.
 pub struct TextSpec {
     pub buffer_index: usize,
@@ -15,7 +37,7 @@ pub struct TextSpec {
     pub color: GlyphonColor,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect {
     pub x: f32,
     pub y: f32,
@@ -1,27 +1,47 @@
 #![windows_subsystem = "windows"]
 
 mod app;
+mod builtin_glyphs;
+mod cli;
 mod colors;
+mod command_palette;
+mod config;
+mod config_watch;
 mod draw;
 mod dropdown;
+mod export;
 mod font;
 mod gpu;
 mod hotkeys;
 mod icons;
+mod images;
+mod ipc;
+mod kitty_graphics;
+mod kitty_keys;
 mod layout;
 mod menu;
+mod output;
+mod replay;
 mod saved_sessions;
+mod session_log;
+mod settings;
+mod sixel;
 mod ssh;
 mod ssh_config;
 mod ssh_dialog;
 mod tab_bar;
 mod terminal_panel;
 mod theme;
+mod themes;
+mod toast;
+mod upload;
 mod widgets;
+mod xterm_keys;
 
 use winit::event_loop::EventLoop;
 
 use app::App;
+use cli::Cli;
 use terminal_panel::TerminalEvent;
 
 /// Prevent macOS App Nap from throttling background threads (e.g. the
@@ -51,7 +71,48 @@ fn disable_app_nap() {
     }
 }
 
+/// The release binary is a GUI program on Windows and starts without a
+/// console; borrow the one it was run from so help, errors and `msg`
+/// replies show up there.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // SAFETY: no preconditions; fails harmlessly without a parent console.
+    let _ = unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
 fn main() {
+    let cli = cli::parse(std::env::args().skip(1));
+    #[cfg(windows)]
+    if !matches!(cli, Ok(Cli::Run(_))) {
+        attach_parent_console();
+    }
+    let options = match cli {
+        Ok(Cli::Run(options)) => options,
+        Ok(Cli::Help) => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        Ok(Cli::Version) => {
+            println!("pfauterminal {}", menu::APP_VERSION);
+            return;
+        }
+        Ok(Cli::Msg { socket, request }) => match ipc::run_client(socket, request) {
+            Ok(output) => {
+                print!("{output}");
+                return;
+            }
+            Err(error) => {
+                eprintln!("pfauterminal: {error}");
+                std::process::exit(1);
+            }
+        },
+        Err(error) => {
+            eprint!("pfauterminal: {error}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     #[cfg(target_os = "macos")]
     disable_app_nap();
 
@@ -63,7 +124,7 @@ fn main() {
     };
 
     let proxy = event_loop.create_proxy();
-    let mut app = App::new(proxy);
+    let mut app = App::new(proxy, options);
 
     if event_loop.run_app(&mut app).is_err() {
         std::process::exit(1);
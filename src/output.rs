@@ -0,0 +1,151 @@
+//! The path program output takes before the terminal parses it: teed into
+//! the session log, scanned for keyboard mode changes, and stripped of inline
+//! images. Local PTY tabs and SSH sessions run the same pipeline.
+
+use std::io;
+
+use crate::images::ImageFilter;
+use crate::session_log::SessionLog;
+use crate::xterm_keys::OtherKeysScanner;
+
+pub struct OutputPipeline {
+    log: SessionLog,
+    other_keys: OtherKeysScanner,
+    images: ImageFilter,
+}
+
+impl OutputPipeline {
+    pub fn new(log: SessionLog, other_keys: OtherKeysScanner, images: ImageFilter) -> Self {
+        Self { log, other_keys, images }
+    }
+
+    /// Run a chunk of raw output through the pipeline, appending what the
+    /// terminal should parse to `out`.
+    pub fn process(&mut self, bytes: &[u8], out: &mut Vec<u8>) {
+        self.log.output(bytes);
+        self.other_keys.feed(bytes);
+        self.images.filter(bytes, out);
+    }
+}
+
+/// A local PTY whose output runs through an [`OutputPipeline`] on its way to
+/// alacritty's event loop.
+#[cfg(unix)]
+pub struct PipedPty {
+    pty: alacritty_terminal::tty::Pty,
+    reader: PipedReader,
+}
+
+#[cfg(unix)]
+pub struct PipedReader {
+    file: std::fs::File,
+    pipeline: OutputPipeline,
+    /// Filtered output that didn't fit the caller's buffer.
+    pending: Vec<u8>,
+}
+
+#[cfg(unix)]
+impl io::Read for PipedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Keep reading while the filter swallows everything (an image in
+        // transit); returning 0 would look like the end of the output.
+        while self.pending.is_empty() {
+            let n = self.file.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            self.pipeline.process(&buf[..n], &mut self.pending);
+        }
+        let n = self.pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+#[cfg(unix)]
+pub fn wrap_pty(
+    pty: alacritty_terminal::tty::Pty,
+    pipeline: OutputPipeline,
+) -> io::Result<PipedPty> {
+    // A dup of the PTY master shares the non-blocking file description, so
+    // reads through it behave exactly like reads through the original.
+    let file = pty.file().try_clone()?;
+    Ok(PipedPty { pty, reader: PipedReader { file, pipeline, pending: Vec::new() } })
+}
+
+/// The Windows PTY reader can't be duplicated; local tabs there run
+/// unwrapped and can't be logged, scanned or show images.
+#[cfg(not(unix))]
+pub type PipedPty = alacritty_terminal::tty::Pty;
+
+#[cfg(not(unix))]
+pub fn wrap_pty(
+    pty: alacritty_terminal::tty::Pty,
+    _pipeline: OutputPipeline,
+) -> io::Result<PipedPty> {
+    Ok(pty)
+}
+
+/// Whether local (PTY) tabs run the pipeline on this platform.
+pub const LOCAL_PIPELINE: bool = cfg!(unix);
+
+#[cfg(unix)]
+mod evented {
+    use std::fs::File;
+    use std::io;
+    use std::sync::Arc;
+
+    use alacritty_terminal::event::{OnResize, WindowSize};
+    use alacritty_terminal::tty::{ChildEvent, EventedPty, EventedReadWrite};
+    use polling::{Event, PollMode, Poller};
+
+    use super::{PipedPty, PipedReader};
+
+    impl EventedReadWrite for PipedPty {
+        type Reader = PipedReader;
+        type Writer = File;
+
+        unsafe fn register(
+            &mut self,
+            poll: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            unsafe { self.pty.register(poll, interest, mode) }
+        }
+
+        fn reregister(
+            &mut self,
+            poll: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            self.pty.reregister(poll, interest, mode)
+        }
+
+        fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+            self.pty.deregister(poll)
+        }
+
+        fn reader(&mut self) -> &mut PipedReader {
+            &mut self.reader
+        }
+
+        fn writer(&mut self) -> &mut File {
+            self.pty.writer()
+        }
+    }
+
+    impl EventedPty for PipedPty {
+        fn next_child_event(&mut self) -> Option<ChildEvent> {
+            self.pty.next_child_event()
+        }
+    }
+
+    impl OnResize for PipedPty {
+        fn on_resize(&mut self, window_size: WindowSize) {
+            self.pty.on_resize(window_size);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Playback of asciicast v2 recordings into a read-only panel.
+//!
+//! A player thread feeds the recorded output through the ANSI processor with
+//! the original timing. The panel sends it [`ReplayControl`]s from the
+//! keyboard; the tab title shows the play state and position.
+
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use alacritty_terminal::event::{Event, EventListener};
+use alacritty_terminal::sync::FairMutex;
+use alacritty_terminal::vte::ansi;
+use alacritty_terminal::Term;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::images::ImageFilter;
+use crate::terminal_panel::EventProxy;
+
+/// Seek step for the arrow keys, in recording seconds.
+const SEEK_STEP: f64 = 5.0;
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 16.0;
+/// How often the title's position readout refreshes while playing.
+const STATUS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A loaded recording: output events with their times (seconds from start).
+pub struct Recording {
+    pub title: Option<String>,
+    events: Vec<(f64, String)>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::parse(&data).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Parse asciicast v2. Input (`"i"`), marker and resize events are
+    /// skipped; playback uses the panel's own size.
+    fn parse(data: &str) -> Result<Self, String> {
+        let mut lines = data.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+        let (_, header) = lines.next().ok_or("empty recording")?;
+        let header: serde_json::Value =
+            serde_json::from_str(header).map_err(|e| format!("line 1: {e}"))?;
+        if header["version"] != 2 {
+            return Err("not an asciicast v2 recording".into());
+        }
+        let title = header["title"].as_str().map(str::to_string);
+        // Pauses longer than this are shortened, as asciinema does.
+        let idle_limit = header["idle_time_limit"].as_f64().unwrap_or(f64::INFINITY);
+
+        let mut events = Vec::new();
+        let (mut last_raw, mut time) = (0.0, 0.0);
+        for (i, line) in lines {
+            let (raw, code, text): (f64, String, String) =
+                serde_json::from_str(line).map_err(|e| format!("line {}: {e}", i + 1))?;
+            time += (raw - last_raw).clamp(0.0, idle_limit);
+            last_raw = raw;
+            if code == "o" {
+                events.push((time, text));
+            }
+        }
+        Ok(Self { title, events })
+    }
+
+    fn duration(&self) -> f64 {
+        self.events.last().map_or(0.0, |(t, _)| *t)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReplayControl {
+    TogglePause,
+    /// Relative seek in recording seconds.
+    Seek(f64),
+    Restart,
+    Faster,
+    Slower,
+}
+
+impl ReplayControl {
+    /// Space pauses, arrows seek (left/right) and change speed (up/down),
+    /// Home restarts and End jumps to the end.
+    pub fn for_key(key: &Key) -> Option<Self> {
+        match key.as_ref() {
+            Key::Named(NamedKey::Space) => Some(ReplayControl::TogglePause),
+            Key::Named(NamedKey::ArrowLeft) => Some(ReplayControl::Seek(-SEEK_STEP)),
+            Key::Named(NamedKey::ArrowRight) => Some(ReplayControl::Seek(SEEK_STEP)),
+            Key::Named(NamedKey::ArrowUp) | Key::Character("+" | "=") => {
+                Some(ReplayControl::Faster)
+            }
+            Key::Named(NamedKey::ArrowDown) | Key::Character("-") => Some(ReplayControl::Slower),
+            Key::Named(NamedKey::Home) => Some(ReplayControl::Restart),
+            Key::Named(NamedKey::End) => Some(ReplayControl::Seek(f64::INFINITY)),
+            _ => None,
+        }
+    }
+}
+
+/// Start playing `recording` into `term`. Dropping the returned sender
+/// stops the player thread. `term` should report to a proxy with recorded
+/// titles muted, since the tab title shows the player status.
+pub fn spawn(
+    recording: Recording,
+    title: String,
+    term: Arc<FairMutex<Term<EventProxy>>>,
+    proxy: EventProxy,
+    images: ImageFilter,
+) -> mpsc::Sender<ReplayControl> {
+    let (tx, rx) = mpsc::channel();
+    let mut player = Player {
+        recording,
+        title,
+        term,
+        proxy,
+        parser: ansi::Processor::new(),
+        images,
+        filtered: Vec::new(),
+        next: 0,
+        position: 0.0,
+        speed: 1.0,
+        paused: false,
+        status: String::new(),
+    };
+    let _ = std::thread::Builder::new()
+        .name("replay".into())
+        .spawn(move || player.run(rx));
+    tx
+}
+
+struct Player {
+    recording: Recording,
+    title: String,
+    term: Arc<FairMutex<Term<EventProxy>>>,
+    proxy: EventProxy,
+    parser: ansi::Processor<ansi::StdSyncHandler>,
+    /// Recorded inline images, shown as they were live.
+    images: ImageFilter,
+    filtered: Vec<u8>,
+    /// Index of the first event not yet fed.
+    next: usize,
+    /// Playback position (recording seconds) as of `clock`.
+    position: f64,
+    speed: f64,
+    paused: bool,
+    /// Last title sent, to avoid redundant updates.
+    status: String,
+}
+
+impl Player {
+    fn run(&mut self, rx: mpsc::Receiver<ReplayControl>) {
+        let mut clock = Instant::now();
+        loop {
+            let now = self.now(clock);
+            self.feed_until(now);
+            if self.next >= self.recording.events.len() {
+                self.paused = true;
+            }
+            self.position = now;
+            clock = Instant::now();
+            self.update_status();
+
+            let wait = match self.recording.events.get(self.next) {
+                Some((t, _)) if !self.paused => {
+                    let until_next = Duration::from_secs_f64(((t - now) / self.speed).max(0.0));
+                    until_next.min(STATUS_INTERVAL)
+                }
+                _ => Duration::MAX,
+            };
+            match rx.recv_timeout(wait) {
+                Ok(control) => {
+                    self.position = self.now(clock);
+                    clock = Instant::now();
+                    self.apply(control);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    /// Playback position at this instant.
+    fn now(&self, clock: Instant) -> f64 {
+        if self.paused {
+            self.position
+        } else {
+            self.position + clock.elapsed().as_secs_f64() * self.speed
+        }
+    }
+
+    fn apply(&mut self, control: ReplayControl) {
+        match control {
+            ReplayControl::TogglePause => {
+                // Play again from the start once the end is reached.
+                if self.paused && self.next >= self.recording.events.len() {
+                    self.seek_to(0.0);
+                }
+                self.paused = !self.paused;
+            }
+            ReplayControl::Seek(delta) => self.seek_to(self.position + delta),
+            ReplayControl::Restart => {
+                self.seek_to(0.0);
+                self.paused = false;
+            }
+            ReplayControl::Faster => self.speed = (self.speed * 2.0).min(MAX_SPEED),
+            ReplayControl::Slower => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+        }
+    }
+
+    fn seek_to(&mut self, target: f64) {
+        let target = target.clamp(0.0, self.recording.duration());
+        if target < self.position {
+            // Output can't be undone; rebuild the screen from the start.
+            {
+                let mut term = self.term.lock();
+                self.parser.advance(&mut *term, b"\x1bc");
+                term.grid_mut().clear_history();
+            }
+            self.next = 0;
+            self.proxy.send_event(Event::Wakeup);
+        }
+        self.feed_until(target);
+        self.position = target;
+    }
+
+    /// Feed every event up to `time` and wake the UI if anything changed.
+    fn feed_until(&mut self, time: f64) {
+        let start = self.next;
+        while let Some((t, text)) = self.recording.events.get(self.next)
+            && *t <= time
+        {
+            self.filtered.clear();
+            self.images.filter(text.as_bytes(), &mut self.filtered);
+            let mut term = self.term.lock();
+            self.parser.advance(&mut *term, &self.filtered);
+            self.next += 1;
+        }
+        if self.next != start {
+            self.proxy.send_event(Event::Wakeup);
+        }
+    }
+
+    fn update_status(&mut self) {
+        let icon = if self.paused { "\u{23f8}" } else { "\u{25b6}" };
+        let speed = if self.speed == 1.0 { String::new() } else { format!(" {}\u{d7}", self.speed) };
+        let status = format!(
+            "{icon} {} {} / {}{speed}",
+            self.title,
+            clock_time(self.position),
+            clock_time(self.recording.duration())
+        );
+        if status != self.status {
+            self.proxy.set_title(status.clone());
+            self.status = status;
+        }
+    }
+}
+
+/// `m:ss`, or `h:mm:ss` past an hour.
+fn clock_time(secs: f64) -> String {
+    let secs = secs as u64;
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        h => format!("{h}:{:02}:{:02}", secs / 60 % 60, secs % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keeps_output_and_caps_idle_time() {
+        let rec = Recording::parse(concat!(
+            "{\"version\": 2, \"width\": 80, \"height\": 24, \"idle_time_limit\": 2}\n",
+            "[0.5, \"o\", \"a\"]\n",
+            "[10.5, \"o\", \"b\"]\n",
+            "[10.6, \"i\", \"x\"]\n",
+        ))
+        .unwrap();
+        assert_eq!(rec.events, vec![(0.5, "a".into()), (2.5, "b".into())]);
+        assert!(Recording::parse("{\"version\": 1}").is_err());
+    }
+
+    #[test]
+    fn clock_time_formats() {
+        assert_eq!(clock_time(65.9), "1:05");
+        assert_eq!(clock_time(3725.0), "1:02:05");
+    }
+}
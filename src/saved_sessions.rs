@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+
+use crate::colors::TabColor;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SavedSession {
@@ -12,6 +13,12 @@ pub struct SavedSession {
     #[serde(default)]
     pub password: Option<String>,
     pub last_used: u64,
+    /// Color scheme for tabs opened from this session; overrides the global one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// Tab color label; overrides the `tab_colors` host rules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<TabColor>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,34 +48,14 @@ pub struct SavedSessions {
 }
 
 impl SavedSessions {
-    fn config_path() -> Option<PathBuf> {
-        dirs::config_dir().map(|d| d.join("pfauterminal").join("sessions.json"))
-    }
-
-    pub fn load() -> Self {
-        let Some(path) = Self::config_path() else {
-            return Self::default();
-        };
-        match std::fs::read_to_string(&path) {
-            Ok(json) => {
-                let mut sessions: Self = serde_json::from_str(&json).unwrap_or_default();
-                sessions.sort();
-                sessions
-            }
-            Err(_) => Self::default(),
-        }
+    pub fn new(sessions: Vec<SavedSession>) -> Self {
+        let mut sessions = Self { sessions };
+        sessions.sort();
+        sessions
     }
 
     pub fn save(&self) {
-        let Some(path) = Self::config_path() else {
-            return;
-        };
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        if let Ok(json) = serde_json::to_string_pretty(self) {
-            let _ = std::fs::write(&path, json);
-        }
+        crate::config::update(|config| config.sessions = self.sessions.clone());
     }
 
     pub fn upsert(&mut self, session: SavedSession) {
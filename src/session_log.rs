@@ -0,0 +1,224 @@
+//! Per-tab session logging: a tee of everything the backend delivers to the
+//! terminal, written as an asciicast v2 recording or as the raw byte stream.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::saved_sessions::now_unix;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// asciicast v2 (`.cast`), replayable with timing.
+    #[default]
+    Asciicast,
+    /// Output bytes as received, escape sequences included (`.log`).
+    Raw,
+}
+
+impl LogFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            LogFormat::Asciicast => "cast",
+            LogFormat::Raw => "log",
+        }
+    }
+}
+
+/// Handle to a tab's log, shared between the panel and the thread that
+/// reads its backend. Inactive until [`SessionLog::start`].
+#[derive(Clone, Default)]
+pub struct SessionLog(Arc<Mutex<Option<Recorder>>>);
+
+impl SessionLog {
+    /// Open a new log file in `dir` named after the start time and `title`.
+    pub fn start(
+        &self,
+        dir: &Path,
+        format: LogFormat,
+        title: &str,
+        size: (usize, usize),
+    ) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        let (file, path) = create_log_file(dir, title, format)?;
+        let mut recorder = Recorder {
+            out: BufWriter::new(file),
+            format,
+            started: Instant::now(),
+            partial: Vec::new(),
+        };
+        if format == LogFormat::Asciicast {
+            let header = serde_json::json!({
+                "version": 2,
+                "width": size.0,
+                "height": size.1,
+                "timestamp": now_unix(),
+                "title": title,
+                "env": { "TERM": "xterm-256color" },
+            });
+            writeln!(recorder.out, "{header}")
+                .and_then(|_| recorder.out.flush())
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        *self.lock() = Some(recorder);
+        Ok(path)
+    }
+
+    pub fn stop(&self) {
+        if let Some(mut recorder) = self.lock().take() {
+            let _ = recorder.out.flush();
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// Record bytes sent by the backend. A write error ends the log.
+    pub fn output(&self, bytes: &[u8]) {
+        let mut guard = self.lock();
+        if let Some(recorder) = guard.as_mut()
+            && recorder.output(bytes).is_err()
+        {
+            *guard = None;
+        }
+    }
+
+    /// Record a terminal resize (asciicast only).
+    pub fn resize(&self, cols: usize, rows: usize) {
+        let mut guard = self.lock();
+        if let Some(recorder) = guard.as_mut()
+            && recorder.format == LogFormat::Asciicast
+            && recorder.event("r", &format!("{cols}x{rows}")).is_err()
+        {
+            *guard = None;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Recorder>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+struct Recorder {
+    out: BufWriter<File>,
+    format: LogFormat,
+    started: Instant,
+    /// Trailing bytes of a UTF-8 sequence split across reads (asciicast
+    /// events must be valid strings).
+    partial: Vec<u8>,
+}
+
+impl Recorder {
+    fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match self.format {
+            LogFormat::Raw => self.out.write_all(bytes)?,
+            LogFormat::Asciicast => {
+                self.partial.extend_from_slice(bytes);
+                let complete = match std::str::from_utf8(&self.partial) {
+                    Ok(_) => self.partial.len(),
+                    Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                    Err(_) => self.partial.len(),
+                };
+                let text = String::from_utf8_lossy(&self.partial[..complete]).into_owned();
+                self.partial.drain(..complete);
+                if !text.is_empty() {
+                    self.event("o", &text)?;
+                }
+            }
+        }
+        self.out.flush()
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        let time = (self.started.elapsed().as_secs_f64() * 1e6).round() / 1e6;
+        writeln!(self.out, "{}", serde_json::json!([time, code, data]))
+    }
+}
+
+/// Create `<date>_<title>.<ext>`, adding a counter if the name is taken.
+fn create_log_file(dir: &Path, title: &str, format: LogFormat) -> Result<(File, PathBuf), String> {
+    let stem = format!("{}_{}", timestamp_utc(now_unix()), file_slug(title));
+    for n in 1.. {
+        let name = match n {
+            1 => format!("{stem}.{}", format.extension()),
+            _ => format!("{stem}-{n}.{}", format.extension()),
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((file, path)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        }
+    }
+    unreachable!()
+}
+
+fn file_slug(title: &str) -> String {
+    let slug: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || "-_.@".contains(c) { c } else { '_' })
+        .take(64)
+        .collect();
+    if slug.is_empty() { "session".into() } else { slug }
+}
+
+/// `YYYY-MM-DD_HH-MM-SS` in UTC.
+fn timestamp_utc(secs: u64) -> String {
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil-from-days (proleptic Gregorian), as in H. Hinnant's date algorithms.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}_{:02}-{:02}-{:02}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_are_utc_civil_dates() {
+        assert_eq!(timestamp_utc(0), "1970-01-01_00-00-00");
+        assert_eq!(timestamp_utc(1_700_000_000), "2023-11-14_22-13-20");
+        assert_eq!(timestamp_utc(951_782_400), "2000-02-29_00-00-00");
+    }
+
+    #[test]
+    fn asciicast_keeps_split_utf8_together() {
+        let dir = std::env::temp_dir().join(format!("pfauterminal-log-{}", std::process::id()));
+        let log = SessionLog::default();
+        let path = log.start(&dir, LogFormat::Asciicast, "a/b", (80, 24)).unwrap();
+        let snowman = "☃".as_bytes();
+        log.output(&snowman[..1]);
+        log.output(&snowman[1..]);
+        log.resize(100, 30);
+        log.stop();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        let lines: Vec<serde_json::Value> =
+            text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert!(path.file_name().unwrap().to_str().unwrap().ends_with("_a_b.cast"));
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "☃");
+        assert_eq!(lines[2][2], "100x30");
+    }
+}
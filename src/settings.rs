@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+/// General preferences that are neither colors nor key bindings. Stored in
+/// the `settings` section of `config.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Render programming ligatures across cells.
+    pub ligatures: bool,
+    /// Keep color emoji fonts in the fallback chain. Read once at startup —
+    /// the font database can't be swapped under already-shaped buffers.
+    pub color_emoji: bool,
+    /// Whether to follow the OS dark/light appearance or force one.
+    pub appearance: Appearance,
+    /// Color scheme name (bundled or from the themes directory) used in dark
+    /// mode. `None` uses the config file's `colors` section.
+    #[serde(alias = "theme")]
+    pub dark_theme: Option<String>,
+    /// Color scheme used in light mode. `None` uses the built-in light scheme.
+    pub light_theme: Option<String>,
+    /// Lines of history kept per tab; profiles can override it.
+    pub scrollback: Scrollback,
+    /// Ceiling for `"unlimited"` scrollback, per tab, in MiB.
+    pub scrollback_memory_mb: usize,
+    /// Accept `pfauterminal msg` commands on a local socket. Any process
+    /// running as the same user can then type into the tabs. Read once at
+    /// startup.
+    pub remote_control: bool,
+}
+
+/// Scrollback history size: a line count, or `"unlimited"` (bounded only by
+/// `scrollback_memory_mb`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ScrollbackRepr", into = "ScrollbackRepr")]
+pub enum Scrollback {
+    Lines(usize),
+    Unlimited,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ScrollbackRepr {
+    Lines(usize),
+    Keyword(String),
+}
+
+impl TryFrom<ScrollbackRepr> for Scrollback {
+    type Error = String;
+
+    fn try_from(repr: ScrollbackRepr) -> Result<Self, String> {
+        match repr {
+            ScrollbackRepr::Lines(n) => Ok(Scrollback::Lines(n)),
+            ScrollbackRepr::Keyword(s) if s == "unlimited" => Ok(Scrollback::Unlimited),
+            ScrollbackRepr::Keyword(s) => {
+                Err(format!("expected a line count or \"unlimited\", found \"{s}\""))
+            }
+        }
+    }
+}
+
+impl From<Scrollback> for ScrollbackRepr {
+    fn from(scrollback: Scrollback) -> Self {
+        match scrollback {
+            Scrollback::Lines(n) => ScrollbackRepr::Lines(n),
+            Scrollback::Unlimited => ScrollbackRepr::Keyword("unlimited".into()),
+        }
+    }
+}
+
+impl Default for Scrollback {
+    fn default() -> Self {
+        Scrollback::Lines(10_000)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Appearance {
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ligatures: true,
+            color_emoji: false,
+            appearance: Appearance::System,
+            dark_theme: None,
+            light_theme: None,
+            scrollback: Scrollback::default(),
+            scrollback_memory_mb: 256,
+            remote_control: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Whether light mode is in effect, given the OS appearance.
+    pub fn is_light(&self, system_light: bool) -> bool {
+        match self.appearance {
+            Appearance::System => system_light,
+            Appearance::Dark => false,
+            Appearance::Light => true,
+        }
+    }
+
+    /// The theme slot for the given mode.
+    pub fn theme_mut(&mut self, light: bool) -> &mut Option<String> {
+        if light {
+            &mut self.light_theme
+        } else {
+            &mut self.dark_theme
+        }
+    }
+
+    pub fn save(&self) {
+        crate::config::update(|config| config.settings = self.clone());
+    }
+}
@@ -0,0 +1,188 @@
+//! Sixel decoding: the body of a `DCS … q … ST` sequence to RGBA pixels.
+//!
+//! Pixels no sixel touches stay transparent, whatever the background
+//! parameter asked for, and the pixel aspect ratio is taken as 1:1 as
+//! every current encoder assumes.
+
+/// Largest image accepted, per side, in pixels.
+pub const MAX_SIDE: usize = 4096;
+
+/// Decode sixel data (everything after the `q`) into `(width, height, rgba)`.
+pub fn decode(data: &[u8]) -> Option<(u32, u32, Vec<u8>)> {
+    // First pass sizes the image, second pass paints it.
+    let (mut width, mut height) = (0, 0);
+    let raster = parse(data, |x, y, run, bits, _| {
+        width = width.max(x.saturating_add(run));
+        height = height.max(y + 8 - bits.leading_zeros() as usize);
+    });
+    let width = width.max(raster.0).min(MAX_SIDE);
+    let height = height.max(raster.1).min(MAX_SIDE);
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut rgba = vec![0u8; width * height * 4];
+    parse(data, |x, y, run, bits, rgb| {
+        for px in x..x.saturating_add(run).min(width) {
+            for bit in 0..6 {
+                let py = y + bit;
+                if bits & (1 << bit) != 0 && py < height {
+                    let i = (py * width + px) * 4;
+                    rgba[i..i + 4].copy_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+                }
+            }
+        }
+    });
+    Some((width as u32, height as u32, rgba))
+}
+
+/// Walk the sixel commands, calling `plot(x, y, run, bits, rgb)` for every
+/// sixel (a column of six pixels, `run` times over). Returns the size from
+/// the raster attributes, `(0, 0)` if there were none.
+fn parse(data: &[u8], mut plot: impl FnMut(usize, usize, usize, u8, [u8; 3])) -> (usize, usize) {
+    let mut palette = default_palette();
+    let mut color = 0;
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut raster = (0, 0);
+    let mut i = 0;
+    while i < data.len() && y < MAX_SIDE {
+        let b = data[i];
+        i += 1;
+        match b {
+            // Raster attributes: "Pan;Pad;Ph;Pv
+            b'"' => {
+                let (params, next) = numbers(data, i);
+                i = next;
+                if let [_, _, w, h, ..] = params[..] {
+                    raster = (w, h);
+                }
+            }
+            // Color select "#Pc", or define "#Pc;Pu;Px;Py;Pz"
+            b'#' => {
+                let (params, next) = numbers(data, i);
+                i = next;
+                let Some(&index) = params.first() else { continue };
+                color = index & 0xff;
+                match params[1..] {
+                    [1, h, l, s] => palette[color] = hls_to_rgb(h, l, s),
+                    [2, r, g, b] => palette[color] = [r, g, b].map(percent),
+                    _ => {}
+                }
+            }
+            // Repeat: "!Pn" then the sixel to repeat
+            b'!' => {
+                let (params, next) = numbers(data, i);
+                i = next;
+                if let Some(&s @ 0x3f..=0x7e) = data.get(i) {
+                    i += 1;
+                    let run = params.first().copied().unwrap_or(1).max(1);
+                    plot(x, y, run, s - 0x3f, palette[color]);
+                    x = x.saturating_add(run);
+                }
+            }
+            b'$' => x = 0,
+            b'-' => {
+                x = 0;
+                y += 6;
+            }
+            0x3f..=0x7e => {
+                plot(x, y, 1, b - 0x3f, palette[color]);
+                x = x.saturating_add(1);
+            }
+            _ => {}
+        }
+    }
+    raster
+}
+
+/// Semicolon-separated decimal parameters starting at `i`; missing ones
+/// are 0. Returns them and the index after the last.
+fn numbers(data: &[u8], mut i: usize) -> (Vec<usize>, usize) {
+    let mut params = vec![0usize];
+    while let Some(&b) = data.get(i) {
+        match b {
+            b'0'..=b'9' => {
+                let last = params.last_mut().expect("params is never empty");
+                *last = last.saturating_mul(10).saturating_add(usize::from(b - b'0'));
+            }
+            b';' => params.push(0),
+            _ => break,
+        }
+        i += 1;
+    }
+    (params, i)
+}
+
+fn percent(value: usize) -> u8 {
+    ((value.min(100) * 255 + 50) / 100) as u8
+}
+
+/// DEC's HLS, whose hue wheel starts at blue rather than red.
+fn hls_to_rgb(h: usize, l: usize, s: usize) -> [u8; 3] {
+    let h = ((h + 240) % 360) as f32 / 360.0;
+    let l = l.min(100) as f32 / 100.0;
+    let s = s.min(100) as f32 / 100.0;
+    let q = if l < 0.5 { l * (1.0 + s) } else { l + s - l * s };
+    let p = 2.0 * l - q;
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.0);
+        let v = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (v * 255.0).round() as u8
+    };
+    [channel(h + 1.0 / 3.0), channel(h), channel(h - 1.0 / 3.0)]
+}
+
+/// The VT340's power-on colors; registers past 15 start black.
+fn default_palette() -> [[u8; 3]; 256] {
+    const VT340: [[usize; 3]; 16] = [
+        [0, 0, 0],
+        [20, 20, 80],
+        [80, 13, 13],
+        [20, 80, 20],
+        [80, 20, 80],
+        [20, 80, 80],
+        [80, 80, 20],
+        [53, 53, 53],
+        [26, 26, 26],
+        [33, 33, 60],
+        [60, 26, 26],
+        [33, 60, 33],
+        [60, 33, 60],
+        [33, 60, 60],
+        [60, 60, 33],
+        [80, 80, 80],
+    ];
+    let mut palette = [[0; 3]; 256];
+    for (slot, rgb) in palette.iter_mut().zip(VT340) {
+        *slot = rgb.map(percent);
+    }
+    palette
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_colors_repeats_and_bands() {
+        // 3x7: a red row of three on top, then one blue pixel in the next band.
+        let (w, h, rgba) = decode(b"\"1;1;3;7#1;2;100;0;0#1!3@-#2;2;0;0;100@").unwrap();
+        assert_eq!((w, h), (3, 7));
+        assert_eq!(&rgba[..4], &[255, 0, 0, 255]);
+        assert_eq!(&rgba[8..12], &[255, 0, 0, 255]);
+        // Row 1 of the first band was never painted.
+        assert_eq!(&rgba[12..16], &[0, 0, 0, 0]);
+        assert_eq!(&rgba[6 * 12..6 * 12 + 4], &[0, 0, 255, 255]);
+
+        assert_eq!(hls_to_rgb(120, 50, 100), [255, 0, 0]);
+        assert!(decode(b"#0").is_none());
+    }
+}
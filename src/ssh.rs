@@ -1,15 +1,19 @@
 use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use alacritty_terminal::sync::FairMutex;
-use alacritty_terminal::term::Config;
 use alacritty_terminal::vte::ansi;
 use alacritty_terminal::Term;
+use tokio::io::AsyncReadExt;
 use tokio::sync::mpsc;
 
 use russh::Pty;
 
-use crate::terminal_panel::{EventProxy, TermSize};
+use crate::output::OutputPipeline;
+use crate::terminal_panel::{EventProxy, TermSize, term_config};
+use crate::upload::{self, RemoteCwd, UploadBatch, Uploads};
 
 /// SSH connection configuration.
 #[derive(Debug, Clone)]
@@ -32,6 +36,14 @@ pub enum SshAuth {
 pub enum SshMsg {
     Input(Cow<'static, [u8]>),
     Resize { cols: u16, rows: u16 },
+    /// Copy local files into the remote shell's working directory.
+    Upload(Vec<PathBuf>),
+}
+
+/// Side channels fed from, or alongside, the session's output.
+struct SessionHooks {
+    output: OutputPipeline,
+    uploads: Uploads,
 }
 
 struct SshHandler;
@@ -53,8 +65,10 @@ pub fn spawn_ssh_thread(
     config: SshConfig,
     size: TermSize,
     event_proxy: EventProxy,
+    output: OutputPipeline,
+    uploads: Uploads,
 ) -> (Arc<FairMutex<Term<EventProxy>>>, mpsc::UnboundedSender<SshMsg>) {
-    let term = Term::new(Config::default(), &size, event_proxy.clone());
+    let term = Term::new(term_config(), &size, event_proxy.clone());
     let term = Arc::new(FairMutex::new(term));
     let (tx, rx) = mpsc::unbounded_channel();
 
@@ -81,7 +95,11 @@ pub fn spawn_ssh_thread(
                 }
             };
             rt.block_on(async move {
-                if let Err(e) = ssh_session(config, term_clone.clone(), event_proxy.clone(), rx, cols, rows).await {
+                let hooks = SessionHooks { output, uploads };
+                let session = ssh_session(
+                    config, term_clone.clone(), event_proxy.clone(), rx, hooks, cols, rows,
+                );
+                if let Err(e) = session.await {
                     use alacritty_terminal::event::EventListener;
                     write_to_term(&term_clone, &format!(
                         "\x1b[?25l\r\n\x1b[31mSSH error: {e}\x1b[0m\r\n"
@@ -111,12 +129,14 @@ async fn ssh_session(
     term: Arc<FairMutex<Term<EventProxy>>>,
     event_proxy: EventProxy,
     mut rx: mpsc::UnboundedReceiver<SshMsg>,
+    hooks: SessionHooks,
     cols: u16,
     rows: u16,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use alacritty_terminal::event::Event;
     use alacritty_terminal::event::EventListener;
 
+    let SessionHooks { mut output, uploads } = hooks;
     let russh_config = Arc::new(russh::client::Config::default());
     let addr = format!("{}:{}", config.host, config.port);
 
@@ -221,6 +241,12 @@ async fn ssh_session(
     // Re-enable cursor now that the remote shell is ready
     write_to_term(&term, "\x1b[?25h");
 
+    // Dropped files go up one batch at a time on their own channels
+    let session = Arc::new(session);
+    let (upload_tx, upload_rx) = mpsc::unbounded_channel();
+    tokio::spawn(run_uploads(session.clone(), upload_rx, uploads, event_proxy.clone()));
+    let mut cwd = RemoteCwd::default();
+
     event_proxy.send_event(Event::Title(format!(
         "{}@{}",
         config.username, config.host
@@ -228,6 +254,7 @@ async fn ssh_session(
 
     // Main I/O loop
     let mut parser = ansi::Processor::<ansi::StdSyncHandler>::new();
+    let mut filtered = Vec::new();
 
     loop {
         tokio::select! {
@@ -235,9 +262,12 @@ async fn ssh_session(
                 match msg {
                     Some(russh::ChannelMsg::Data { data }
                         | russh::ChannelMsg::ExtendedData { data, .. }) => {
+                        cwd.feed(&data);
+                        filtered.clear();
+                        output.process(&data, &mut filtered);
                         {
                             let mut t = term.lock();
-                            parser.advance(&mut *t, &data);
+                            parser.advance(&mut *t, &filtered);
                         }
                         event_proxy.send_event(Event::Wakeup);
                     }
@@ -256,6 +286,10 @@ async fn ssh_session(
                     Some(SshMsg::Resize { cols, rows }) => {
                         channel.window_change(cols as u32, rows as u32, 0, 0).await?;
                     }
+                    Some(SshMsg::Upload(files)) => {
+                        let dir = cwd.cwd().map(str::to_string);
+                        let _ = upload_tx.send(UploadBatch { files, dir });
+                    }
                     None => break,
                 }
             }
@@ -268,6 +302,180 @@ async fn ssh_session(
     Ok(())
 }
 
+/// Upload batches of dropped files in order, reporting progress to the panel.
+async fn run_uploads(
+    session: Arc<russh::client::Handle<SshHandler>>,
+    mut batches: mpsc::UnboundedReceiver<UploadBatch>,
+    uploads: Uploads,
+    event_proxy: EventProxy,
+) {
+    use alacritty_terminal::event::{Event, EventListener};
+
+    while let Some(batch) = batches.recv().await {
+        let mut total = 0;
+        for file in &batch.files {
+            total += tokio::fs::metadata(file).await.map_or(0, |m| m.len());
+        }
+        let place = batch.dir.clone().unwrap_or_else(|| "~".into());
+        let count = batch.files.len();
+        let mut sent = 0;
+        let mut last_wake = Instant::now();
+        let mut result = Ok(());
+        for (i, file) in batch.files.iter().enumerate() {
+            let name = file_name(file);
+            let label = match count {
+                1 => format!("Uploading {name} to {place}"),
+                _ => format!("Uploading {name} to {place} ({}/{count})", i + 1),
+            };
+            uploads.progress(label.clone(), sent, total);
+            event_proxy.send_event(Event::Wakeup);
+            result = upload_file(&session, file, batch.dir.as_deref(), |n| {
+                sent += n;
+                uploads.progress(label.clone(), sent, total);
+                // Progress only needs a few frames a second.
+                if last_wake.elapsed() >= Duration::from_millis(100) {
+                    last_wake = Instant::now();
+                    event_proxy.send_event(Event::Wakeup);
+                }
+            })
+            .await;
+            if result.is_err() {
+                break;
+            }
+        }
+        match result {
+            Ok(()) if count == 1 => {
+                let name = file_name(&batch.files[0]);
+                uploads.finish(format!("Uploaded {name} to {place}"), false);
+            }
+            Ok(()) => uploads.finish(format!("Uploaded {count} files to {place}"), false),
+            Err(e) => uploads.finish(format!("Upload failed: {e}"), true),
+        }
+        event_proxy.send_event(Event::Wakeup);
+        // Redraw once more when the result line should disappear.
+        let proxy = event_proxy.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(upload::RESULT_LINGER).await;
+            proxy.send_event(Event::Wakeup);
+        });
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into())
+}
+
+/// Exit status the upload command uses when the target already exists.
+const EXISTS_STATUS: u32 = 17;
+/// Exit status when fewer bytes arrived than the file holds.
+const INCOMPLETE_STATUS: u32 = 18;
+
+/// Stream one file into `cat` on a new exec channel, calling `progress`
+/// with each chunk's size. The data goes to a part file next to the target
+/// that this upload creates itself (`set -C`), and is moved into place only
+/// once complete and never over an existing file. A failed upload removes
+/// the part file, nothing else.
+async fn upload_file(
+    session: &russh::client::Handle<SshHandler>,
+    path: &Path,
+    dir: Option<&str>,
+    progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let name = file_name(path);
+    let file = tokio::fs::File::open(path).await.map_err(|e| format!("{name}: {e}"))?;
+    let size = file.metadata().await.map_err(|e| format!("{name}: {e}"))?.len();
+    let dir = dir.map(|d| format!("{}/", d.trim_end_matches('/'))).unwrap_or_default();
+    let target = upload::sh_quote(&format!("{dir}{name}"));
+    let part = upload::sh_quote(&format!("{dir}.{name}.pfauterminal-part"));
+
+    let mut channel = session.channel_open_session().await.map_err(|e| e.to_string())?;
+    // The empty line tells us the part file is ours before any data is sent.
+    let command = format!(
+        "test -e {target} && exit {EXISTS_STATUS}; set -C; : > {part} || exit; echo; \
+         cat >> {part} && test $(wc -c < {part}) -eq {size} \
+         || {{ rm -f {part}; exit {INCOMPLETE_STATUS}; }}; \
+         mv -n {part} {target} || {{ rm -f {part}; exit 1; }}; \
+         test -e {part} && {{ rm -f {part}; exit {EXISTS_STATUS}; }}; exit 0"
+    );
+    channel.exec(true, command).await.map_err(|e| e.to_string())?;
+    let mut created = false;
+    let error = match send_file(&mut channel, file.take(size), &name, &mut created, progress).await
+    {
+        Ok((0, _)) => return Ok(()),
+        Ok((EXISTS_STATUS, _)) => return Err(format!("{name} already exists on the remote host")),
+        Ok((INCOMPLETE_STATUS, _)) => format!("{name}: upload was cut short"),
+        Ok((status, stderr)) => match String::from_utf8_lossy(&stderr).trim() {
+            "" => format!("{name}: exit status {status}"),
+            message => message.to_string(),
+        },
+        Err(e) => e,
+    };
+    let _ = channel.close().await;
+    // The remote shell cleans up after itself when it gets to; this is for
+    // a session that went away mid-upload.
+    if created {
+        remove_partial(session, &part).await;
+    }
+    Err(error)
+}
+
+/// Wait for the remote side to create the part file, send the file, and
+/// wait for the command to exit; returns its exit status and what it wrote
+/// to stderr. `created` is set once the part file exists.
+async fn send_file(
+    channel: &mut russh::Channel<russh::client::Msg>,
+    mut file: tokio::io::Take<tokio::fs::File>,
+    name: &str,
+    created: &mut bool,
+    mut progress: impl FnMut(u64),
+) -> Result<(u32, Vec<u8>), String> {
+    let mut stderr = Vec::new();
+    while !*created {
+        match channel.wait().await {
+            Some(russh::ChannelMsg::Data { .. }) => *created = true,
+            Some(russh::ChannelMsg::ExtendedData { data, .. }) => stderr.extend_from_slice(&data),
+            Some(russh::ChannelMsg::ExitStatus { exit_status }) => {
+                return Ok((exit_status, stderr));
+            }
+            Some(_) => {}
+            None => return Err(format!("{name}: channel closed")),
+        }
+    }
+
+    let mut buf = vec![0u8; 32 * 1024];
+    loop {
+        let n = file.read(&mut buf).await.map_err(|e| format!("{name}: {e}"))?;
+        if n == 0 {
+            break;
+        }
+        channel.data(&buf[..n]).await.map_err(|e| e.to_string())?;
+        progress(n as u64);
+    }
+    channel.eof().await.map_err(|e| e.to_string())?;
+
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            russh::ChannelMsg::ExtendedData { data, .. } => stderr.extend_from_slice(&data),
+            russh::ChannelMsg::ExitStatus { exit_status } => return Ok((exit_status, stderr)),
+            _ => {}
+        }
+    }
+    Err(format!("{name}: channel closed"))
+}
+
+/// Best-effort removal of an upload's part file (`part` is quoted).
+async fn remove_partial(session: &russh::client::Handle<SshHandler>, part: &str) {
+    let Ok(mut channel) = session.channel_open_session().await else { return };
+    if channel.exec(true, format!("rm -f {part}")).await.is_err() {
+        return;
+    }
+    while let Some(msg) = channel.wait().await {
+        if let russh::ChannelMsg::ExitStatus { .. } = msg {
+            break;
+        }
+    }
+}
+
 async fn try_agent_auth(
     session: &mut russh::client::Handle<SshHandler>,
     username: &str,
@@ -5,7 +5,7 @@ use winit::window::CursorIcon;
 
 use crate::colors::ColorScheme;
 use crate::draw::DrawContext;
-use crate::dropdown::{DropdownElement, DropdownMenu, MenuAction, MenuEntry};
+use crate::dropdown::{DropdownElement, DropdownKey, DropdownMenu, MenuAction, MenuEntry};
 use crate::layout::Rect;
 use crate::theme::{DialogTheme, Theme};
 use crate::widgets::{Button, ButtonKind, Label, TextField};
@@ -608,37 +608,7 @@ impl SshDialog {
                 None
             }
             DialogHit::DropdownItem(idx) => {
-                if let Some(method) = AuthMethod::from_index(idx) {
-                    let old_method = self.auth_method;
-                    self.auth_method = method;
-                    self.auth_dropdown.close();
-                    if old_method != method {
-                        let t = &self.dialog_theme;
-                        let metrics =
-                            Metrics::new(t.font_size, t.font_size * LINE_HEIGHT_MULT);
-                        let attrs = crate::font::default_attrs();
-                        self.auth_value_label = Label::new(
-                            method.display_text(),
-                            attrs,
-                            metrics,
-                            font_system,
-                        );
-                        // Reposition label
-                        let dd = &self.auth_dropdown_rect;
-                        let line_h = t.font_size * LINE_HEIGHT_MULT;
-                        let text_y = dd.y + (dd.height - line_h * self.scale) / 2.0;
-                        let pad = t.field_pad_h * self.scale;
-                        self.auth_value_label
-                            .set_position(dd.x + pad, text_y, *dd);
-                        self.auth_value_label
-                            .set_color(self.label_color);
-                        match method {
-                            AuthMethod::Password => self.set_focus(FocusedField::Password),
-                            AuthMethod::Key => self.set_focus(FocusedField::KeyPath),
-                            AuthMethod::Agent => {}
-                        }
-                    }
-                }
+                self.select_auth_method(idx, font_system);
                 None
             }
             DialogHit::BrowseButton => None,
@@ -655,6 +625,33 @@ impl SshDialog {
         }
     }
 
+    /// Switch to auth dropdown item `idx` and close the dropdown.
+    fn select_auth_method(&mut self, idx: usize, font_system: &mut FontSystem) {
+        let Some(method) = AuthMethod::from_index(idx) else { return };
+        let old_method = self.auth_method;
+        self.auth_method = method;
+        self.auth_dropdown.close();
+        if old_method != method {
+            let t = &self.dialog_theme;
+            let metrics = Metrics::new(t.font_size, t.font_size * LINE_HEIGHT_MULT);
+            let attrs = crate::font::default_attrs();
+            self.auth_value_label =
+                Label::new(method.display_text(), attrs, metrics, font_system);
+            // Reposition label
+            let dd = &self.auth_dropdown_rect;
+            let line_h = t.font_size * LINE_HEIGHT_MULT;
+            let text_y = dd.y + (dd.height - line_h * self.scale) / 2.0;
+            let pad = t.field_pad_h * self.scale;
+            self.auth_value_label.set_position(dd.x + pad, text_y, *dd);
+            self.auth_value_label.set_color(self.label_color);
+            match method {
+                AuthMethod::Password => self.set_focus(FocusedField::Password),
+                AuthMethod::Key => self.set_focus(FocusedField::KeyPath),
+                AuthMethod::Agent => {}
+            }
+        }
+    }
+
     fn open_auth_dropdown(
         &mut self,
         font_system: &mut FontSystem,
@@ -684,7 +681,11 @@ impl SshDialog {
         }
 
         if self.auth_dropdown.is_open() {
-            self.auth_dropdown.close();
+            match self.auth_dropdown.key(event) {
+                DropdownKey::Activate(idx) => self.select_auth_method(idx, font_system),
+                DropdownKey::Close => self.auth_dropdown.close(),
+                DropdownKey::Handled | DropdownKey::Remove(_) => {}
+            }
             return None;
         }
 
@@ -855,11 +856,9 @@ impl SshDialog {
         shift_pressed: bool,
     ) -> Result<Option<SshResult>, ()> {
         if event.state == ElementState::Pressed {
-            if let Key::Named(NamedKey::Escape) = event.logical_key.as_ref() {
-                if self.auth_dropdown.is_open() {
-                    self.auth_dropdown.close();
-                    return Ok(None);
-                }
+            if let Key::Named(NamedKey::Escape) = event.logical_key.as_ref()
+                && !self.auth_dropdown.is_open()
+            {
                 return Err(());
             }
 
@@ -1058,6 +1057,29 @@ impl SshDialog {
     // Accessors
     // -----------------------------------------------------------------------
 
+    /// Show an IME composition in the focused field.
+    pub fn set_preedit(&mut self, text: &str, cursor: Option<usize>, font_system: &mut FontSystem) {
+        self.focused_field_widget_mut().set_preedit(text, cursor, font_system);
+    }
+
+    /// Insert text committed by the IME into the focused field.
+    pub fn commit_text(&mut self, text: &str, font_system: &mut FontSystem) {
+        self.insert_text(text, font_system);
+    }
+
+    /// The focused field's caret, for placing the IME candidate window.
+    pub fn ime_area(&self) -> Rect {
+        let field = match self.focused_field {
+            FocusedField::Host => &self.host_field,
+            FocusedField::Port => &self.port_field,
+            FocusedField::Username => &self.username_field,
+            FocusedField::Password => &self.password_field,
+            FocusedField::KeyPath => &self.keypath_field,
+            FocusedField::Passphrase => &self.passphrase_field,
+        };
+        field.cursor_rect(self.scale)
+    }
+
     /// Access the auth dropdown (for drawing and buffer access).
     pub fn auth_dropdown(&self) -> &DropdownMenu {
         &self.auth_dropdown
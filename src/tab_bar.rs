@@ -3,6 +3,7 @@ use glyphon::{Buffer, FontSystem, Metrics, Shaping};
 use crate::draw::{centered_text, DrawContext};
 use crate::font;
 use crate::font::LINE_HEIGHT as TAB_LINE_HEIGHT;
+use crate::colors::TabColor;
 use crate::icons;
 use crate::layout::{update_if_changed, Rect, TextSpec};
 use crate::theme::{TabBarTheme, Theme};
@@ -13,26 +14,51 @@ pub enum TabBarElement {
     Tab(usize),
     CloseButton(usize),
     PlusButton,
+    /// Lists every tab when they don't all fit.
+    OverflowButton,
+}
+
+/// What the tab bar shows for one tab.
+pub struct TabLabel {
+    pub title: String,
+    /// Typed input is broadcast to this tab.
+    pub broadcast: bool,
+    pub color: Option<TabColor>,
 }
 
 pub struct TabBar {
     tab_buffers: Vec<Buffer>,
+    broadcast: Vec<bool>,
+    colors: Vec<Option<TabColor>>,
     tab_rects: Vec<Rect>,
     close_rects: Vec<Rect>,
     plus_rect: Rect,
+    /// `Rect::ZERO` unless tabs are scrolled out of view.
+    overflow_rect: Rect,
     active_tab: usize,
     hover: TabBarElement,
+    /// First tab shown and how many fit; tabs outside have zero-size rects.
+    first: usize,
+    visible: usize,
+    /// Fractional scroll left over from trackpad deltas.
+    scroll_accum: f32,
 }
 
 impl TabBar {
     pub fn new() -> Self {
         Self {
             tab_buffers: Vec::new(),
+            broadcast: Vec::new(),
+            colors: Vec::new(),
             tab_rects: Vec::new(),
             close_rects: Vec::new(),
             plus_rect: Rect::ZERO,
+            overflow_rect: Rect::ZERO,
             active_tab: 0,
             hover: TabBarElement::None,
+            first: 0,
+            visible: 0,
+            scroll_accum: 0.0,
         }
     }
 
@@ -42,7 +68,7 @@ impl TabBar {
 
     pub fn update(
         &mut self,
-        titles: &[String],
+        tabs: &[TabLabel],
         active: usize,
         surface_width: f32,
         y_offset: f32,
@@ -50,7 +76,10 @@ impl TabBar {
         font_system: &mut FontSystem,
         theme: &TabBarTheme,
     ) {
+        let activated = active != self.active_tab;
         self.active_tab = active;
+        self.broadcast = tabs.iter().map(|t| t.broadcast).collect();
+        self.colors = tabs.iter().map(|t| t.color).collect();
 
         let tab_metrics = Metrics::new(theme.font_size, theme.font_size * TAB_LINE_HEIGHT);
         let pad_h = theme.tab_padding_h * scale_factor;
@@ -61,44 +90,57 @@ impl TabBar {
         let close_size = theme.close_size * scale_factor;
         let bar_h = theme.height * scale_factor;
         let plus_w = theme.plus_size * scale_factor;
+        let min_w = theme.tab_min_width * scale_factor;
 
         let tab_h = pad_v * 2.0 + icon_size;
         let margin_top = y_offset + (bar_h - tab_h) / 2.0;
+        let chrome = pad_h + icon_size + icon_gap + icon_gap + close_size + pad_h;
 
         // Resize buffers
-        while self.tab_buffers.len() < titles.len() {
+        while self.tab_buffers.len() < tabs.len() {
             self.tab_buffers.push(Buffer::new(font_system, tab_metrics));
         }
-        self.tab_buffers.truncate(titles.len());
+        self.tab_buffers.truncate(tabs.len());
 
-        // Compute tab widths
-        let mut tab_widths = Vec::with_capacity(titles.len());
-        for (i, title) in titles.iter().enumerate() {
+        // Natural tab widths
+        let mut tab_widths = Vec::with_capacity(tabs.len());
+        for (i, tab) in tabs.iter().enumerate() {
             let buf = &mut self.tab_buffers[i];
             buf.set_metrics(font_system, tab_metrics);
             buf.set_size(font_system, Some(300.0), Some(tab_metrics.line_height));
-            buf.set_text(font_system, title, font::default_attrs(), Shaping::Advanced);
+            buf.set_text(font_system, &tab.title, font::default_attrs(), Shaping::Advanced);
             buf.shape_until_scroll(font_system, false);
 
-            let text_width = buf
-                .layout_runs()
-                .next()
-                .map(|run| run.glyphs.iter().map(|g| g.w).sum::<f32>())
-                .unwrap_or(50.0)
-                * scale_factor;
-
-            let tab_width =
-                pad_h + icon_size + icon_gap + text_width + icon_gap + close_size + pad_h;
-            tab_widths.push(tab_width);
+            let text_width = line_width(buf).unwrap_or(50.0) * scale_factor;
+            tab_widths.push(chrome + text_width);
         }
 
-        let total_tabs_width: f32 = tab_widths.iter().sum();
-        let total_gaps = if titles.is_empty() {
-            0.0
+        // Room for the tabs (each followed by a gap) before the "+" button.
+        // When natural widths don't fit, tabs shrink evenly down to the
+        // minimum; past that the bar scrolls and gets an overflow button.
+        let n = tabs.len();
+        let mut room = surface_width - gap - plus_w;
+        let natural_total: f32 = tab_widths.iter().map(|w| w + gap).sum();
+        let (cap, overflow) = if n == 0 || natural_total <= room {
+            (f32::INFINITY, false)
         } else {
-            gap * titles.len() as f32
+            let even = room / n as f32 - gap;
+            if even >= min_w { (even, false) } else { (min_w, true) }
         };
-        let total_width = total_tabs_width + total_gaps + plus_w;
+        if overflow {
+            room -= plus_w + gap;
+            self.visible = ((room / (min_w + gap)) as usize).clamp(1, n);
+        } else {
+            self.visible = n;
+        }
+
+        // Keep a newly activated tab in view; otherwise hold the scroll position.
+        if activated && active < self.first {
+            self.first = active;
+        } else if activated && active >= self.first + self.visible {
+            self.first = active + 1 - self.visible;
+        }
+        self.first = self.first.min(n - self.visible);
 
         let panel_x = y_offset;
         let mut x = panel_x + gap;
@@ -106,7 +148,20 @@ impl TabBar {
         self.tab_rects.clear();
         self.close_rects.clear();
 
-        for &tab_width in &tab_widths {
+        for (i, (tab, &natural)) in tabs.iter().zip(&tab_widths).enumerate() {
+            if i < self.first || i >= self.first + self.visible {
+                self.tab_rects.push(Rect::ZERO);
+                self.close_rects.push(Rect::ZERO);
+                continue;
+            }
+
+            let tab_width = natural.min(cap);
+            if tab_width < natural {
+                let max_text = (tab_width - chrome) / scale_factor;
+                let buf = &mut self.tab_buffers[i];
+                ellipsize(buf, font_system, &tab.title, max_text, theme.font_size);
+            }
+
             self.tab_rects.push(Rect {
                 x,
                 y: margin_top,
@@ -127,6 +182,18 @@ impl TabBar {
         }
 
         let plus_y = y_offset + (bar_h - plus_w) / 2.0;
+        self.overflow_rect = if overflow {
+            let rect = Rect {
+                x,
+                y: plus_y,
+                width: plus_w,
+                height: plus_w,
+            };
+            x += plus_w + gap;
+            rect
+        } else {
+            Rect::ZERO
+        };
         self.plus_rect = Rect {
             x,
             y: plus_y,
@@ -135,8 +202,38 @@ impl TabBar {
         };
     }
 
+    /// Scroll the tab strip by `delta` tabs (positive reveals tabs to the
+    /// right). Returns true if the visible range changed; the caller must
+    /// then call [`TabBar::update`] to lay it out.
+    pub fn scroll(&mut self, delta: f32) -> bool {
+        if !self.is_overflowing() {
+            self.scroll_accum = 0.0;
+            return false;
+        }
+        self.scroll_accum += delta;
+        let steps = self.scroll_accum.trunc();
+        self.scroll_accum -= steps;
+        let max_first = self.tab_rects.len() - self.visible;
+        let first = (self.first as isize + steps as isize).clamp(0, max_first as isize) as usize;
+        update_if_changed(&mut self.first, first)
+    }
+
+    pub fn is_overflowing(&self) -> bool {
+        self.visible < self.tab_rects.len()
+    }
+
+    /// Index of the visible tab spanning horizontal position `x`.
+    pub fn tab_at(&self, x: f32) -> Option<usize> {
+        self.tab_rects
+            .iter()
+            .position(|r| r.width > 0.0 && x >= r.x && x < r.x + r.width)
+    }
+
     pub fn hit_test(&self, x: f32, y: f32) -> TabBarElement {
         for (i, rect) in self.close_rects.iter().enumerate() {
+            if rect.width == 0.0 {
+                continue;
+            }
             let is_active = i == self.active_tab;
             let is_tab_hovered = matches!(self.hover, TabBarElement::Tab(idx) | TabBarElement::CloseButton(idx) if idx == i);
             let pad = 4.0;
@@ -153,6 +250,9 @@ impl TabBar {
                 return TabBarElement::Tab(i);
             }
         }
+        if self.overflow_rect.contains(x, y) {
+            return TabBarElement::OverflowButton;
+        }
         if self.plus_rect.contains(x, y) {
             return TabBarElement::PlusButton;
         }
@@ -192,13 +292,50 @@ impl TabBar {
         let hover_stroke = colors.tab_hover_stroke.to_linear_f32();
 
         for (i, rect) in self.tab_rects.iter().enumerate() {
+            if rect.width == 0.0 {
+                continue;
+            }
             let is_active = i == self.active_tab;
             let is_hovered = matches!(self.hover, TabBarElement::Tab(idx) | TabBarElement::CloseButton(idx) if idx == i);
 
-            if is_active {
-                ctx.stroked_rect(rect, active_stroke, active_fill, radius, border);
-            } else if is_hovered {
-                ctx.stroked_rect(rect, hover_stroke, hover_bg, radius, border);
+            let tint = self.colors.get(i).copied().flatten().map(|c| c.resolve(colors));
+            match tint {
+                // Labelled tabs are always tinted; stronger when active or hovered
+                Some(tint) => {
+                    let (stroke, fill) = match (is_active, is_hovered) {
+                        (true, _) => (tint, tint.with_alpha(0x59)),
+                        (false, true) => (tint.with_alpha(0xB3), tint.with_alpha(0x33)),
+                        (false, false) => (tint.with_alpha(0x66), tint.with_alpha(0x1F)),
+                    };
+                    ctx.stroked_rect(
+                        rect,
+                        stroke.to_linear_f32(),
+                        fill.to_linear_f32(),
+                        radius,
+                        border,
+                    );
+                }
+                None if is_active => {
+                    ctx.stroked_rect(rect, active_stroke, active_fill, radius, border);
+                }
+                None if is_hovered => {
+                    ctx.stroked_rect(rect, hover_stroke, hover_bg, radius, border);
+                }
+                None => {}
+            }
+
+            if self.broadcast.get(i).copied().unwrap_or(false) {
+                let h = t.indicator_height * scale_factor;
+                ctx.rounded_rect(
+                    Rect {
+                        x: rect.x + radius,
+                        y: rect.y + rect.height - h,
+                        width: rect.width - 2.0 * radius,
+                        height: h,
+                    },
+                    colors.yellow.to_linear_f32(),
+                    h / 2.0,
+                );
             }
 
             // Terminal icon
@@ -237,6 +374,24 @@ impl TabBar {
             }
         }
 
+        // Overflow button: a small downward chevron
+        if self.overflow_rect.width > 0.0 {
+            let r = self.overflow_rect;
+            if matches!(self.hover, TabBarElement::OverflowButton) {
+                ctx.rounded_rect(r, hover_bg, plus_radius);
+            }
+            let half = plus_icon_size / 2.0;
+            let (cx, cy) = (r.x + r.width / 2.0, r.y + r.height / 2.0);
+            ctx.flat_triangle(
+                [
+                    [cx - half, cy - half / 2.0],
+                    [cx + half, cy - half / 2.0],
+                    [cx, cy + half / 2.0],
+                ],
+                colors.foreground.to_linear_f32(),
+            );
+        }
+
         // "+" button
         if matches!(self.hover, TabBarElement::PlusButton) {
             ctx.rounded_rect(self.plus_rect, hover_bg, plus_radius);
@@ -260,7 +415,54 @@ impl TabBar {
         self.plus_rect
     }
 
+    /// Bounds of tab `idx`; `Rect::ZERO` while it's scrolled out of view.
+    pub fn tab_rect(&self, idx: usize) -> Rect {
+        self.tab_rects.get(idx).copied().unwrap_or(Rect::ZERO)
+    }
+
+    pub fn overflow_rect(&self) -> Rect {
+        self.overflow_rect
+    }
+
     pub fn tab_buffers(&self) -> &[Buffer] {
         &self.tab_buffers
     }
 }
+
+/// Width of the first laid-out line, in unscaled pixels.
+fn line_width(buf: &Buffer) -> Option<f32> {
+    buf.layout_runs()
+        .next()
+        .map(|run| run.glyphs.iter().map(|g| g.w).sum::<f32>())
+}
+
+/// Replace the buffer's text with `title` cut to fit `max_width` (unscaled
+/// pixels) and ended with an ellipsis.
+fn ellipsize(
+    buf: &mut Buffer,
+    font_system: &mut FontSystem,
+    title: &str,
+    max_width: f32,
+    font_size: f32,
+) {
+    // Cut where the glyphs pass the limit, leaving about an em for the "…".
+    let mut cut = buf
+        .layout_runs()
+        .next()
+        .and_then(|run| {
+            run.glyphs
+                .iter()
+                .find(|g| g.x + g.w > max_width - font_size)
+                .map(|g| g.start)
+        })
+        .unwrap_or(title.len());
+    loop {
+        let text = format!("{}\u{2026}", title[..cut].trim_end());
+        buf.set_text(font_system, &text, font::default_attrs(), Shaping::Advanced);
+        buf.shape_until_scroll(font_system, false);
+        if cut == 0 || line_width(buf).is_none_or(|w| w <= max_width) {
+            break;
+        }
+        cut = title[..cut].char_indices().next_back().map_or(0, |(i, _)| i);
+    }
+}
@@ -1,31 +1,45 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-use alacritty_terminal::event::{Event, EventListener, WindowSize};
+use alacritty_terminal::event::{Event, EventListener, VoidListener, WindowSize};
 use alacritty_terminal::event_loop::{EventLoop, EventLoopSender, Msg};
 use alacritty_terminal::grid::Dimensions;
 use alacritty_terminal::index::{Column, Line, Point, Side};
 use alacritty_terminal::selection::{Selection, SelectionType};
 use alacritty_terminal::sync::FairMutex;
 use alacritty_terminal::term::{self, TermMode};
-use alacritty_terminal::vte::ansi::{Color, CursorShape};
+use alacritty_terminal::vte::ansi::{Color, CursorShape, NamedColor};
 use alacritty_terminal::tty;
 use alacritty_terminal::term::cell::Flags;
 use alacritty_terminal::Term;
-use glyphon::{Buffer, Color as GlyphonColor, FontSystem, Shaping};
+use glyphon::{Buffer, Color as GlyphonColor, FontSystem, Shaping, TextArea};
 use tokio::sync::mpsc;
 use winit::event::{ElementState, KeyEvent, MouseScrollDelta};
 use winit::event_loop::EventLoopProxy;
-use winit::keyboard::{Key, KeyCode, NamedKey, PhysicalKey};
+use winit::keyboard::{KeyCode, PhysicalKey};
 
-use crate::colors::ColorScheme;
+use crate::builtin_glyphs;
+use crate::colors::{ColorScheme, TabColor};
+use crate::config_watch::ConfigFile;
 use crate::draw::DrawContext;
+use crate::export::{self, ExportFormat};
 use crate::font::{self, CellMetrics};
-use crate::layout::{CursorData, Rect, TextSpec};
+use crate::images::{self, ImageFilter, Images};
+use crate::ipc::{Reply, Request};
+use crate::kitty_graphics::{self, PlaceholderCell};
+use crate::kitty_keys;
+use crate::upload::Uploads;
+use crate::xterm_keys::{self, OtherKeys};
+use crate::layout::{CursorData, ImageQuad, Rect, TextSpec};
+use crate::output::{self, OutputPipeline};
 use crate::ssh::{SshConfig, SshMsg};
+use crate::replay::{self, Recording, ReplayControl};
+use crate::session_log::{LogFormat, SessionLog};
+use crate::settings::Scrollback;
 use crate::theme::PanelTheme;
 
 // --- Events ---
@@ -36,10 +50,53 @@ pub enum TerminalEvent {
     Wakeup,
     Title(PanelId, String),
     Exit(PanelId),
+    /// A file in the config directory changed on disk.
+    ConfigChanged(ConfigFile),
+    /// A remote-control request from the IPC socket, answered through the reply.
+    Ipc(Request, Reply),
 }
 
 // --- Panel types ---
 
+/// What to run in a new local panel.
+#[derive(Debug, Clone, Default)]
+pub struct Launch {
+    /// Program to run; `None` uses the platform's default login shell.
+    pub shell: Option<String>,
+    pub args: Vec<String>,
+    /// Start `shell` as a login shell (`--login` before `args`).
+    pub login: bool,
+    /// Extra environment variables, applied over the terminal defaults.
+    pub env: Vec<(String, String)>,
+    /// Working directory; `None` starts in the home directory.
+    pub cwd: Option<PathBuf>,
+}
+
+/// Per-tab overrides of the window-wide look, usually from a profile.
+#[derive(Clone, Default)]
+pub struct PanelStyle {
+    pub color_scheme: Option<ColorScheme>,
+    pub font_family: Option<String>,
+    /// Base font size; scaled by the window zoom like the default size.
+    pub font_size: Option<f32>,
+    /// Replaces the theme's island padding.
+    pub padding: Option<f32>,
+}
+
+/// How a tab was opened, so "Duplicate" can open another like it.
+#[derive(Debug, Clone, Default)]
+pub enum TabSource {
+    #[default]
+    None,
+    /// A shell, `None` being the default login shell.
+    Shell(Option<String>),
+    /// Started from the command line (`--command`, `--working-directory`).
+    Command(Launch),
+    Profile(String),
+    Ssh(SshConfig),
+    Replay(PathBuf),
+}
+
 static NEXT_PANEL_ID: AtomicU64 = AtomicU64::new(1);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -49,6 +106,11 @@ impl PanelId {
     pub fn next() -> Self {
         Self(NEXT_PANEL_ID.fetch_add(1, Ordering::Relaxed))
     }
+
+    /// The number remote-control clients address the tab by.
+    pub fn get(self) -> u64 {
+        self.0
+    }
 }
 
 pub struct PanelViewport {
@@ -170,10 +232,12 @@ impl ScrollbarState {
 
 // --- Event proxy (bridges alacritty events to winit) ---
 
-/// I/O backend: either a local PTY or an SSH channel.
+/// I/O backend: a local PTY, an SSH channel, or a recording being played
+/// back (which takes no input).
 enum Backend {
     Local(EventLoopSender),
     Ssh(mpsc::UnboundedSender<SshMsg>),
+    Replay(std::sync::mpsc::Sender<ReplayControl>),
 }
 
 impl Backend {
@@ -185,6 +249,7 @@ impl Backend {
             Backend::Ssh(tx) => {
                 let _ = tx.send(SshMsg::Input(data));
             }
+            Backend::Replay(_) => {}
         }
     }
 
@@ -204,6 +269,7 @@ impl Backend {
                     rows: size.screen_lines as u16,
                 });
             }
+            Backend::Replay(_) => {}
         }
     }
 }
@@ -214,6 +280,8 @@ pub struct EventProxy {
     proxy: EventLoopProxy<TerminalEvent>,
     panel_id: PanelId,
     backend: Arc<Mutex<Option<Backend>>>,
+    /// Forward OSC title changes from the terminal.
+    titles: bool,
 }
 
 impl EventProxy {
@@ -222,9 +290,23 @@ impl EventProxy {
             proxy,
             panel_id,
             backend: Arc::new(Mutex::new(None)),
+            titles: true,
         }
     }
 
+    /// A copy that drops the terminal's own title changes.
+    fn without_titles(&self) -> Self {
+        Self {
+            titles: false,
+            ..self.clone()
+        }
+    }
+
+    /// Set the tab title directly, bypassing the terminal.
+    pub fn set_title(&self, title: String) {
+        let _ = self.proxy.send_event(TerminalEvent::Title(self.panel_id, title));
+    }
+
     fn set_backend(&self, backend: Backend) {
         if let Ok(mut guard) = self.backend.lock() {
             *guard = Some(backend);
@@ -236,7 +318,7 @@ impl EventListener for EventProxy {
     fn send_event(&self, event: Event) {
         let _ = match event {
             Event::Wakeup => self.proxy.send_event(TerminalEvent::Wakeup),
-            Event::Title(t) => self
+            Event::Title(t) if self.titles => self
                 .proxy
                 .send_event(TerminalEvent::Title(self.panel_id, t)),
             Event::Exit | Event::ChildExit(_) => {
@@ -360,10 +442,12 @@ impl CursorAnimation {
 
 // --- Grid snapshot (for lock-free rendering) ---
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 struct SnapshotCell {
     point: Point,
     c: char,
+    /// Combining marks / ZWJ sequence members attached to this cell.
+    zerowidth: Option<Box<[char]>>,
     fg: Color,
     bg: Color,
     flags: Flags,
@@ -376,6 +460,25 @@ struct CharKey {
     ch: char,
     bold: bool,
     italic: bool,
+    wide: bool,
+}
+
+/// An IME composition in progress, drawn over the cursor cell.
+struct Preedit {
+    text: String,
+    /// Caret byte offset within `text`, if the IME shows one.
+    cursor: Option<usize>,
+    /// Shaped with the panel's font on the next draw.
+    buffer: Option<Buffer>,
+}
+
+/// Cache key for multi-char text: ligature runs and grapheme clusters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RunKey {
+    text: String,
+    bold: bool,
+    italic: bool,
+    wide: bool,
 }
 
 pub struct TerminalPanel {
@@ -383,9 +486,38 @@ pub struct TerminalPanel {
     term: Arc<FairMutex<Term<EventProxy>>>,
     backend: Backend,
     viewport: Option<PanelViewport>,
-    char_buffers: Vec<Buffer>,
-    char_key_map: HashMap<CharKey, usize>,
+    glyph_cache: GlyphCache,
+    /// Shape runs of adjacent same-style cells together so font ligatures
+    /// (`->`, `!=`, `===`) render across cells.
+    ligatures: bool,
+    style: PanelStyle,
+    /// Font size and cell metrics when the style overrides the font,
+    /// measured by the app (it owns the font system).
+    font_metrics: Option<(f32, CellMetrics)>,
     title: String,
+    /// Title set by the user; shown instead of `title` until cleared.
+    custom_title: Option<String>,
+    tab_color: Option<TabColor>,
+    /// How the tab was opened, for duplicating it.
+    source: TabSource,
+    /// Profile the tab was opened from, for re-applying config on reload.
+    profile: Option<String>,
+    /// Tee of the backend's output; inactive unless logging is on.
+    log: SessionLog,
+    /// modifyOtherKeys level the app asked for (not tracked for Windows PTYs).
+    other_keys: OtherKeys,
+    /// Inline images the backend's output placed in the grid.
+    images: Images,
+    /// Progress of files dropped onto an SSH tab, and its shaped label.
+    uploads: Uploads,
+    upload_label: Option<(String, Buffer)>,
+    /// Member of the set of tabs that typed input is broadcast to.
+    broadcast: bool,
+    scrollback: Scrollback,
+    /// Memory ceiling for `Scrollback::Unlimited`, in bytes.
+    scrollback_memory: usize,
+    /// History line limit currently set on the term.
+    history_limit: usize,
     /// Sub-cell pixel offset for smooth trackpad scrolling (physical pixels).
     scroll_pixel_offset: f32,
     /// Raw pixel accumulator not yet committed as whole lines (physical pixels).
@@ -398,6 +530,9 @@ pub struct TerminalPanel {
     cursor_anim: CursorAnimation,
     /// Whether the cursor was visible in the last draw call.
     cursor_visible: bool,
+    /// Screen rect of the cursor cell in the last draw, shown or not.
+    cursor_cell: Option<Rect>,
+    preedit: Option<Preedit>,
     /// Reusable buffer for grid cell snapshots — avoids per-frame Vec allocation.
     snapshot_cells: Vec<SnapshotCell>,
     /// Scrollbar state.
@@ -410,20 +545,21 @@ impl TerminalPanel {
         size: TermSize,
         cell_px: (u16, u16),
         event_proxy: EventProxy,
-        shell: Option<String>,
-        args: Vec<String>,
+        launch: Launch,
     ) -> Result<Self, String> {
-        let config = alacritty_terminal::term::Config::default();
-        let term = Term::new(config, &size, event_proxy.clone());
+        let term = Term::new(term_config(), &size, event_proxy.clone());
         let term = Arc::new(FairMutex::new(term));
 
         let pty_config = tty::Options {
-            shell: shell.map(|program| {
-                let mut shell_args = vec!["--login".to_string()];
-                shell_args.extend(args);
+            shell: launch.shell.map(|program| {
+                let mut shell_args = Vec::new();
+                if launch.login {
+                    shell_args.push("--login".to_string());
+                }
+                shell_args.extend(launch.args);
                 tty::Shell::new(program, shell_args)
             }),
-            working_directory: dirs::home_dir(),
+            working_directory: launch.cwd.or_else(dirs::home_dir),
             drain_on_exit: true,
             env: {
                 let mut env = std::collections::HashMap::new();
@@ -435,6 +571,7 @@ impl TerminalPanel {
                 if std::env::var("LANG").map_or(true, |v| v.is_empty()) {
                     env.insert("LANG".into(), "en_US.UTF-8".into());
                 }
+                env.extend(launch.env);
                 env
             },
             #[cfg(target_os = "windows")]
@@ -448,7 +585,18 @@ impl TerminalPanel {
             cell_height: cell_px.1,
         };
 
-        let pty = match tty::new(&pty_config, window_size, 0) {
+        let log = SessionLog::default();
+        let other_keys = OtherKeys::default();
+        let uploads = Uploads::default();
+        let images = Images::default();
+        let pipeline = OutputPipeline::new(
+            log.clone(),
+            other_keys.scanner(),
+            ImageFilter::new(images.clone(), event_proxy.clone(), true),
+        );
+        let pty = match tty::new(&pty_config, window_size, 0)
+            .and_then(|pty| output::wrap_pty(pty, pipeline))
+        {
             Ok(p) => p,
             Err(e) => return Err(format!("Failed to create PTY: {e}")),
         };
@@ -467,14 +615,31 @@ impl TerminalPanel {
             term,
             backend: Backend::Local(channel),
             viewport: None,
-            char_buffers: Vec::new(),
-            char_key_map: HashMap::new(),
+            glyph_cache: GlyphCache::default(),
+            ligatures: true,
+            style: PanelStyle::default(),
+            font_metrics: None,
             title: String::from("Terminal"),
+            custom_title: None,
+            tab_color: None,
+            source: TabSource::None,
+            profile: None,
+            log,
+            other_keys,
+            images,
+            uploads,
+            upload_label: None,
+            broadcast: false,
+            scrollback: Scrollback::default(),
+            scrollback_memory: 0,
+            history_limit: alacritty_terminal::term::Config::default().scrolling_history,
             scroll_pixel_offset: 0.0,
             scroll_accumulator: 0.0,
             active_selection: None,
             cursor_anim: CursorAnimation::new(),
             cursor_visible: false,
+            cursor_cell: None,
+            preedit: None,
             snapshot_cells: Vec::new(),
             scrollbar: ScrollbarState::default(),
         })
@@ -486,7 +651,23 @@ impl TerminalPanel {
         event_proxy: EventProxy,
         ssh_config: SshConfig,
     ) -> Self {
-        let (term, sender) = crate::ssh::spawn_ssh_thread(ssh_config, size, event_proxy.clone());
+        // Same title the session reports once connected; also names log files.
+        let title = format!("{}@{}", ssh_config.username, ssh_config.host);
+        let log = SessionLog::default();
+        let other_keys = OtherKeys::default();
+        let uploads = Uploads::default();
+        let images = Images::default();
+        let (term, sender) = crate::ssh::spawn_ssh_thread(
+            ssh_config,
+            size,
+            event_proxy.clone(),
+            OutputPipeline::new(
+                log.clone(),
+                other_keys.scanner(),
+                ImageFilter::new(images.clone(), event_proxy.clone(), false),
+            ),
+            uploads.clone(),
+        );
 
         event_proxy.set_backend(Backend::Ssh(sender.clone()));
 
@@ -495,14 +676,82 @@ impl TerminalPanel {
             term,
             backend: Backend::Ssh(sender),
             viewport: None,
-            char_buffers: Vec::new(),
-            char_key_map: HashMap::new(),
-            title: String::from("SSH"),
+            glyph_cache: GlyphCache::default(),
+            ligatures: true,
+            style: PanelStyle::default(),
+            font_metrics: None,
+            title,
+            custom_title: None,
+            tab_color: None,
+            source: TabSource::None,
+            profile: None,
+            log,
+            other_keys,
+            images,
+            uploads,
+            upload_label: None,
+            broadcast: false,
+            scrollback: Scrollback::default(),
+            scrollback_memory: 0,
+            history_limit: alacritty_terminal::term::Config::default().scrolling_history,
+            scroll_pixel_offset: 0.0,
+            scroll_accumulator: 0.0,
+            active_selection: None,
+            cursor_anim: CursorAnimation::new(),
+            cursor_visible: false,
+            cursor_cell: None,
+            preedit: None,
+            snapshot_cells: Vec::new(),
+            scrollbar: ScrollbarState::default(),
+        }
+    }
+
+    /// Create a read-only panel that plays back a recorded session.
+    pub fn new_replay(
+        id: PanelId,
+        size: TermSize,
+        event_proxy: EventProxy,
+        recording: Recording,
+        name: &str,
+    ) -> Self {
+        let term = Term::new(term_config(), &size, event_proxy.without_titles());
+        let term = Arc::new(FairMutex::new(term));
+        let title = recording.title.clone().unwrap_or_else(|| name.to_string());
+        let images = Images::default();
+        // Replies would go nowhere, and recorded file paths mean nothing now.
+        let filter = ImageFilter::new(images.clone(), VoidListener, false);
+        let control = replay::spawn(recording, title.clone(), term.clone(), event_proxy, filter);
+
+        Self {
+            id,
+            term,
+            backend: Backend::Replay(control),
+            viewport: None,
+            glyph_cache: GlyphCache::default(),
+            ligatures: true,
+            style: PanelStyle::default(),
+            font_metrics: None,
+            title,
+            custom_title: None,
+            tab_color: None,
+            source: TabSource::None,
+            profile: None,
+            log: SessionLog::default(),
+            other_keys: OtherKeys::default(),
+            images,
+            uploads: Uploads::default(),
+            upload_label: None,
+            broadcast: false,
+            scrollback: Scrollback::default(),
+            scrollback_memory: 0,
+            history_limit: alacritty_terminal::term::Config::default().scrolling_history,
             scroll_pixel_offset: 0.0,
             scroll_accumulator: 0.0,
             active_selection: None,
             cursor_anim: CursorAnimation::new(),
             cursor_visible: false,
+            cursor_cell: None,
+            preedit: None,
             snapshot_cells: Vec::new(),
             scrollbar: ScrollbarState::default(),
         }
@@ -515,8 +764,7 @@ impl TerminalPanel {
         event_proxy: EventProxy,
         error: &str,
     ) -> Self {
-        let config = alacritty_terminal::term::Config::default();
-        let term = Term::new(config, &size, event_proxy.clone());
+        let term = Term::new(term_config(), &size, event_proxy.clone());
         let term = Arc::new(FairMutex::new(term));
 
         // Write the error message into the terminal buffer so the user can see it
@@ -534,14 +782,31 @@ impl TerminalPanel {
             term,
             backend: Backend::Ssh(mpsc::unbounded_channel().0),
             viewport: None,
-            char_buffers: Vec::new(),
-            char_key_map: HashMap::new(),
+            glyph_cache: GlyphCache::default(),
+            ligatures: true,
+            style: PanelStyle::default(),
+            font_metrics: None,
             title: String::from("Error"),
+            custom_title: None,
+            tab_color: None,
+            source: TabSource::None,
+            profile: None,
+            log: SessionLog::default(),
+            other_keys: OtherKeys::default(),
+            images: Images::default(),
+            uploads: Uploads::default(),
+            upload_label: None,
+            broadcast: false,
+            scrollback: Scrollback::default(),
+            scrollback_memory: 0,
+            history_limit: alacritty_terminal::term::Config::default().scrolling_history,
             scroll_pixel_offset: 0.0,
             scroll_accumulator: 0.0,
             active_selection: None,
             cursor_anim: CursorAnimation::new(),
             cursor_visible: false,
+            cursor_cell: None,
+            preedit: None,
             snapshot_cells: Vec::new(),
             scrollbar: ScrollbarState::default(),
         }
@@ -579,13 +844,159 @@ impl TerminalPanel {
     }
 
     pub fn title(&self) -> &str {
-        &self.title
+        self.custom_title.as_deref().unwrap_or(&self.title)
     }
 
+    /// Title reported by the shell or session.
     pub fn set_title(&mut self, title: String) {
         self.title = title;
     }
 
+    pub fn has_custom_title(&self) -> bool {
+        self.custom_title.is_some()
+    }
+
+    /// Pin a title over the one the shell sets; `None` goes back to it.
+    pub fn set_custom_title(&mut self, title: Option<String>) {
+        self.custom_title = title;
+    }
+
+    pub fn tab_color(&self) -> Option<TabColor> {
+        self.tab_color
+    }
+
+    pub fn set_tab_color(&mut self, color: Option<TabColor>) {
+        self.tab_color = color;
+    }
+
+    pub fn source(&self) -> &TabSource {
+        &self.source
+    }
+
+    pub fn set_source(&mut self, source: TabSource) {
+        self.source = source;
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.profile = profile;
+    }
+
+    /// Set the history size. `memory_mb` bounds `Scrollback::Unlimited`; the
+    /// line limit follows from the current column count.
+    pub fn set_scrollback(&mut self, scrollback: Scrollback, memory_mb: usize) {
+        self.scrollback = scrollback;
+        self.scrollback_memory = memory_mb.saturating_mul(1024 * 1024);
+        self.apply_history_limit();
+    }
+
+    fn apply_history_limit(&mut self) {
+        let mut term = self.term.lock();
+        let limit = match self.scrollback {
+            Scrollback::Lines(lines) => lines,
+            Scrollback::Unlimited => self.scrollback_memory / row_bytes(term.columns()),
+        };
+        if limit != self.history_limit {
+            self.history_limit = limit;
+            term.set_options(term::Config { scrolling_history: limit, ..term_config() });
+        }
+    }
+
+    /// Scrollback lines in use, the line limit, and the memory they take.
+    pub fn scrollback_usage(&self) -> (usize, usize, usize) {
+        let term = self.term.lock();
+        let used = term.grid().history_size();
+        (used, self.history_limit, used * row_bytes(term.columns()))
+    }
+
+    /// Start logging this tab's output to a new file in `dir`.
+    pub fn start_logging(&mut self, dir: &Path, format: LogFormat) -> Result<PathBuf, String> {
+        match self.backend {
+            Backend::Local(_) if !output::LOCAL_PIPELINE => {
+                return Err("Logging local tabs isn't supported on this platform".into());
+            }
+            Backend::Replay(_) => return Err("A replayed recording can't be logged".into()),
+            _ => {}
+        }
+        let size = {
+            let term = self.term.lock();
+            (term.columns(), term.screen_lines())
+        };
+        self.log.start(dir, format, &self.title, size)
+    }
+
+    pub fn stop_logging(&mut self) {
+        self.log.stop();
+    }
+
+    pub fn is_logging(&self) -> bool {
+        self.log.is_active()
+    }
+
+    pub fn broadcast(&self) -> bool {
+        self.broadcast
+    }
+
+    pub fn set_broadcast(&mut self, broadcast: bool) {
+        self.broadcast = broadcast && self.accepts_input();
+    }
+
+    /// False for replayed recordings, whose keys control playback.
+    pub fn accepts_input(&self) -> bool {
+        !matches!(self.backend, Backend::Replay(_))
+    }
+
+    /// The whole grid, history included, in the given format.
+    pub fn export(&self, colors: &ColorScheme, format: ExportFormat) -> String {
+        export::render(self.term.lock().grid(), colors, format)
+    }
+
+    pub fn clear_scrollback(&mut self) {
+        self.term.lock().grid_mut().clear_history();
+        self.scroll_pixel_offset = 0.0;
+        self.scroll_accumulator = 0.0;
+    }
+
+    pub fn set_ligatures(&mut self, enabled: bool) {
+        self.ligatures = enabled;
+    }
+
+    pub fn color_scheme(&self) -> Option<&ColorScheme> {
+        self.style.color_scheme.as_ref()
+    }
+
+    pub fn set_color_scheme(&mut self, scheme: Option<ColorScheme>) {
+        self.style.color_scheme = scheme;
+    }
+
+    pub fn style(&self) -> &PanelStyle {
+        &self.style
+    }
+
+    pub fn set_style(&mut self, style: PanelStyle) {
+        if style.font_family != self.style.font_family {
+            self.glyph_cache.clear();
+            self.glyph_cache.family = style.font_family.clone();
+        }
+        self.style = style;
+    }
+
+    /// Font size and cell metrics for this panel, if its style overrides
+    /// the window's.
+    pub fn font_metrics(&self) -> Option<(f32, CellMetrics)> {
+        self.font_metrics
+    }
+
+    pub fn set_font_metrics(&mut self, metrics: Option<(f32, CellMetrics)>) {
+        if metrics.map(|(size, _)| size) != self.font_metrics.map(|(size, _)| size) {
+            self.glyph_cache.clear();
+        }
+        self.font_metrics = metrics;
+    }
+
     pub fn set_viewport(&mut self, viewport: PanelViewport, cell: &CellMetrics) {
         let dims_changed = self
             .viewport
@@ -598,11 +1009,20 @@ impl TerminalPanel {
             let size = TermSize::new(viewport.cols, viewport.rows);
             self.backend.send_resize(size, cell_w, cell_h);
             self.term.lock().resize(size);
-            self.char_buffers.clear();
-            self.char_key_map.clear();
+            self.glyph_cache.clear();
             self.cursor_anim.snap();
+            if self.scrollback == Scrollback::Unlimited {
+                self.apply_history_limit();
+            }
+            self.log.resize(viewport.cols, viewport.rows);
         }
 
+        // New images are measured in cells as drawn.
+        let cell_px = (
+            viewport.content_rect.width / viewport.cols as f32,
+            viewport.content_rect.height / viewport.rows as f32,
+        );
+        self.images.set_geometry(cell_px, viewport.cols, viewport.rows);
         self.viewport = Some(viewport);
     }
 
@@ -662,11 +1082,7 @@ impl TerminalPanel {
         })
     }
 
-    pub fn handle_key(&mut self, event: &KeyEvent, ctrl: bool, alt: bool, shift: bool) -> bool {
-        if event.state != ElementState::Pressed {
-            return false;
-        }
-
+    pub fn handle_key(&mut self, event: &KeyEvent, mods: kitty_keys::Mods) -> bool {
         // Don't clear selection for modifier-only keys (Cmd, Ctrl, Alt, Shift).
         let is_modifier = matches!(
             event.physical_key,
@@ -681,10 +1097,55 @@ impl TerminalPanel {
                     | KeyCode::ShiftRight
             )
         );
-        if is_modifier {
+        let pressed = event.state == ElementState::Pressed;
+
+        if let Backend::Replay(control) = &self.backend {
+            if !pressed || is_modifier {
+                return false;
+            }
+            return match ReplayControl::for_key(&event.logical_key) {
+                Some(c) => control.send(c).is_ok(),
+                None => false,
+            };
+        }
+
+        // Apps that pushed kitty keyboard flags get keys in that encoding,
+        // including releases when they asked for event types.
+        let kitty_mode = *self.term.lock().mode() & TermMode::KITTY_KEYBOARD_PROTOCOL;
+        if !kitty_mode.is_empty()
+            && let Some(bytes) = kitty_keys::encode(event, mods, kitty_mode)
+        {
+            if pressed && !is_modifier {
+                self.prepare_input();
+            }
+            self.backend.send_input(Cow::Owned(bytes));
+            return true;
+        }
+
+        if !pressed || is_modifier {
             return false;
         }
 
+        let mode = self.prepare_input();
+        let code = match event.physical_key {
+            PhysicalKey::Code(code) => Some(code),
+            PhysicalKey::Unidentified(_) => None,
+        };
+        let text = event.text.as_deref().filter(|t| !t.is_empty());
+        // The legacy encodings have no Super modifier.
+        let mods = kitty_keys::Mods { super_key: false, ..mods };
+        let level = self.other_keys.level();
+        match xterm_keys::encode(event.logical_key.as_ref(), code, text, mods, mode, level) {
+            Some(bytes) => {
+                self.backend.send_input(Cow::Owned(bytes));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Typing snaps back to the live screen and clears the selection.
+    fn prepare_input(&mut self) -> TermMode {
         self.cursor_anim.on_input();
         self.active_selection = None;
         let mode = {
@@ -697,141 +1158,7 @@ impl TerminalPanel {
         };
         self.scroll_pixel_offset = 0.0;
         self.scroll_accumulator = 0.0;
-        let app_cursor = mode.contains(TermMode::APP_CURSOR);
-        // SS3 prefix for application cursor mode, CSI for normal mode
-        let cursor_prefix: &[u8] = if app_cursor { b"\x1bO" } else { b"\x1b[" };
-
-        // Handle Ctrl+key → control characters (0x00–0x1F)
-        // Use physical key for letters to work correctly with non-Latin layouts.
-        if ctrl {
-            let ctrl_byte = match event.physical_key {
-                PhysicalKey::Code(code) => match code {
-                    KeyCode::KeyA => Some(1),  KeyCode::KeyB => Some(2),
-                    KeyCode::KeyC => Some(3),  KeyCode::KeyD => Some(4),
-                    KeyCode::KeyE => Some(5),  KeyCode::KeyF => Some(6),
-                    KeyCode::KeyG => Some(7),  KeyCode::KeyH => Some(8),
-                    KeyCode::KeyI => Some(9),  KeyCode::KeyJ => Some(10),
-                    KeyCode::KeyK => Some(11), KeyCode::KeyL => Some(12),
-                    KeyCode::KeyM => Some(13), KeyCode::KeyN => Some(14),
-                    KeyCode::KeyO => Some(15), KeyCode::KeyP => Some(16),
-                    KeyCode::KeyQ => Some(17), KeyCode::KeyR => Some(18),
-                    KeyCode::KeyS => Some(19), KeyCode::KeyT => Some(20),
-                    KeyCode::KeyU => Some(21), KeyCode::KeyV => Some(22),
-                    KeyCode::KeyW => Some(23), KeyCode::KeyX => Some(24),
-                    KeyCode::KeyY => Some(25), KeyCode::KeyZ => Some(26),
-                    _ => None,
-                },
-                _ => None,
-            }
-            .or_else(|| match event.logical_key.as_ref() {
-                Key::Character(c) => match c.chars().next() {
-                    Some('@' | ' ') => Some(0x00),
-                    Some('[') => Some(0x1B),
-                    Some('\\') => Some(0x1C),
-                    Some(']') => Some(0x1D),
-                    Some('^' | '~') => Some(0x1E),
-                    Some('_' | '/') => Some(0x1F),
-                    _ => None,
-                },
-                _ => None,
-            });
-            if let Some(b) = ctrl_byte {
-                let data = if alt { vec![0x1B, b] } else { vec![b] };
-                self.backend.send_input(Cow::Owned(data));
-                return true;
-            }
-        }
-
-        let bytes = match event.logical_key.as_ref() {
-            Key::Named(NamedKey::Enter) => {
-                // CSI u modifier encoding: 1 + Shift(1) + Alt(2)
-                let modifier = 1 + if shift { 1 } else { 0 } + if alt { 2 } else { 0 };
-                if modifier > 1 {
-                    Some(format!("\x1b[13;{modifier}u").into_bytes())
-                } else {
-                    Some(b"\r".to_vec())
-                }
-            }
-            Key::Named(NamedKey::Backspace) => Some(b"\x7f".to_vec()),
-            Key::Named(NamedKey::Tab) => Some(b"\t".to_vec()),
-            Key::Named(NamedKey::Escape) => Some(b"\x1b".to_vec()),
-            Key::Named(
-                NamedKey::ArrowUp
-                | NamedKey::ArrowDown
-                | NamedKey::ArrowRight
-                | NamedKey::ArrowLeft
-                | NamedKey::Home
-                | NamedKey::End,
-            ) => {
-                let suffix = match event.logical_key.as_ref() {
-                    Key::Named(NamedKey::ArrowUp) => b'A',
-                    Key::Named(NamedKey::ArrowDown) => b'B',
-                    Key::Named(NamedKey::ArrowRight) => b'C',
-                    Key::Named(NamedKey::ArrowLeft) => b'D',
-                    Key::Named(NamedKey::Home) => b'H',
-                    Key::Named(NamedKey::End) => b'F',
-                    _ => unreachable!(),
-                };
-                // Option+Left/Right → ESC b / ESC f (readline word navigation)
-                if alt && !ctrl && (suffix == b'D' || suffix == b'C') {
-                    let ch = if suffix == b'D' { b'b' } else { b'f' };
-                    Some(vec![0x1b, ch])
-                } else {
-                    // xterm modifier encoding: 1 + (Alt=2) + (Ctrl=4)
-                    let modifier = 1 + if alt { 2 } else { 0 } + if ctrl { 4 } else { 0 };
-                    if modifier > 1 {
-                        // Modified keys always use CSI format, even in app cursor mode
-                        Some(format!("\x1b[1;{modifier}{}", suffix as char).into_bytes())
-                    } else {
-                        Some([cursor_prefix, &[suffix]].concat())
-                    }
-                }
-            }
-            Key::Named(NamedKey::PageUp) => Some(b"\x1b[5~".to_vec()),
-            Key::Named(NamedKey::PageDown) => Some(b"\x1b[6~".to_vec()),
-            Key::Named(NamedKey::Delete) => Some(b"\x1b[3~".to_vec()),
-            Key::Named(NamedKey::Insert) => Some(b"\x1b[2~".to_vec()),
-            Key::Named(NamedKey::F1) => Some(b"\x1bOP".to_vec()),
-            Key::Named(NamedKey::F2) => Some(b"\x1bOQ".to_vec()),
-            Key::Named(NamedKey::F3) => Some(b"\x1bOR".to_vec()),
-            Key::Named(NamedKey::F4) => Some(b"\x1bOS".to_vec()),
-            Key::Named(NamedKey::F5) => Some(b"\x1b[15~".to_vec()),
-            Key::Named(NamedKey::F6) => Some(b"\x1b[17~".to_vec()),
-            Key::Named(NamedKey::F7) => Some(b"\x1b[18~".to_vec()),
-            Key::Named(NamedKey::F8) => Some(b"\x1b[19~".to_vec()),
-            Key::Named(NamedKey::F9) => Some(b"\x1b[20~".to_vec()),
-            Key::Named(NamedKey::F10) => Some(b"\x1b[21~".to_vec()),
-            Key::Named(NamedKey::F11) => Some(b"\x1b[23~".to_vec()),
-            Key::Named(NamedKey::F12) => Some(b"\x1b[24~".to_vec()),
-            _ => None,
-        };
-
-        if let Some(b) = bytes {
-            self.backend.send_input(Cow::Owned(b));
-            return true;
-        }
-
-        // Handle Alt+key → ESC prefix + key (after named keys to avoid intercepting Alt+Arrows)
-        if alt {
-            if let Some(t) = &event.text
-                && !t.is_empty()
-            {
-                let mut data = vec![0x1B];
-                data.extend_from_slice(t.as_bytes());
-                self.backend.send_input(Cow::Owned(data));
-                return true;
-            }
-        }
-
-        if let Some(t) = &event.text
-            && !t.is_empty()
-        {
-            self.backend
-                .send_input(Cow::Owned(t.to_string().into_bytes()));
-            true
-        } else {
-            false
-        }
+        mode
     }
 
     pub fn handle_scroll(&mut self, delta: MouseScrollDelta, cell_height: f64) -> bool {
@@ -1146,13 +1473,17 @@ impl TerminalPanel {
         let island_rect = vp.rect;
         let pixel_offset = self.scroll_pixel_offset;
 
-        // Island background (stroke + fill)
+        // Island background (stroke + fill); a labelled tab's color frames it
         let island_radius = panel_theme.island_radius * scale;
-        let island_stroke = panel_theme.island_stroke_width * scale;
+        let label = self.tab_color.map(|c| c.resolve(colors));
+        let island_stroke = match label {
+            Some(_) => panel_theme.label_stroke_width,
+            None => panel_theme.island_stroke_width,
+        } * scale;
         if island_stroke > 0.0 {
             ctx.stroked_rect(
                 &island_rect,
-                colors.panel_stroke.to_linear_f32(),
+                label.unwrap_or(colors.panel_stroke).to_linear_f32(),
                 colors.background.to_linear_f32(),
                 island_radius,
                 island_stroke,
@@ -1162,6 +1493,10 @@ impl TerminalPanel {
         }
 
         let metrics = font::metrics_for_size(font_size);
+        let has_images = !self.images.is_empty();
+        // (placement id, placement row, viewport line, column) of visible anchors
+        let mut anchors = Vec::new();
+        let mut placeholders = Vec::new();
 
         // --- Snapshot grid data under the lock, then release it ---
         let (cursor_point, cursor_shape, selection_range, display_offset, extra_row_cells) = {
@@ -1194,12 +1529,28 @@ impl TerminalPanel {
                     continue;
                 }
                 let cell = &*indexed;
+                let (mut bg, mut flags) = (cell.bg, cell.flags);
+                if has_images && let Some((id, row)) = images::anchor(cell) {
+                    anchors.push((id, row, viewport_line, col));
+                    // The anchor's space took on whatever SGR was current.
+                    (bg, flags) = (Color::Named(NamedColor::Background), Flags::empty());
+                }
+                if has_images && cell.c == kitty_graphics::PLACEHOLDER {
+                    placeholders.push(PlaceholderCell {
+                        line: viewport_line,
+                        col,
+                        fg: cell.fg,
+                        underline: cell.underline_color(),
+                        marks: cell.zerowidth().map(<[char]>::to_vec).unwrap_or_default(),
+                    });
+                }
                 self.snapshot_cells.push(SnapshotCell {
                     point: indexed.point,
                     c: cell.c,
+                    zerowidth: cell.zerowidth().map(Box::from),
                     fg: cell.fg,
-                    bg: cell.bg,
-                    flags: cell.flags,
+                    bg,
+                    flags,
                 });
             }
 
@@ -1211,12 +1562,14 @@ impl TerminalPanel {
                 let row_data = &grid[Line(grid_line)];
                 Some((0..cols).map(|col_idx| {
                     let cell = &row_data[Column(col_idx)];
+                    let anchored = has_images && images::anchor(cell).is_some();
                     SnapshotCell {
                         point: Point::new(Line(grid_line), Column(col_idx)),
                         c: cell.c,
+                        zerowidth: cell.zerowidth().map(Box::from),
                         fg: cell.fg,
-                        bg: cell.bg,
-                        flags: cell.flags,
+                        bg: if anchored { Color::Named(NamedColor::Background) } else { cell.bg },
+                        flags: if anchored { Flags::empty() } else { cell.flags },
                     }
                 }).collect::<Vec<_>>())
             } else {
@@ -1228,11 +1581,82 @@ impl TerminalPanel {
             self.scrollbar.screen_lines = term.grid().screen_lines();
             self.scrollbar.display_offset = display_offset;
 
+            self.images.sweep(term.grid(), term.mode().contains(TermMode::ALT_SCREEN));
+            if self.images.has_screen_deletes() {
+                self.images.apply_screen_deletes(term.grid());
+            }
+
             (cursor_point, cursor_shape, selection_range, display_offset, extra_row_cells)
         }; // lock released here
 
+        // Each placement hangs from the topmost of its anchors on screen
+        let mut placed = Vec::new();
+        for (id, row, line, col) in anchors {
+            if placed.contains(&id) {
+                continue;
+            }
+            placed.push(id);
+            let Some(placement) = self.images.get(id) else { continue };
+            let top = (line - row as i32) as f32 + placement.offset.1;
+            let rect = Rect {
+                x: (content_x + (col as f32 + placement.offset.0) * pcw).round(),
+                y: (content_y + top * pch + pixel_offset).round(),
+                width: (placement.cells.0 * pcw).round(),
+                height: (placement.cells.1 * pch).round(),
+            };
+            ctx.image(ImageQuad {
+                image: placement.image.clone(),
+                rect,
+                clip: content_clip,
+                uv: placement.uv,
+                z: placement.z,
+            });
+        }
+
+        // Placeholder cells each show their piece of a virtual placement,
+        // which fits the image centered into its cells.
+        let mut virtuals = HashMap::new();
+        for piece in kitty_graphics::image_pieces(&placeholders) {
+            let Some((image, virt)) = virtuals
+                .entry((piece.image, piece.placement))
+                .or_insert_with(|| self.images.virtual_placement(piece.image, piece.placement))
+                .clone()
+            else {
+                continue;
+            };
+            let (area_w, area_h) = (virt.cols as f32 * pcw, virt.rows as f32 * pch);
+            let fit = (area_w / image.width as f32).min(area_h / image.height as f32);
+            let (width, height) = (image.width as f32 * fit, image.height as f32 * fit);
+            let cell = Rect {
+                x: content_x + piece.col as f32 * pcw,
+                y: content_y + piece.line as f32 * pch + pixel_offset,
+                width: pcw,
+                height: pch,
+            };
+            let Some(clip) = cell.clip_y(content_y, content_bottom) else { continue };
+            let rect = Rect {
+                x: cell.x - piece.column as f32 * pcw + (area_w - width) / 2.0,
+                y: cell.y - piece.row as f32 * pch + (area_h - height) / 2.0,
+                width,
+                height,
+            };
+            ctx.image(ImageQuad { image, rect, clip, uv: [0.0, 0.0, 1.0, 1.0], z: virt.z });
+        }
+
         // --- Build quads and text specs without holding the lock ---
         let selection_color = colors.selection.to_linear_f32();
+        self.glyph_cache.trim();
+        let mut shaper = RunShaper {
+            cache: &mut self.glyph_cache,
+            text_specs,
+            font_system,
+            metrics,
+            cell_metrics,
+            bounds: content_clip,
+            scale,
+            ligatures: self.ligatures,
+            pending: None,
+        };
 
         for snap in &self.snapshot_cells {
             let viewport_line = snap.point.line.0 + display_offset as i32;
@@ -1274,7 +1698,8 @@ impl TerminalPanel {
                 Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER | Flags::HIDDEN,
             );
             let c = if is_invisible { ' ' } else { snap.c };
-            if c == ' ' || c == '\0' {
+            // Placeholder cells show their piece of an image instead.
+            if c == ' ' || c == '\0' || c == kitty_graphics::PLACEHOLDER {
                 continue;
             }
 
@@ -1288,36 +1713,26 @@ impl TerminalPanel {
 
             let cx = content_x + col as f32 * pcw;
 
-            if let Some(rects) = block_char_rects(c, cx, cy, pcw, pch) {
-                let color = glyphon_to_linear(fg);
-                for r in rects {
-                    if let Some(clipped) = r.clip_y(content_y, content_bottom) {
-                        ctx.flat_quad(clipped, color);
-                    }
-                }
+            let cell_rect = Rect { x: cx, y: cy, width: pcw, height: pch };
+            let color = glyphon_to_linear(fg);
+            if builtin_glyphs::draw(ctx, c, cell_rect, color, content_y, content_bottom) {
                 continue;
             }
 
-            let bold = flags.contains(Flags::BOLD);
-            let italic = flags.contains(Flags::ITALIC);
-            let key = CharKey { ch: c, bold, italic };
-            let buf_idx = get_or_create_buffer(
-                &mut self.char_buffers,
-                &mut self.char_key_map,
-                key,
-                font_system,
-                metrics,
-                cell_metrics,
-            );
-
-            text_specs.push(TextSpec {
+            shaper.push(CellText {
+                line: viewport_line,
+                col,
                 left: cx,
                 top: cy,
-                color: fg,
-                buffer_index: buf_idx,
-                bounds: content_clip,
+                c,
+                zerowidth: if is_invisible { None } else { snap.zerowidth.as_deref() },
+                fg,
+                bold: flags.contains(Flags::BOLD),
+                italic: flags.contains(Flags::ITALIC),
+                wide: flags.contains(Flags::WIDE_CHAR),
             });
         }
+        shaper.flush();
 
         // Animated cursor (hidden when TUI apps send ESC[?25l)
         if cursor_shape != CursorShape::Hidden {
@@ -1437,38 +1852,205 @@ impl TerminalPanel {
 
                 let cx = content_x + col_idx as f32 * pcw;
 
-                if let Some(rects) = block_char_rects(c, cx, y_base, pcw, pch) {
-                    let color = glyphon_to_linear(fg);
-                    for r in rects {
-                        if let Some(clipped) = r.clip_y(content_y, content_bottom) {
-                            ctx.flat_quad(clipped, color);
-                        }
-                    }
+                let cell_rect = Rect { x: cx, y: y_base, width: pcw, height: pch };
+                let color = glyphon_to_linear(fg);
+                if builtin_glyphs::draw(ctx, c, cell_rect, color, content_y, content_bottom) {
                     continue;
                 }
 
-                let bold = flags.contains(Flags::BOLD);
-                let italic = flags.contains(Flags::ITALIC);
-                let key = CharKey { ch: c, bold, italic };
-                let buf_idx = get_or_create_buffer(
-                    &mut self.char_buffers,
-                    &mut self.char_key_map,
-                    key,
+                shaper.push(CellText {
+                    line: rows as i32,
+                    col: col_idx,
+                    left: cx,
+                    top: y_base,
+                    c,
+                    zerowidth: if is_invisible { None } else { snap.zerowidth.as_deref() },
+                    fg,
+                    bold: flags.contains(Flags::BOLD),
+                    italic: flags.contains(Flags::ITALIC),
+                    wide: flags.contains(Flags::WIDE_CHAR),
+                });
+            }
+            shaper.flush();
+        }
+
+        // Cell the cursor sits in, where IME compositions are shown
+        let cur_line = cursor_point.line.0 + display_offset as i32;
+        let cur_col = cursor_point.column.0;
+        self.cursor_cell = (cur_line >= 0 && (cur_line as usize) < rows && cur_col < cols)
+            .then_some(Rect {
+                x: content_x + cur_col as f32 * pcw,
+                y: content_y + cur_line as f32 * pch + pixel_offset,
+                width: pcw,
+                height: pch,
+            });
+        match self.uploads.status() {
+            Some(status) if self.upload_label.as_ref().is_none_or(|(l, _)| *l != status.label) => {
+                let mut buffer = Buffer::new(font_system, metrics);
+                font::set_buffer_text(
+                    &mut buffer,
                     font_system,
+                    &status.label,
                     metrics,
-                    cell_metrics,
+                    font::default_attrs(),
+                    f32::MAX,
                 );
+                self.upload_label = Some((status.label, buffer));
+            }
+            Some(_) => {}
+            None => self.upload_label = None,
+        }
+        if let Some(preedit) = &mut self.preedit {
+            let attrs = cell_attrs(self.glyph_cache.family.as_deref(), false, false);
+            let cells = preedit.text.chars().count() * 2 + 1;
+            preedit.buffer =
+                Some(shape_text(&preedit.text, attrs, cells, font_system, metrics, cell_metrics));
+        }
+    }
 
-                text_specs.push(TextSpec {
-                    left: cx,
-                    top: y_base,
-                    color: fg,
-                    buffer_index: buf_idx,
-                    bounds: content_clip,
-                });
+    /// Draw the IME composition over the cursor cell, underlined, into the
+    /// overlay so it covers the cell's own text.
+    pub fn draw_preedit<'a>(
+        &'a self,
+        ctx: &mut DrawContext,
+        text_areas: &mut Vec<TextArea<'a>>,
+        colors: &ColorScheme,
+    ) {
+        let (Some(preedit), Some(cell), Some(vp)) =
+            (&self.preedit, self.cursor_cell, &self.viewport)
+        else {
+            return;
+        };
+        let Some(buffer) = &preedit.buffer else { return };
+        let scale = vp.scale_factor;
+        let content = vp.content_rect;
+        let text_w = buffer.layout_runs().map(|run| run.line_w).fold(0.0, f32::max) * scale;
+        // Keep the composition on screen when the cursor is near the right edge.
+        let x = cell.x.min(content.x + content.width - text_w).max(content.x);
+        let rect = Rect { x, y: cell.y, width: text_w.max(cell.width), height: cell.height };
+        let Some(clipped) = rect.clip_y(content.y, content.y + content.height) else { return };
+        ctx.flat_quad(clipped, colors.background.to_linear_f32());
+        text_areas.push(TextArea {
+            buffer,
+            left: x,
+            top: cell.y,
+            scale,
+            bounds: clipped.to_text_bounds(),
+            default_color: colors.foreground.to_glyphon(),
+            custom_glyphs: &[],
+        });
+        let thickness = scale.max(1.0);
+        let underline = Rect { y: rect.y + rect.height - thickness, height: thickness, ..rect };
+        if let Some(underline) = underline.clip_y(content.y, content.y + content.height) {
+            ctx.flat_quad(underline, colors.foreground.to_linear_f32());
+        }
+        if let Some(byte) = preedit.cursor {
+            let caret_x = buffer
+                .layout_runs()
+                .flat_map(|run| run.glyphs.iter())
+                .find(|glyph| glyph.start >= byte)
+                .map_or(text_w / scale, |glyph| glyph.x);
+            let caret = Rect { x: x + caret_x * scale, width: thickness, ..rect };
+            if let Some(caret) = caret.clip_y(content.y, content.y + content.height) {
+                ctx.flat_quad(caret, colors.cursor.to_linear_f32());
             }
         }
+    }
+
+    /// Draw upload progress along the bottom of the panel: a label over a
+    /// bar that fills as bytes go out.
+    pub fn draw_upload<'a>(
+        &'a self,
+        ctx: &mut DrawContext,
+        text_areas: &mut Vec<TextArea<'a>>,
+        colors: &ColorScheme,
+    ) {
+        let (Some(status), Some((_, buffer)), Some(vp)) =
+            (self.uploads.status(), &self.upload_label, &self.viewport)
+        else {
+            return;
+        };
+        let scale = vp.scale_factor;
+        let content = vp.content_rect;
+        let line_h = buffer.metrics().line_height * scale;
+        let pad = 6.0 * scale;
+        let bar_h = 3.0 * scale;
+        let height = line_h + 2.0 * pad + bar_h;
+        let rect = Rect {
+            x: content.x,
+            y: content.y + content.height - height,
+            width: content.width,
+            height,
+        };
+        ctx.flat_quad(rect, colors.dropdown_bg.to_linear_f32());
+        text_areas.push(TextArea {
+            buffer,
+            left: rect.x + pad,
+            top: rect.y + pad,
+            scale,
+            bounds: rect.to_text_bounds(),
+            default_color: colors.dropdown_text.to_glyphon(),
+            custom_glyphs: &[],
+        });
+        let fraction = match status.total {
+            0 => 1.0,
+            total => (status.sent as f64 / total as f64).min(1.0) as f32,
+        };
+        let bar_color = if status.failed { colors.red } else { colors.field_focused };
+        let bar = Rect {
+            y: rect.y + rect.height - bar_h,
+            height: bar_h,
+            width: rect.width * fraction,
+            ..rect
+        };
+        ctx.flat_quad(bar, bar_color.to_linear_f32());
+    }
+
+    /// Whether upload progress is showing (it animates, so the app redraws fully).
+    pub fn has_upload_status(&self) -> bool {
+        self.uploads.status().is_some()
+    }
+
+    /// True for tabs connected over SSH, which can take file uploads.
+    pub fn is_ssh(&self) -> bool {
+        matches!(self.source, TabSource::Ssh(_)) && matches!(self.backend, Backend::Ssh(_))
+    }
+
+    /// Upload local files to the remote working directory over this tab's
+    /// session.
+    pub fn upload(&self, files: Vec<PathBuf>) {
+        if let Backend::Ssh(tx) = &self.backend {
+            let _ = tx.send(SshMsg::Upload(files));
+        }
+    }
+
+    /// Show an IME composition at the cursor; `cursor` is a byte offset into
+    /// `text`. An empty string ends the composition.
+    pub fn set_preedit(&mut self, text: &str, cursor: Option<usize>) {
+        self.preedit = (!text.is_empty()).then(|| Preedit {
+            text: text.to_string(),
+            cursor,
+            buffer: None,
+        });
+    }
+
+    pub fn has_preedit(&self) -> bool {
+        self.preedit.is_some()
+    }
+
+    /// The cursor cell from the last draw, for placing the IME candidate window.
+    pub fn ime_area(&self) -> Option<Rect> {
+        self.cursor_cell
+    }
 
+    /// Send text committed by the IME to the backend, as typing would.
+    pub fn commit_text(&mut self, text: &str) {
+        self.preedit = None;
+        if text.is_empty() || matches!(self.backend, Backend::Replay(_)) {
+            return;
+        }
+        self.prepare_input();
+        self.backend.send_input(Cow::Owned(text.as_bytes().to_vec()));
     }
 
     /// Draw the scrollbar thumb into the given context (should be the overlay layer
@@ -1493,7 +2075,7 @@ impl TerminalPanel {
     }
 
     pub fn buffers(&self) -> &[Buffer] {
-        &self.char_buffers
+        &self.glyph_cache.buffers
     }
 
     pub fn write_to_pty(&self, data: Vec<u8>) {
@@ -1501,36 +2083,264 @@ impl TerminalPanel {
     }
 }
 
-fn get_or_create_buffer(
-    char_buffers: &mut Vec<Buffer>,
-    char_key_map: &mut HashMap<CharKey, usize>,
-    key: CharKey,
+// --- Glyph shaping ---
+
+/// Shaped buffers kept before the glyph cache is flushed; several screens'
+/// worth of distinct runs.
+const MAX_GLYPH_BUFFERS: usize = 8192;
+
+/// Shaped text buffers shared by every frame. Single chars are keyed by
+/// `CharKey`; ligature runs and grapheme clusters by `RunKey`. Both index
+/// into `buffers`, which is what `TextSpec::buffer_index` refers to.
+#[derive(Default)]
+struct GlyphCache {
+    /// Font family override; `None` uses the default monospace font.
+    family: Option<String>,
+    buffers: Vec<Buffer>,
+    /// Horizontal offset (logical px) that centers each buffer in its cells.
+    offsets: Vec<f32>,
+    chars: HashMap<CharKey, usize>,
+    runs: HashMap<RunKey, usize>,
+}
+
+impl GlyphCache {
+    fn clear(&mut self) {
+        self.buffers.clear();
+        self.offsets.clear();
+        self.chars.clear();
+        self.runs.clear();
+    }
+
+    /// Start over once the cache outgrows `MAX_GLYPH_BUFFERS` (a long session
+    /// of changing text keeps adding runs). Only safe before a frame's text
+    /// specs are built, since clearing invalidates their indices.
+    fn trim(&mut self) {
+        if self.buffers.len() > MAX_GLYPH_BUFFERS {
+            self.clear();
+        }
+    }
+
+    fn char_buffer(
+        &mut self,
+        key: CharKey,
+        font_system: &mut FontSystem,
+        metrics: glyphon::Metrics,
+        cell_metrics: &CellMetrics,
+    ) -> usize {
+        if let Some(&idx) = self.chars.get(&key) {
+            return idx;
+        }
+        let mut tmp = [0u8; 4];
+        let text = key.ch.encode_utf8(&mut tmp);
+        let attrs = cell_attrs(self.family.as_deref(), key.bold, key.italic);
+        let mut buf = shape_text(text, attrs, 2, font_system, metrics, cell_metrics);
+        let dx = if key.wide { fit_wide(&mut buf, font_system, metrics, cell_metrics) } else { 0.0 };
+        let idx = self.insert(buf, dx);
+        self.chars.insert(key, idx);
+        idx
+    }
+
+    fn run_buffer(
+        &mut self,
+        key: RunKey,
+        cells: usize,
+        font_system: &mut FontSystem,
+        metrics: glyphon::Metrics,
+        cell_metrics: &CellMetrics,
+    ) -> usize {
+        if let Some(&idx) = self.runs.get(&key) {
+            return idx;
+        }
+        let attrs = cell_attrs(self.family.as_deref(), key.bold, key.italic);
+        let mut buf = shape_text(&key.text, attrs, cells + 1, font_system, metrics, cell_metrics);
+        let dx = if key.wide { fit_wide(&mut buf, font_system, metrics, cell_metrics) } else { 0.0 };
+        let idx = self.insert(buf, dx);
+        self.runs.insert(key, idx);
+        idx
+    }
+
+    fn insert(&mut self, buf: Buffer, dx: f32) -> usize {
+        self.buffers.push(buf);
+        self.offsets.push(dx);
+        self.buffers.len() - 1
+    }
+}
+
+/// One visible cell's text, as fed to `RunShaper::push`.
+struct CellText<'a> {
+    line: i32,
+    col: usize,
+    left: f32,
+    top: f32,
+    c: char,
+    zerowidth: Option<&'a [char]>,
+    fg: GlyphonColor,
+    bold: bool,
+    italic: bool,
+    /// Double-width cell (`Flags::WIDE_CHAR`): CJK, emoji.
+    wide: bool,
+}
+
+/// Text accumulated for adjacent cells that will be shaped as one buffer.
+struct PendingRun {
+    text: String,
+    cells: usize,
+    line: i32,
+    end_col: usize,
+    left: f32,
+    top: f32,
+    fg: GlyphonColor,
+    bold: bool,
+    italic: bool,
+    wide: bool,
+    /// False once the run holds a grapheme cluster — it can't be extended.
+    joinable: bool,
+}
+
+/// Groups cells into runs and emits one `TextSpec` per run.
+///
+/// Only ASCII punctuation is joined: that is where programming ligatures
+/// live, and it keeps letters on the per-char cache so shaping cost stays
+/// flat. Within a run glyphs advance by the font's own cell width, which
+/// matches the grid for the monospace primary font.
+struct RunShaper<'a> {
+    cache: &'a mut GlyphCache,
+    text_specs: &'a mut Vec<TextSpec>,
+    font_system: &'a mut FontSystem,
+    metrics: glyphon::Metrics,
+    cell_metrics: &'a CellMetrics,
+    bounds: Rect,
+    scale: f32,
+    ligatures: bool,
+    pending: Option<PendingRun>,
+}
+
+impl RunShaper<'_> {
+    fn push(&mut self, cell: CellText) {
+        let joinable = self.ligatures
+            && !cell.wide
+            && cell.zerowidth.is_none()
+            && cell.c.is_ascii_punctuation();
+        if let Some(run) = &mut self.pending
+            && joinable
+            && run.joinable
+            && run.line == cell.line
+            && run.end_col == cell.col
+            && run.fg == cell.fg
+            && run.bold == cell.bold
+            && run.italic == cell.italic
+        {
+            run.text.push(cell.c);
+            run.cells += 1;
+            run.end_col += 1;
+            return;
+        }
+
+        self.flush();
+        let mut text = String::new();
+        text.push(cell.c);
+        if let Some(zw) = cell.zerowidth {
+            text.extend(zw.iter());
+        }
+        self.pending = Some(PendingRun {
+            text,
+            cells: 1,
+            line: cell.line,
+            end_col: cell.col + 1,
+            left: cell.left,
+            top: cell.top,
+            fg: cell.fg,
+            bold: cell.bold,
+            italic: cell.italic,
+            wide: cell.wide,
+            joinable: cell.zerowidth.is_none() && !cell.wide,
+        });
+    }
+
+    fn flush(&mut self) {
+        let Some(run) = self.pending.take() else {
+            return;
+        };
+        let mut chars = run.text.chars();
+        let buffer_index = match (chars.next(), chars.next()) {
+            (Some(ch), None) => self.cache.char_buffer(
+                CharKey { ch, bold: run.bold, italic: run.italic, wide: run.wide },
+                self.font_system,
+                self.metrics,
+                self.cell_metrics,
+            ),
+            _ => {
+                let key = RunKey {
+                    text: run.text,
+                    bold: run.bold,
+                    italic: run.italic,
+                    wide: run.wide,
+                };
+                self.cache
+                    .run_buffer(key, run.cells, self.font_system, self.metrics, self.cell_metrics)
+            }
+        };
+        self.text_specs.push(TextSpec {
+            left: run.left + self.cache.offsets[buffer_index] * self.scale,
+            top: run.top,
+            color: run.fg,
+            buffer_index,
+            bounds: self.bounds,
+        });
+    }
+}
+
+/// Shape `text` into a buffer `cells` cells wide (extra room avoids wrapping
+/// glyphs that overhang their cell).
+fn shape_text(
+    text: &str,
+    attrs: glyphon::Attrs,
+    cells: usize,
     font_system: &mut FontSystem,
     metrics: glyphon::Metrics,
     cell_metrics: &CellMetrics,
-) -> usize {
-    if let Some(&idx) = char_key_map.get(&key) {
-        return idx;
-    }
+) -> Buffer {
     let mut buf = Buffer::new(font_system, metrics);
     buf.set_size(
         font_system,
-        Some(cell_metrics.width * 2.0),
+        Some(cell_metrics.width * cells as f32),
         Some(cell_metrics.height),
     );
-    let mut attrs = font::default_attrs();
-    if key.bold {
+    buf.set_text(font_system, text, attrs, Shaping::Advanced);
+    buf.shape_until_scroll(font_system, false);
+    buf
+}
+
+fn cell_attrs(family: Option<&str>, bold: bool, italic: bool) -> glyphon::Attrs<'_> {
+    let mut attrs = font::family_attrs(family);
+    if bold {
         attrs = attrs.weight(glyphon::Weight::BOLD);
     }
-    if key.italic {
+    if italic {
         attrs = attrs.style(glyphon::Style::Italic);
     }
-    buf.set_text(font_system, &key.ch.to_string(), attrs, Shaping::Advanced);
-    buf.shape_until_scroll(font_system, false);
-    let idx = char_buffers.len();
-    char_buffers.push(buf);
-    char_key_map.insert(key, idx);
-    idx
+    attrs
+}
+
+/// Fit a double-width glyph into exactly two cells: shrink it if the
+/// fallback font (typically a color emoji face) advances wider than that,
+/// then return the x offset that centers it.
+fn fit_wide(
+    buf: &mut Buffer,
+    font_system: &mut FontSystem,
+    metrics: glyphon::Metrics,
+    cell_metrics: &CellMetrics,
+) -> f32 {
+    let target = cell_metrics.width * 2.0;
+    let line_w = |buf: &Buffer| buf.layout_runs().next().map_or(0.0, |run| run.line_w);
+    let mut w = line_w(buf);
+    if w > target {
+        let font_size = metrics.font_size * target / w;
+        buf.set_metrics(font_system, glyphon::Metrics::new(font_size, metrics.line_height));
+        buf.shape_until_scroll(font_system, false);
+        w = line_w(buf);
+    }
+    ((target - w) / 2.0).max(0.0)
 }
 
 /// Convert a GlyphonColor (sRGB u8) to linear f32 RGBA for GPU quads.
@@ -1538,40 +2348,13 @@ fn glyphon_to_linear(c: GlyphonColor) -> [f32; 4] {
     crate::colors::rgba_u8_to_linear(c.r(), c.g(), c.b(), c.a())
 }
 
-/// Return sub-rectangles for block-drawing characters (U+2580-U+259F).
-fn block_char_rects(c: char, cx: f32, cy: f32, cw: f32, ch: f32) -> Option<Vec<Rect>> {
-    let u = c as u32;
-    let hw = cw / 2.0;
-    let hh = ch / 2.0;
-    let r = |x, y, w, h| Rect { x, y, width: w, height: h };
-
-    match u {
-        // Lower N/8 blocks: U+2581 (1/8) .. U+2588 (full)
-        0x2581..=0x2588 => {
-            let n = (u - 0x2580) as f32 / 8.0;
-            Some(vec![r(cx, cy + ch * (1.0 - n), cw, ch * n)])
-        }
-        // Left N/8 blocks: U+2589 (7/8) .. U+258F (1/8)
-        0x2589..=0x258F => {
-            let n = (0x2590 - u) as f32 / 8.0;
-            Some(vec![r(cx, cy, cw * n, ch)])
-        }
-        // Special halves and eighths
-        0x2580 => Some(vec![r(cx, cy, cw, hh)]),             // upper half
-        0x2590 => Some(vec![r(cx + hw, cy, hw, ch)]),         // right half
-        0x2594 => Some(vec![r(cx, cy, cw, ch / 8.0)]),        // upper 1/8
-        0x2595 => Some(vec![r(cx + cw * 7.0 / 8.0, cy, cw / 8.0, ch)]), // right 1/8
-        // Quadrant characters: U+2596-U+259F — bitmask: TL=1, TR=2, BL=4, BR=8
-        0x2596..=0x259F => {
-            const QUAD_BITS: [u8; 10] = [4, 8, 1, 13, 9, 7, 11, 2, 6, 14];
-            let bits = QUAD_BITS[(u - 0x2596) as usize];
-            let mut rects = Vec::with_capacity(4);
-            if bits & 1 != 0 { rects.push(r(cx, cy, hw, hh)); }
-            if bits & 2 != 0 { rects.push(r(cx + hw, cy, hw, hh)); }
-            if bits & 4 != 0 { rects.push(r(cx, cy + hh, hw, hh)); }
-            if bits & 8 != 0 { rects.push(r(cx + hw, cy + hh, hw, hh)); }
-            Some(rects)
-        }
-        _ => None,
-    }
+/// Terminal options shared by every panel. The kitty keyboard protocol is on
+/// so apps can push their own key reporting flags.
+pub fn term_config() -> term::Config {
+    term::Config { kitty_keyboard: true, ..Default::default() }
+}
+
+/// Approximate memory held by one history row of `columns` cells.
+fn row_bytes(columns: usize) -> usize {
+    columns.max(1) * std::mem::size_of::<alacritty_terminal::term::cell::Cell>()
 }
@@ -1,4 +1,7 @@
+use serde::{Deserialize, Serialize};
+
 use crate::colors::ColorScheme;
+use crate::config::LayoutConfig;
 
 #[derive(Clone)]
 pub struct Theme {
@@ -7,20 +10,36 @@ pub struct Theme {
     pub dropdown: DropdownTheme,
     pub dialog: DialogTheme,
     pub panel: PanelTheme,
+    pub toast: ToastTheme,
+    pub palette: PaletteTheme,
     pub general: GeneralTheme,
 }
 
 impl Theme {
     pub fn new() -> Self {
         Self {
-            colors: ColorScheme::load(),
+            colors: ColorScheme::default(),
             tab_bar: TabBarTheme::default(),
             dropdown: DropdownTheme::default(),
             dialog: DialogTheme::default(),
             panel: PanelTheme::default(),
+            toast: ToastTheme::default(),
+            palette: PaletteTheme::default(),
             general: GeneralTheme::default(),
         }
     }
+
+    /// Apply the config file's layout overrides; omitted sections revert to
+    /// the built-in defaults.
+    pub fn apply_layout(&mut self, layout: &LayoutConfig) {
+        self.general = layout.general.clone().unwrap_or_default();
+        self.tab_bar = layout.tab_bar.clone().unwrap_or_default();
+        self.dropdown = layout.dropdown.clone().unwrap_or_default();
+        self.dialog = layout.dialog.clone().unwrap_or_default();
+        self.panel = layout.panel.clone().unwrap_or_default();
+        self.toast = layout.toast.clone().unwrap_or_default();
+        self.palette = layout.palette.clone().unwrap_or_default();
+    }
 }
 
 impl Default for Theme {
@@ -29,7 +48,8 @@ impl Default for Theme {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct GeneralTheme {
     pub panel_area_padding: f32,
 }
@@ -42,7 +62,8 @@ impl Default for GeneralTheme {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct TabBarTheme {
     pub height: f32,
     pub tab_padding_h: f32,
@@ -58,6 +79,11 @@ pub struct TabBarTheme {
     pub plus_icon_size: f32,
     pub plus_radius: f32,
     pub font_size: f32,
+    /// Thickness of the underline marking a tab that receives broadcast input.
+    pub indicator_height: f32,
+    /// Tabs shrink (ellipsizing their titles) down to this width before the
+    /// bar starts scrolling.
+    pub tab_min_width: f32,
 }
 
 impl Default for TabBarTheme {
@@ -77,11 +103,14 @@ impl Default for TabBarTheme {
             plus_icon_size: 12.0,
             plus_radius: 5.0,
             font_size: 11.0,
+            indicator_height: 2.0,
+            tab_min_width: 90.0,
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct DropdownTheme {
     pub width: f32,
     pub corner_radius: f32,
@@ -122,7 +151,8 @@ impl Default for DropdownTheme {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct DialogTheme {
     pub width: f32,
     pub border_width: f32,
@@ -177,11 +207,14 @@ impl Default for DialogTheme {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct PanelTheme {
     pub island_padding: f32,
     pub island_radius: f32,
     pub island_stroke_width: f32,
+    /// Border width of a panel whose tab has a color label.
+    pub label_stroke_width: f32,
 }
 
 impl Default for PanelTheme {
@@ -190,6 +223,65 @@ impl Default for PanelTheme {
             island_padding: 16.0,
             island_radius: 10.0,
             island_stroke_width: 0.5,
+            label_stroke_width: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToastTheme {
+    pub max_width: f32,
+    pub margin: f32,
+    pub padding_h: f32,
+    pub padding_v: f32,
+    pub corner_radius: f32,
+    pub border_width: f32,
+    pub accent_width: f32,
+    pub shadow_spread: f32,
+    pub font_size: f32,
+    pub duration_secs: f32,
+}
+
+impl Default for ToastTheme {
+    fn default() -> Self {
+        Self {
+            max_width: 420.0,
+            margin: 24.0,
+            padding_h: 14.0,
+            padding_v: 10.0,
+            corner_radius: 8.0,
+            border_width: 1.0,
+            accent_width: 3.0,
+            shadow_spread: 12.0,
+            font_size: 13.0,
+            duration_secs: 6.0,
+        }
+    }
+}
+
+/// The command palette. Rows, padding and corners follow the dropdown theme.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaletteTheme {
+    pub width: f32,
+    /// Distance from the top of the window.
+    pub top: f32,
+    pub field_height: f32,
+    /// Space between the search field and the first row.
+    pub field_gap: f32,
+    /// Rows shown before the list scrolls.
+    pub max_rows: usize,
+}
+
+impl Default for PaletteTheme {
+    fn default() -> Self {
+        Self {
+            width: 560.0,
+            top: 64.0,
+            field_height: 34.0,
+            field_gap: 6.0,
+            max_rows: 10,
         }
     }
 }
@@ -0,0 +1,636 @@
+//! Color scheme import and the bundled scheme catalogue.
+//!
+//! Foreign theme formats only describe the terminal palette (background,
+//! foreground, cursor, selection and the 16 ANSI colors). They are parsed into
+//! a [`Palette`] first, and the chrome colors (tab bar, dropdowns, dialog
+//! fields) are then derived from it so imported themes restyle the whole
+//! window, not just the terminal grid.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::colors::{ColorScheme, HexColor};
+use crate::config::Config;
+
+/// Terminal palette shared by every supported theme format.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub background: HexColor,
+    pub foreground: HexColor,
+    pub cursor: Option<HexColor>,
+    pub selection: Option<HexColor>,
+    /// black, red, green, yellow, blue, magenta, cyan, white, then the
+    /// bright variants in the same order.
+    pub ansi: [HexColor; 16],
+}
+
+const fn rgb(v: u32) -> HexColor {
+    HexColor::from_u32((v << 8) | 0xFF)
+}
+
+const fn palette(
+    background: u32,
+    foreground: u32,
+    cursor: u32,
+    selection: u32,
+    ansi: [u32; 16],
+) -> Palette {
+    let mut out = [rgb(0); 16];
+    let mut i = 0;
+    while i < 16 {
+        out[i] = rgb(ansi[i]);
+        i += 1;
+    }
+    Palette {
+        background: rgb(background),
+        foreground: rgb(foreground),
+        cursor: Some(rgb(cursor)),
+        selection: Some(rgb(selection)),
+        ansi: out,
+    }
+}
+
+const SOLARIZED_ANSI: [u32; 16] = [
+    0x073642, 0xDC322F, 0x859900, 0xB58900, 0x268BD2, 0xD33682, 0x2AA198, 0xEEE8D5,
+    0x002B36, 0xCB4B16, 0x586E75, 0x657B83, 0x839496, 0x6C71C4, 0x93A1A1, 0xFDF6E3,
+];
+
+/// Schemes shipped with the binary, listed in the theme picker.
+const BUNDLED: &[(&str, Palette)] = &[
+    (
+        "Dracula",
+        palette(0x282A36, 0xF8F8F2, 0xF8F8F2, 0x44475A, [
+            0x21222C, 0xFF5555, 0x50FA7B, 0xF1FA8C, 0xBD93F9, 0xFF79C6, 0x8BE9FD, 0xF8F8F2,
+            0x6272A4, 0xFF6E6E, 0x69FF94, 0xFFFFA5, 0xD6ACFF, 0xFF92DF, 0xA4FFFF, 0xFFFFFF,
+        ]),
+    ),
+    (
+        "Gruvbox Dark",
+        palette(0x282828, 0xEBDBB2, 0xEBDBB2, 0x504945, [
+            0x282828, 0xCC241D, 0x98971A, 0xD79921, 0x458588, 0xB16286, 0x689D6A, 0xA89984,
+            0x928374, 0xFB4934, 0xB8BB26, 0xFABD2F, 0x83A598, 0xD3869B, 0x8EC07C, 0xEBDBB2,
+        ]),
+    ),
+    (
+        "Nord",
+        palette(0x2E3440, 0xD8DEE9, 0xD8DEE9, 0x434C5E, [
+            0x3B4252, 0xBF616A, 0xA3BE8C, 0xEBCB8B, 0x81A1C1, 0xB48EAD, 0x88C0D0, 0xE5E9F0,
+            0x4C566A, 0xBF616A, 0xA3BE8C, 0xEBCB8B, 0x81A1C1, 0xB48EAD, 0x8FBCBB, 0xECEFF4,
+        ]),
+    ),
+    (
+        "One Dark",
+        palette(0x282C34, 0xABB2BF, 0x528BFF, 0x3E4451, [
+            0x282C34, 0xE06C75, 0x98C379, 0xE5C07B, 0x61AFEF, 0xC678DD, 0x56B6C2, 0xABB2BF,
+            0x5C6370, 0xE06C75, 0x98C379, 0xE5C07B, 0x61AFEF, 0xC678DD, 0x56B6C2, 0xFFFFFF,
+        ]),
+    ),
+    (
+        "Solarized Dark",
+        palette(0x002B36, 0x839496, 0x93A1A1, 0x073642, SOLARIZED_ANSI),
+    ),
+    (
+        "Solarized Light",
+        palette(0xFDF6E3, 0x657B83, 0x586E75, 0xEEE8D5, SOLARIZED_ANSI),
+    ),
+    (
+        "Tokyo Night",
+        palette(0x1A1B26, 0xC0CAF5, 0xC0CAF5, 0x33467C, [
+            0x15161E, 0xF7768E, 0x9ECE6A, 0xE0AF68, 0x7AA2F7, 0xBB9AF7, 0x7DCFFF, 0xA9B1D6,
+            0x414868, 0xF7768E, 0x9ECE6A, 0xE0AF68, 0x7AA2F7, 0xBB9AF7, 0x7DCFFF, 0xC0CAF5,
+        ]),
+    ),
+];
+
+/// User theme directory: `<config>/pfauterminal/themes/`.
+pub fn themes_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("pfauterminal").join("themes"))
+}
+
+/// Names of all selectable schemes: bundled first, then user theme files
+/// (by file stem) that aren't shadowing a bundled name.
+pub fn available() -> Vec<String> {
+    let mut names: Vec<String> = BUNDLED.iter().map(|(n, _)| n.to_string()).collect();
+    let mut user: Vec<String> = user_theme_files()
+        .into_iter()
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .filter(|n| !names.contains(n))
+        .collect();
+    user.sort();
+    user.dedup();
+    names.extend(user);
+    names
+}
+
+/// Resolve a scheme by name. User theme files take precedence over bundled
+/// schemes so a bundled one can be overridden by dropping in a file.
+pub fn load(name: &str) -> Result<ColorScheme, String> {
+    load_from(&user_theme_files(), name)
+}
+
+fn load_from(user_files: &[PathBuf], name: &str) -> Result<ColorScheme, String> {
+    if let Some(path) =
+        user_files.iter().find(|p| p.file_stem().is_some_and(|s| s.to_string_lossy() == name))
+    {
+        return import(path);
+    }
+    BUNDLED
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, p)| ColorScheme::from(*p))
+        .ok_or_else(|| format!("unknown theme \"{name}\""))
+}
+
+/// The scheme configured for dark or light mode: the named theme, or when
+/// unset, the config's `colors` section (dark) or the built-in light scheme.
+pub fn resolve(config: &Config, light: bool) -> Result<ColorScheme, String> {
+    let settings = &config.settings;
+    let name = if light {
+        &settings.light_theme
+    } else {
+        &settings.dark_theme
+    };
+    match name {
+        Some(name) => load(name),
+        None if light => Ok(ColorScheme::light()),
+        None => Ok(config.colors.clone().unwrap_or_default()),
+    }
+}
+
+/// Copy a theme file into the themes directory after checking it parses.
+/// Returns the name it is selectable under.
+pub fn install(path: &Path) -> Result<String, String> {
+    import(path)?;
+    let dir = themes_dir().ok_or("no config directory")?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let file_name = path.file_name().ok_or("not a file")?;
+    std::fs::copy(path, dir.join(file_name)).map_err(|e| e.to_string())?;
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .ok_or_else(|| "not a file".to_string())
+}
+
+/// Parse a theme file, detecting the format from its extension and contents.
+pub fn import(path: &Path) -> Result<ColorScheme, String> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let data = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let text = || String::from_utf8_lossy(&data).into_owned();
+    let result = match ext.as_str() {
+        "itermcolors" | "plist" => parse_iterm(&data).map(ColorScheme::from),
+        "toml" => parse_alacritty_toml(&text()).map(ColorScheme::from),
+        "yml" | "yaml" => parse_yaml(&text()).map(ColorScheme::from),
+        "json" => parse_json(&text()),
+        _ => Err(format!("unsupported theme format \".{ext}\"")),
+    };
+    result.map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn user_theme_files() -> Vec<PathBuf> {
+    themes_dir().map_or_else(Vec::new, |dir| theme_files(&dir))
+}
+
+/// Files in `dir` with a theme extension.
+fn theme_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension().is_some_and(|e| {
+                matches!(
+                    e.to_string_lossy().to_ascii_lowercase().as_str(),
+                    "itermcolors" | "plist" | "toml" | "yml" | "yaml" | "json"
+                )
+            })
+        })
+        .collect()
+}
+
+fn color(s: &str) -> Result<HexColor, String> {
+    HexColor::parse(s).ok_or_else(|| format!("invalid color \"{s}\""))
+}
+
+// --- iTerm2 ---
+
+fn parse_iterm(data: &[u8]) -> Result<Palette, String> {
+    let value = plist::Value::from_reader(std::io::Cursor::new(data)).map_err(|e| e.to_string())?;
+    let dict = value.as_dictionary().ok_or("expected a plist dictionary")?;
+    let get = |key: &str| -> Option<HexColor> {
+        let c = dict.get(key)?.as_dictionary()?;
+        let component = |k: &str| {
+            let v = c.get(k).and_then(|v| v.as_real()).unwrap_or(0.0);
+            (v.clamp(0.0, 1.0) * 255.0).round() as u8
+        };
+        Some(HexColor {
+            r: component("Red Component"),
+            g: component("Green Component"),
+            b: component("Blue Component"),
+            a: 255,
+        })
+    };
+    let required = |key: &str| get(key).ok_or_else(|| format!("missing \"{key}\""));
+
+    let mut ansi = [HexColor::from_u32(0); 16];
+    for (i, slot) in ansi.iter_mut().enumerate() {
+        *slot = required(&format!("Ansi {i} Color"))?;
+    }
+    Ok(Palette {
+        background: required("Background Color")?,
+        foreground: required("Foreground Color")?,
+        cursor: get("Cursor Color"),
+        selection: get("Selection Color"),
+        ansi,
+    })
+}
+
+// --- Alacritty (TOML, and the legacy YAML layout) ---
+
+#[derive(Deserialize)]
+struct AlacrittyFile {
+    colors: AlacrittyColors,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyColors {
+    primary: AlacrittyPrimary,
+    #[serde(default)]
+    cursor: Option<AlacrittyCursor>,
+    #[serde(default)]
+    selection: Option<AlacrittySelection>,
+    normal: AlacrittyAnsi,
+    bright: AlacrittyAnsi,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyPrimary {
+    background: String,
+    foreground: String,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyCursor {
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittySelection {
+    background: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyAnsi {
+    black: String,
+    red: String,
+    green: String,
+    yellow: String,
+    blue: String,
+    magenta: String,
+    cyan: String,
+    white: String,
+}
+
+impl AlacrittyAnsi {
+    fn colors(&self) -> Result<[HexColor; 8], String> {
+        Ok([
+            color(&self.black)?,
+            color(&self.red)?,
+            color(&self.green)?,
+            color(&self.yellow)?,
+            color(&self.blue)?,
+            color(&self.magenta)?,
+            color(&self.cyan)?,
+            color(&self.white)?,
+        ])
+    }
+}
+
+impl AlacrittyFile {
+    fn into_palette(self) -> Result<Palette, String> {
+        let c = self.colors;
+        // Alacritty allows "CellForeground"/"CellBackground" here, which
+        // has no fixed color — fall back to the derived default instead.
+        let optional = |s: Option<String>| s.and_then(|s| HexColor::parse(&s));
+        let mut ansi = [HexColor::from_u32(0); 16];
+        ansi[..8].copy_from_slice(&c.normal.colors()?);
+        ansi[8..].copy_from_slice(&c.bright.colors()?);
+        Ok(Palette {
+            background: color(&c.primary.background)?,
+            foreground: color(&c.primary.foreground)?,
+            cursor: optional(c.cursor.and_then(|c| c.cursor)),
+            selection: optional(c.selection.and_then(|s| s.background)),
+            ansi,
+        })
+    }
+}
+
+fn parse_alacritty_toml(text: &str) -> Result<Palette, String> {
+    toml::from_str::<AlacrittyFile>(text)
+        .map_err(|e| e.to_string())?
+        .into_palette()
+}
+
+// --- base16 ---
+
+/// Both the classic flat layout (`base00: "..."`) and the newer tinted-theming
+/// layout with a `palette:` map.
+#[derive(Deserialize)]
+struct Base16File {
+    #[serde(default)]
+    palette: Option<HashMap<String, String>>,
+    #[serde(flatten)]
+    rest: HashMap<String, serde_yaml::Value>,
+}
+
+fn parse_yaml(text: &str) -> Result<Palette, String> {
+    let file: Base16File = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+    let base = file.palette.unwrap_or_else(|| {
+        file.rest
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+            .collect()
+    });
+    if base.contains_key("base00") {
+        return base16_palette(&base);
+    }
+    serde_yaml::from_str::<AlacrittyFile>(text)
+        .map_err(|e| e.to_string())?
+        .into_palette()
+}
+
+fn base16_palette(base: &HashMap<String, String>) -> Result<Palette, String> {
+    let get = |n: u8| -> Result<HexColor, String> {
+        let key = format!("base{n:02X}");
+        base.get(&key)
+            .ok_or_else(|| format!("missing \"{key}\""))
+            .and_then(|s| color(s))
+    };
+    // Standard base16 shell mapping.
+    let ansi = [
+        get(0x00)?,
+        get(0x08)?,
+        get(0x0B)?,
+        get(0x0A)?,
+        get(0x0D)?,
+        get(0x0E)?,
+        get(0x0C)?,
+        get(0x05)?,
+        get(0x03)?,
+        get(0x08)?,
+        get(0x0B)?,
+        get(0x0A)?,
+        get(0x0D)?,
+        get(0x0E)?,
+        get(0x0C)?,
+        get(0x07)?,
+    ];
+    Ok(Palette {
+        background: get(0x00)?,
+        foreground: get(0x05)?,
+        cursor: Some(get(0x05)?),
+        selection: Some(get(0x02)?),
+        ansi,
+    })
+}
+
+// --- JSON: Windows Terminal, or our own color scheme layout ---
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WindowsTerminalScheme {
+    background: String,
+    foreground: String,
+    cursor_color: Option<String>,
+    selection_background: Option<String>,
+    black: String,
+    red: String,
+    green: String,
+    yellow: String,
+    blue: String,
+    purple: String,
+    cyan: String,
+    white: String,
+    bright_black: String,
+    bright_red: String,
+    bright_green: String,
+    bright_yellow: String,
+    bright_blue: String,
+    bright_purple: String,
+    bright_cyan: String,
+    bright_white: String,
+}
+
+impl WindowsTerminalScheme {
+    fn into_palette(self) -> Result<Palette, String> {
+        Ok(Palette {
+            background: color(&self.background)?,
+            foreground: color(&self.foreground)?,
+            cursor: self.cursor_color.as_deref().map(color).transpose()?,
+            selection: self.selection_background.as_deref().map(color).transpose()?,
+            ansi: [
+                color(&self.black)?,
+                color(&self.red)?,
+                color(&self.green)?,
+                color(&self.yellow)?,
+                color(&self.blue)?,
+                color(&self.purple)?,
+                color(&self.cyan)?,
+                color(&self.white)?,
+                color(&self.bright_black)?,
+                color(&self.bright_red)?,
+                color(&self.bright_green)?,
+                color(&self.bright_yellow)?,
+                color(&self.bright_blue)?,
+                color(&self.bright_purple)?,
+                color(&self.bright_cyan)?,
+                color(&self.bright_white)?,
+            ],
+        })
+    }
+}
+
+fn parse_json(text: &str) -> Result<ColorScheme, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    // A full Windows Terminal settings.json: take the first scheme.
+    let scheme = match value.get("schemes").and_then(|s| s.as_array()) {
+        Some(schemes) => schemes.first().cloned().ok_or("\"schemes\" is empty")?,
+        None => value,
+    };
+    if scheme.get("brightBlack").is_some() {
+        let wt: WindowsTerminalScheme =
+            serde_json::from_value(scheme).map_err(|e| e.to_string())?;
+        return wt.into_palette().map(ColorScheme::from);
+    }
+    if scheme.get("bright_black").is_some() || scheme.get("chrome").is_some() {
+        return serde_json::from_value(scheme).map_err(|e| e.to_string());
+    }
+    Err("not a Windows Terminal or pfauterminal color scheme".into())
+}
+
+// --- Chrome derivation ---
+
+fn mix(a: HexColor, b: HexColor, t: f32) -> HexColor {
+    let lerp = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+    HexColor {
+        r: lerp(a.r, b.r),
+        g: lerp(a.g, b.g),
+        b: lerp(a.b, b.b),
+        a: 255,
+    }
+}
+
+impl From<Palette> for ColorScheme {
+    fn from(p: Palette) -> Self {
+        let bg = p.background;
+        let fg = p.foreground;
+        let [black, red, green, yellow, blue, magenta, cyan, white, bright_black, bright_red, bright_green, bright_yellow, bright_blue, bright_magenta, bright_cyan, bright_white] =
+            p.ansi;
+        let light = bg.luminance() > 0.5;
+        // Extreme on the foreground side: used for emphasized text.
+        let ink = if light {
+            HexColor::from_u32(0x000000FF)
+        } else {
+            HexColor::from_u32(0xFFFFFFFF)
+        };
+        let accent = blue;
+        let dropdown_bg = mix(bg, fg, 0.08);
+
+        Self {
+            background: bg,
+            chrome: mix(bg, fg, 0.05),
+            foreground: fg,
+            cursor: p.cursor.unwrap_or(fg),
+            black,
+            red,
+            green,
+            yellow,
+            blue,
+            magenta,
+            cyan,
+            white,
+            bright_black,
+            bright_red,
+            bright_green,
+            bright_yellow,
+            bright_blue,
+            bright_magenta,
+            bright_cyan,
+            bright_white,
+            tab_active_fill: mix(bg, accent, 0.3),
+            tab_active_stroke: mix(bg, accent, 0.5),
+            tab_active_text: mix(fg, ink, 0.3),
+            tab_hover_bg: mix(bg, fg, 0.15),
+            tab_hover_stroke: mix(bg, fg, 0.25),
+            tab_separator: mix(bg, fg, 0.15),
+            selection: p.selection.unwrap_or_else(|| mix(bg, accent, 0.4)),
+            panel_stroke: mix(bg, fg, 0.15),
+            dropdown_bg,
+            dropdown_border: mix(bg, fg, 0.2),
+            dropdown_shadow: HexColor::from_u32(0x00000073),
+            dropdown_item_hover: mix(dropdown_bg, accent, 0.35),
+            dropdown_text: mix(fg, ink, 0.2),
+            dropdown_text_active: ink,
+            field_border: mix(bg, fg, 0.35),
+            field_focused: bright_blue,
+            ok_bg: accent,
+            ok_hover_bg: bright_blue,
+            text_dim: mix(bg, fg, 0.45),
+            text_placeholder: mix(bg, fg, 0.6),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(c: HexColor) -> String {
+        c.to_string()
+    }
+
+    #[test]
+    fn base16_maps_onto_ansi() {
+        let yaml = "scheme: \"Test\"\nbase00: \"101010\"\nbase01: \"202020\"\nbase02: \"303030\"\n\
+            base03: \"404040\"\nbase04: \"505050\"\nbase05: \"d0d0d0\"\nbase06: \"e0e0e0\"\n\
+            base07: \"f0f0f0\"\nbase08: \"ff0000\"\nbase09: \"ff8000\"\nbase0A: \"ffff00\"\n\
+            base0B: \"00ff00\"\nbase0C: \"00ffff\"\nbase0D: \"0000ff\"\nbase0E: \"ff00ff\"\n\
+            base0F: \"800000\"\n";
+        let p = parse_yaml(yaml).unwrap();
+        assert_eq!(hex(p.background), "101010FF");
+        assert_eq!(hex(p.ansi[1]), "FF0000FF");
+        assert_eq!(hex(p.ansi[4]), "0000FFFF");
+        assert_eq!(hex(p.ansi[15]), "F0F0F0FF");
+    }
+
+    #[test]
+    fn alacritty_toml() {
+        let toml = r##"
+[colors.primary]
+background = "#1d1f21"
+foreground = "0xc5c8c6"
+
+[colors.cursor]
+cursor = "CellForeground"
+
+[colors.normal]
+black = "#000000"
+red = "#cc6666"
+green = "#b5bd68"
+yellow = "#f0c674"
+blue = "#81a2be"
+magenta = "#b294bb"
+cyan = "#8abeb7"
+white = "#ffffff"
+
+[colors.bright]
+black = "#666666"
+red = "#d54e53"
+green = "#b9ca4a"
+yellow = "#e7c547"
+blue = "#7aa6da"
+magenta = "#c397d8"
+cyan = "#70c0b1"
+white = "#eaeaea"
+"##;
+        let p = parse_alacritty_toml(toml).unwrap();
+        assert_eq!(hex(p.foreground), "C5C8C6FF");
+        assert!(p.cursor.is_none());
+        assert_eq!(hex(p.ansi[9]), "D54E53FF");
+    }
+
+    #[test]
+    fn windows_terminal_settings() {
+        let json = r##"{"schemes": [{"name": "Campbell", "background": "#0C0C0C",
+            "foreground": "#CCCCCC", "cursorColor": "#FFFFFF",
+            "black": "#0C0C0C", "red": "#C50F1F", "green": "#13A10E", "yellow": "#C19C00",
+            "blue": "#0037DA", "purple": "#881798", "cyan": "#3A96DD", "white": "#CCCCCC",
+            "brightBlack": "#767676", "brightRed": "#E74856", "brightGreen": "#16C60C",
+            "brightYellow": "#F9F1A5", "brightBlue": "#3B78FF", "brightPurple": "#B4009E",
+            "brightCyan": "#61D6D6", "brightWhite": "#F2F2F2"}]}"##;
+        let scheme = parse_json(json).unwrap();
+        assert_eq!(hex(scheme.magenta), "881798FF");
+        assert_eq!(hex(scheme.cursor), "FFFFFFFF");
+    }
+
+    #[test]
+    fn bundled_schemes_resolve() {
+        // A scratch themes directory, so themes the user installed don't
+        // shadow the bundled ones.
+        let dir = std::env::temp_dir().join(format!("pfauterminal-themes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, _) in BUNDLED {
+            assert!(load_from(&theme_files(&dir), name).is_ok(), "{name}");
+        }
+
+        std::fs::write(dir.join("Nord.json"), "{}").unwrap();
+        let files = theme_files(&dir);
+        let nord = load_from(&files, "Nord");
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(files.len(), 1);
+        assert!(nord.is_err(), "user files take precedence");
+    }
+}
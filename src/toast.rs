@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use glyphon::{Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+use crate::draw::DrawContext;
+use crate::font;
+use crate::layout::{Rect, TextSpec};
+use crate::theme::{Theme, ToastTheme};
+
+/// Non-blocking notification pinned to the bottom of the window. Used to
+/// report problems (e.g. a config file that failed to parse) without
+/// interrupting the terminal.
+pub struct Toast {
+    buffer: Buffer,
+    /// Laid-out text size in logical pixels.
+    text_width: f32,
+    text_height: f32,
+    expires: Instant,
+}
+
+impl Toast {
+    pub fn new(message: &str, font_system: &mut FontSystem, theme: &ToastTheme) -> Self {
+        let metrics = Metrics::new(theme.font_size, theme.font_size * font::LINE_HEIGHT);
+        let max_text_w = theme.max_width - 2.0 * theme.padding_h - theme.accent_width;
+        let mut buffer = Buffer::new(font_system, metrics);
+        buffer.set_wrap(font_system, Wrap::WordOrGlyph);
+        buffer.set_size(font_system, Some(max_text_w), None);
+        buffer.set_text(font_system, message, font::default_attrs(), Shaping::Advanced);
+        buffer.shape_until_scroll(font_system, false);
+
+        let (text_width, lines) = buffer
+            .layout_runs()
+            .fold((0.0f32, 0usize), |(w, n), run| (w.max(run.line_w), n + 1));
+
+        Self {
+            buffer,
+            text_width: text_width.ceil(),
+            text_height: lines.max(1) as f32 * metrics.line_height,
+            expires: Instant::now() + Duration::from_secs_f32(theme.duration_secs),
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        Instant::now() >= self.expires
+    }
+
+    pub fn draw(
+        &self,
+        ctx: &mut DrawContext,
+        text_specs: &mut Vec<TextSpec>,
+        theme: &Theme,
+        scale: f32,
+        surface_width: f32,
+        surface_height: f32,
+    ) {
+        let t = &theme.toast;
+        let colors = &theme.colors;
+
+        let pad_h = t.padding_h * scale;
+        let pad_v = t.padding_v * scale;
+        let accent = t.accent_width * scale;
+        let radius = t.corner_radius * scale;
+        let text_w = self.text_width * scale;
+        let text_h = self.text_height * scale;
+
+        let width = accent + 2.0 * pad_h + text_w;
+        let height = 2.0 * pad_v + text_h;
+        let rect = Rect {
+            x: ((surface_width - width) / 2.0).max(0.0),
+            y: surface_height - t.margin * scale - height,
+            width,
+            height,
+        };
+
+        ctx.shadow(
+            rect,
+            colors.dropdown_shadow.to_linear_f32(),
+            radius,
+            t.shadow_spread * scale,
+        );
+        ctx.stroked_rect(
+            &rect,
+            colors.dropdown_border.to_linear_f32(),
+            colors.dropdown_bg.to_linear_f32(),
+            radius,
+            t.border_width * scale,
+        );
+        // Error accent along the left edge
+        ctx.rounded_rect(
+            Rect {
+                x: rect.x + pad_h / 2.0,
+                y: rect.y + pad_v,
+                width: accent,
+                height: text_h,
+            },
+            colors.red.to_linear_f32(),
+            accent / 2.0,
+        );
+
+        let left = rect.x + accent + pad_h;
+        let top = rect.y + pad_v;
+        text_specs.push(TextSpec {
+            buffer_index: 0,
+            left,
+            top,
+            bounds: Rect {
+                x: left,
+                y: top,
+                width: text_w + 1.0,
+                height: text_h,
+            },
+            color: colors.dropdown_text.to_glyphon(),
+        });
+    }
+
+    pub fn buffers(&self) -> &[Buffer] {
+        std::slice::from_ref(&self.buffer)
+    }
+}
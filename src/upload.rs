@@ -0,0 +1,191 @@
+//! File uploads over an SSH tab's session: progress shared with the panel,
+//! the remote working directory (from OSC 7) and shell quoting for dropped
+//! paths.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long the final "Uploaded …" / error line stays up.
+pub const RESULT_LINGER: Duration = Duration::from_secs(4);
+
+/// Progress of a tab's uploads, written by the SSH thread and drawn by the
+/// panel.
+#[derive(Clone, Default)]
+pub struct Uploads(Arc<Mutex<Option<UploadStatus>>>);
+
+#[derive(Clone)]
+pub struct UploadStatus {
+    pub label: String,
+    pub sent: u64,
+    pub total: u64,
+    /// Set once the batch is done; the status is hidden `RESULT_LINGER` later.
+    pub finished: Option<Instant>,
+    pub failed: bool,
+}
+
+impl Uploads {
+    /// The status to draw, if any is still current.
+    pub fn status(&self) -> Option<UploadStatus> {
+        let status = self.lock().clone()?;
+        match status.finished {
+            Some(at) if at.elapsed() >= RESULT_LINGER => None,
+            _ => Some(status),
+        }
+    }
+
+    pub fn progress(&self, label: String, sent: u64, total: u64) {
+        *self.lock() = Some(UploadStatus { label, sent, total, finished: None, failed: false });
+    }
+
+    pub fn finish(&self, label: String, failed: bool) {
+        let mut guard = self.lock();
+        let (sent, total) = guard.as_ref().map_or((0, 0), |s| (s.sent, s.total));
+        *guard = Some(UploadStatus {
+            label,
+            sent: if failed { sent } else { total },
+            total,
+            finished: Some(Instant::now()),
+            failed,
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<UploadStatus>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A batch of dropped files and the remote directory to put them in
+/// (`None` for the login directory).
+pub struct UploadBatch {
+    pub files: Vec<PathBuf>,
+    pub dir: Option<String>,
+}
+
+/// Tracks the remote shell's working directory from OSC 7
+/// (`ESC ] 7 ; file://host/path ST`), which most shells can be set up to
+/// emit on every prompt.
+#[derive(Default)]
+pub struct RemoteCwd {
+    state: OscScan,
+    buf: Vec<u8>,
+    cwd: Option<String>,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum OscScan {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Longest OSC payload worth collecting.
+const MAX_OSC: usize = 4096;
+
+impl RemoteCwd {
+    pub fn cwd(&self) -> Option<&str> {
+        self.cwd.as_deref()
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.state = match (self.state, b) {
+                (OscScan::Osc, 0x07) | (OscScan::OscEscape, b'\\') => {
+                    self.finish();
+                    OscScan::Ground
+                }
+                (OscScan::Osc, 0x1b) => OscScan::OscEscape,
+                (OscScan::Osc, _) if self.buf.len() < MAX_OSC => {
+                    self.buf.push(b);
+                    OscScan::Osc
+                }
+                (_, 0x1b) => OscScan::Escape,
+                (OscScan::Escape, b']') => {
+                    self.buf.clear();
+                    OscScan::Osc
+                }
+                _ => OscScan::Ground,
+            };
+        }
+    }
+
+    fn finish(&mut self) {
+        let Some(uri) = self.buf.strip_prefix(b"7;") else { return };
+        let Ok(uri) = std::str::from_utf8(uri) else { return };
+        let Some(rest) = uri.strip_prefix("file://") else { return };
+        // Skip the host part; the path starts at the next slash.
+        if let Some(slash) = rest.find('/') {
+            self.cwd = Some(percent_decode(&rest[slash..]));
+        }
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Quote `text` for a POSIX shell, leaving plain words alone.
+pub fn sh_quote(text: &str) -> String {
+    // A leading `=` would run zsh's `=cmd` path expansion.
+    let plain = !text.is_empty()
+        && !text.starts_with('=')
+        && text.chars().all(|c| c.is_alphanumeric() || "_-./+@%:,=".contains(c));
+    if plain {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
+
+/// A dropped path as the local shell should see it typed.
+pub fn local_path_arg(path: &Path) -> String {
+    let text = path.to_string_lossy();
+    if cfg!(windows) {
+        if text.contains([' ', '&', '(', ')', '^', ';', ',']) {
+            format!("\"{text}\"")
+        } else {
+            text.into_owned()
+        }
+    } else {
+        sh_quote(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting_and_cwd() {
+        assert_eq!(sh_quote("/tmp/a.txt"), "/tmp/a.txt");
+        assert_eq!(sh_quote("my file's.txt"), r"'my file'\''s.txt'");
+        assert_eq!(sh_quote("=ls"), "'=ls'");
+        assert_eq!(sh_quote("~/notes"), "'~/notes'");
+        assert_eq!(sh_quote("a=b"), "a=b");
+
+        let mut cwd = RemoteCwd::default();
+        cwd.feed(b"\x1b]7;file://host/home/me/My%20Docs\x07$ ");
+        assert_eq!(cwd.cwd(), Some("/home/me/My Docs"));
+        cwd.feed(b"\x1b]7;file://host/srv\x1b");
+        cwd.feed(b"\\");
+        assert_eq!(cwd.cwd(), Some("/srv"));
+    }
+}
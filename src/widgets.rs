@@ -1,6 +1,8 @@
 use std::cell::Cell;
 
 use glyphon::{Attrs, Buffer, Color as GlyphonColor, FontSystem, Metrics, TextArea};
+use winit::event::KeyEvent;
+use winit::keyboard::{Key, NamedKey};
 
 use crate::colors::{ColorScheme, HexColor};
 use crate::draw::DrawContext;
@@ -73,6 +75,10 @@ pub struct TextField {
     /// Horizontal scroll offset in physical pixels.
     /// Uses Cell so draw() can adjust it through &self.
     scroll_offset: Cell<f32>,
+    /// Uncommitted IME composition, shown underlined at the cursor.
+    preedit: String,
+    /// Caret position within `preedit`, in chars.
+    preedit_cursor: usize,
 }
 
 impl TextField {
@@ -100,6 +106,8 @@ impl TextField {
             radius,
             pad_h,
             scroll_offset: Cell::new(0.0),
+            preedit: String::new(),
+            preedit_cursor: 0,
         }
     }
 
@@ -152,6 +160,7 @@ impl TextField {
     }
 
     pub fn insert_text(&mut self, text: &str, font_system: &mut FontSystem) {
+        self.preedit.clear();
         self.delete_selection_inner();
         for c in text.chars() {
             if c.is_control() {
@@ -270,6 +279,53 @@ impl TextField {
         }
     }
 
+    /// Apply a pressed editing key (deletion, cursor movement or typed
+    /// text). Returns false for keys the field doesn't handle.
+    pub fn edit_key(
+        &mut self,
+        event: &KeyEvent,
+        font_system: &mut FontSystem,
+        shift: bool,
+    ) -> bool {
+        match event.logical_key.as_ref() {
+            Key::Named(NamedKey::Backspace) => self.delete_back(font_system),
+            Key::Named(NamedKey::Delete) => self.delete_forward(font_system),
+            Key::Named(NamedKey::ArrowLeft) => self.move_left(shift),
+            Key::Named(NamedKey::ArrowRight) => self.move_right(shift),
+            Key::Named(NamedKey::Home) => self.move_home(shift),
+            Key::Named(NamedKey::End) => self.move_end(shift),
+            _ => match &event.text {
+                Some(text) => self.insert_text(text, font_system),
+                None => return false,
+            },
+        }
+        true
+    }
+
+    /// Show an IME composition at the cursor; `cursor` is a byte offset
+    /// into `text`. An empty string ends the composition.
+    pub fn set_preedit(&mut self, text: &str, cursor: Option<usize>, font_system: &mut FontSystem) {
+        self.preedit = text.to_string();
+        self.preedit_cursor = match cursor {
+            Some(byte) => text.get(..byte).map_or(0, |t| t.chars().count()),
+            None => text.chars().count(),
+        };
+        self.refresh_buffer(font_system);
+    }
+
+    /// Where the caret is drawn, for placing the IME candidate window.
+    pub fn cursor_rect(&self, scale: f32) -> Rect {
+        let x = self.rect.x + self.pad_h * scale
+            + self.cursor_x_from_layout(self.display_cursor()) * scale
+            - self.scroll_offset.get();
+        Rect { x, y: self.rect.y, width: 1.5 * scale, height: self.rect.height }
+    }
+
+    /// Caret position in the displayed text, which includes the preedit.
+    fn display_cursor(&self) -> usize {
+        self.cursor_pos + self.preedit_cursor.min(self.preedit.chars().count())
+    }
+
     pub fn has_selection(&self) -> bool {
         self.selection_anchor.is_some()
     }
@@ -338,7 +394,7 @@ impl TextField {
         if field_inner_w <= 0.0 {
             return;
         }
-        let cursor_x = self.cursor_x_from_layout(self.cursor_pos) * scale;
+        let cursor_x = self.cursor_x_from_layout(self.display_cursor()) * scale;
         let mut offset = self.scroll_offset.get();
         // Cursor scrolled left of the visible window
         if cursor_x < offset {
@@ -353,16 +409,18 @@ impl TextField {
 
     fn display_text(&self) -> String {
         if self.password {
-            "*".repeat(self.value.chars().count())
-        } else if self.value.is_empty() {
+            "*".repeat(self.value.chars().count() + self.preedit.chars().count())
+        } else if self.value.is_empty() && self.preedit.is_empty() {
             self.placeholder.clone()
         } else {
-            self.value.clone()
+            let mut text = self.value.clone();
+            text.insert_str(char_to_byte(&self.value, self.cursor_pos), &self.preedit);
+            text
         }
     }
 
     fn is_showing_placeholder(&self) -> bool {
-        !self.password && self.value.is_empty()
+        !self.password && self.value.is_empty() && self.preedit.is_empty()
     }
 
     fn refresh_buffer(&mut self, font_system: &mut FontSystem) {
@@ -464,9 +522,26 @@ impl TextField {
                 }
             }
 
+            // Underline the IME composition
+            if !self.preedit.is_empty() {
+                let end = self.cursor_pos + self.preedit.chars().count();
+                let start_x = self.rect.x + pad
+                    + self.cursor_x_from_layout(self.cursor_pos) * scale - scroll;
+                let end_x = self.rect.x + pad + self.cursor_x_from_layout(end) * scale - scroll;
+                let field_left = self.rect.x + pad;
+                let field_right = self.rect.x + self.rect.width - pad;
+                let (x, right) = (start_x.max(field_left), end_x.min(field_right));
+                if right > x {
+                    ctx.flat_quad(
+                        Rect { x, y: cursor_y + cursor_h - scale, width: right - x, height: scale },
+                        colors.dropdown_text.to_linear_f32(),
+                    );
+                }
+            }
+
             // Draw cursor
             let cursor_x = self.rect.x + pad
-                + self.cursor_x_from_layout(self.cursor_pos) * scale - scroll;
+                + self.cursor_x_from_layout(self.display_cursor()) * scale - scroll;
             ctx.rounded_rect(
                 Rect {
                     x: cursor_x,
@@ -0,0 +1,386 @@
+//! Legacy (xterm) key encoding: modifier parameters, modifyOtherKeys and
+//! application keypad mode.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use alacritty_terminal::term::TermMode;
+use winit::keyboard::{Key, KeyCode, NamedKey};
+
+use crate::kitty_keys::Mods;
+
+/// A panel's xterm modifyOtherKeys level (0–2), set by `CSI > 4 ; Pv m`.
+/// alacritty_terminal parses that sequence but drops it, so the thread
+/// reading the backend watches for it with an [`OtherKeysScanner`].
+#[derive(Clone, Default)]
+pub struct OtherKeys(Arc<AtomicU8>);
+
+impl OtherKeys {
+    pub fn level(&self) -> u8 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn scanner(&self) -> OtherKeysScanner {
+        OtherKeysScanner {
+            level: self.0.clone(),
+            state: Scan::Ground,
+            params: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scan {
+    Ground,
+    Escape,
+    Csi,
+}
+
+/// Longest CSI parameter string worth collecting.
+const MAX_PARAMS: usize = 16;
+
+pub struct OtherKeysScanner {
+    level: Arc<AtomicU8>,
+    state: Scan,
+    params: Vec<u8>,
+}
+
+impl OtherKeysScanner {
+    /// Look for modifyOtherKeys changes in a chunk of backend output.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.state = match (self.state, b) {
+                (_, 0x1b) => Scan::Escape,
+                // CAN and SUB abort a sequence.
+                (_, 0x18 | 0x1a) => Scan::Ground,
+                (Scan::Ground, _) => Scan::Ground,
+                (Scan::Escape, b'[') => {
+                    self.params.clear();
+                    Scan::Csi
+                }
+                (Scan::Escape, b'c') => {
+                    // RIS resets the level along with everything else.
+                    self.set(0);
+                    Scan::Ground
+                }
+                (Scan::Escape, _) => Scan::Ground,
+                (Scan::Csi, 0x20..=0x3f) if self.params.len() < MAX_PARAMS => {
+                    self.params.push(b);
+                    Scan::Csi
+                }
+                (Scan::Csi, 0x40..=0x7e) => {
+                    self.finish(b);
+                    Scan::Ground
+                }
+                (Scan::Csi, _) => Scan::Ground,
+            };
+        }
+    }
+
+    fn finish(&mut self, final_byte: u8) {
+        let Some(params) = self.params.strip_prefix(b">") else { return };
+        let mut fields = params.split(|&b| b == b';');
+        if fields.next() != Some(b"4") {
+            return;
+        }
+        let value = fields.next().unwrap_or_default();
+        match final_byte {
+            // An empty value resets to the default, which is off.
+            b'm' if value.is_empty() => self.set(0),
+            b'm' => {
+                let level = std::str::from_utf8(value).ok().and_then(|v| v.parse::<u8>().ok());
+                if let Some(level) = level {
+                    self.set(level.min(2));
+                }
+            }
+            // CSI > 4 n disables the option.
+            b'n' => self.set(0),
+            _ => {}
+        }
+    }
+
+    fn set(&self, level: u8) {
+        self.level.store(level, Ordering::Relaxed);
+    }
+}
+
+/// Encode a key press the way xterm does, or `None` if it sends nothing.
+///
+/// `code` is the physical key, used for Ctrl+letter on non-Latin layouts and
+/// to tell keypad keys apart.
+pub fn encode(
+    key: Key<&str>,
+    code: Option<KeyCode>,
+    text: Option<&str>,
+    mods: Mods,
+    mode: TermMode,
+    other_keys: u8,
+) -> Option<Vec<u8>> {
+    let m = mods.param();
+    let esc_if_alt = |mut bytes: Vec<u8>| {
+        if mods.alt {
+            bytes.insert(0, 0x1b);
+        }
+        bytes
+    };
+
+    // DECKPAM: the keypad sends SS3 sequences instead of digits and operators.
+    if mode.contains(TermMode::APP_KEYPAD)
+        && text.is_some()
+        && let Some(c) = code.and_then(keypad_final)
+    {
+        return Some(vec![0x1b, b'O', c]);
+    }
+
+    if let Some(c) = other_key(&key, mods, other_keys) {
+        return Some(format!("\x1b[27;{m};{c}~").into_bytes());
+    }
+
+    // Ctrl+key → control characters (0x00–0x1F)
+    if mods.ctrl
+        && let Some(b) = ctrl_byte(&key, code)
+    {
+        return Some(esc_if_alt(vec![b]));
+    }
+
+    let bytes = match key {
+        Key::Named(NamedKey::Enter) => {
+            // CSI u modifier encoding: 1 + Shift(1) + Alt(2)
+            let modifier = 1 + if mods.shift { 1 } else { 0 } + if mods.alt { 2 } else { 0 };
+            if modifier > 1 {
+                format!("\x1b[13;{modifier}u").into_bytes()
+            } else {
+                b"\r".to_vec()
+            }
+        }
+        Key::Named(NamedKey::Backspace) => esc_if_alt(vec![if mods.ctrl { 0x08 } else { 0x7f }]),
+        Key::Named(NamedKey::Tab) if mods.shift => esc_if_alt(b"\x1b[Z".to_vec()),
+        Key::Named(NamedKey::Tab) => esc_if_alt(b"\t".to_vec()),
+        Key::Named(NamedKey::Escape) => esc_if_alt(b"\x1b".to_vec()),
+        Key::Named(
+            named @ (NamedKey::ArrowUp
+            | NamedKey::ArrowDown
+            | NamedKey::ArrowRight
+            | NamedKey::ArrowLeft
+            | NamedKey::Home
+            | NamedKey::End),
+        ) => {
+            let suffix = match named {
+                NamedKey::ArrowUp => b'A',
+                NamedKey::ArrowDown => b'B',
+                NamedKey::ArrowRight => b'C',
+                NamedKey::ArrowLeft => b'D',
+                NamedKey::Home => b'H',
+                _ => b'F',
+            };
+            if mods.alt && !mods.ctrl && !mods.shift && (suffix == b'D' || suffix == b'C') {
+                // Option+Left/Right → ESC b / ESC f (readline word navigation)
+                vec![0x1b, if suffix == b'D' { b'b' } else { b'f' }]
+            } else if m > 1 {
+                // Modified keys always use CSI format, even in app cursor mode
+                format!("\x1b[1;{m}{}", suffix as char).into_bytes()
+            } else if mode.contains(TermMode::APP_CURSOR) {
+                // SS3 prefix for application cursor mode, CSI for normal mode
+                vec![0x1b, b'O', suffix]
+            } else {
+                vec![0x1b, b'[', suffix]
+            }
+        }
+        Key::Named(named @ (NamedKey::F1 | NamedKey::F2 | NamedKey::F3 | NamedKey::F4)) => {
+            let suffix = match named {
+                NamedKey::F1 => 'P',
+                NamedKey::F2 => 'Q',
+                NamedKey::F3 => 'R',
+                _ => 'S',
+            };
+            if m > 1 {
+                format!("\x1b[1;{m}{suffix}").into_bytes()
+            } else {
+                format!("\x1bO{suffix}").into_bytes()
+            }
+        }
+        Key::Named(named) if let Some(n) = tilde_number(named) => {
+            if m > 1 {
+                format!("\x1b[{n};{m}~").into_bytes()
+            } else {
+                format!("\x1b[{n}~").into_bytes()
+            }
+        }
+        // Alt+key → ESC prefix + key
+        _ => esc_if_alt(text?.as_bytes().to_vec()),
+    };
+    Some(bytes)
+}
+
+/// The character code for a modifyOtherKeys `CSI 27 ; m ; code ~` report,
+/// if the key needs one at `level`.
+fn other_key(key: &Key<&str>, mods: Mods, level: u8) -> Option<u32> {
+    if level == 0 || mods.param() == 1 {
+        return None;
+    }
+    let code = match key {
+        Key::Named(NamedKey::Enter) => 13,
+        Key::Named(NamedKey::Tab) => 9,
+        Key::Named(NamedKey::Backspace) => 127,
+        Key::Named(NamedKey::Escape) => 27,
+        Key::Named(NamedKey::Space) => 32,
+        Key::Character(s) => {
+            let mut chars = s.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            c as u32
+        }
+        _ => return None,
+    };
+    let is_char = matches!(key, Key::Character(_));
+    if level >= 2 {
+        // Shift alone is already in the character it types.
+        return (!(is_char && !mods.ctrl && !mods.alt)).then_some(code);
+    }
+    // Level 1 leaves keys with a well-known legacy meaning alone: Ctrl+letter,
+    // Alt+key, Shift+Tab, Ctrl+Space.
+    let known = if is_char {
+        !mods.ctrl || (!mods.shift && ctrl_byte(key, None).is_some())
+    } else {
+        !mods.ctrl || code == 32
+    };
+    (!known).then_some(code)
+}
+
+fn ctrl_byte(key: &Key<&str>, code: Option<KeyCode>) -> Option<u8> {
+    // Use physical key for letters to work correctly with non-Latin layouts.
+    let letter = match code {
+        Some(code) => match code {
+            KeyCode::KeyA => Some(1),  KeyCode::KeyB => Some(2),
+            KeyCode::KeyC => Some(3),  KeyCode::KeyD => Some(4),
+            KeyCode::KeyE => Some(5),  KeyCode::KeyF => Some(6),
+            KeyCode::KeyG => Some(7),  KeyCode::KeyH => Some(8),
+            KeyCode::KeyI => Some(9),  KeyCode::KeyJ => Some(10),
+            KeyCode::KeyK => Some(11), KeyCode::KeyL => Some(12),
+            KeyCode::KeyM => Some(13), KeyCode::KeyN => Some(14),
+            KeyCode::KeyO => Some(15), KeyCode::KeyP => Some(16),
+            KeyCode::KeyQ => Some(17), KeyCode::KeyR => Some(18),
+            KeyCode::KeyS => Some(19), KeyCode::KeyT => Some(20),
+            KeyCode::KeyU => Some(21), KeyCode::KeyV => Some(22),
+            KeyCode::KeyW => Some(23), KeyCode::KeyX => Some(24),
+            KeyCode::KeyY => Some(25), KeyCode::KeyZ => Some(26),
+            _ => None,
+        },
+        None => None,
+    };
+    letter.or_else(|| match key {
+        Key::Character(c) => match c.chars().next() {
+            Some(c @ ('a'..='z' | 'A'..='Z')) => Some(c.to_ascii_lowercase() as u8 - b'a' + 1),
+            Some('@' | ' ') => Some(0x00),
+            Some('[') => Some(0x1B),
+            Some('\\') => Some(0x1C),
+            Some(']') => Some(0x1D),
+            Some('^' | '~') => Some(0x1E),
+            Some('_' | '/') => Some(0x1F),
+            _ => None,
+        },
+        Key::Named(NamedKey::Space) => Some(0x00),
+        _ => None,
+    })
+}
+
+fn tilde_number(key: NamedKey) -> Option<u8> {
+    Some(match key {
+        NamedKey::Insert => 2,
+        NamedKey::Delete => 3,
+        NamedKey::PageUp => 5,
+        NamedKey::PageDown => 6,
+        NamedKey::F5 => 15,
+        NamedKey::F6 => 17,
+        NamedKey::F7 => 18,
+        NamedKey::F8 => 19,
+        NamedKey::F9 => 20,
+        NamedKey::F10 => 21,
+        NamedKey::F11 => 23,
+        NamedKey::F12 => 24,
+        _ => return None,
+    })
+}
+
+/// SS3 final byte for a keypad key in application keypad mode.
+fn keypad_final(code: KeyCode) -> Option<u8> {
+    Some(match code {
+        KeyCode::Numpad0 => b'p',
+        KeyCode::Numpad1 => b'q',
+        KeyCode::Numpad2 => b'r',
+        KeyCode::Numpad3 => b's',
+        KeyCode::Numpad4 => b't',
+        KeyCode::Numpad5 => b'u',
+        KeyCode::Numpad6 => b'v',
+        KeyCode::Numpad7 => b'w',
+        KeyCode::Numpad8 => b'x',
+        KeyCode::Numpad9 => b'y',
+        KeyCode::NumpadDecimal => b'n',
+        KeyCode::NumpadDivide => b'o',
+        KeyCode::NumpadMultiply => b'j',
+        KeyCode::NumpadSubtract => b'm',
+        KeyCode::NumpadAdd => b'k',
+        KeyCode::NumpadEnter => b'M',
+        KeyCode::NumpadEqual => b'X',
+        KeyCode::NumpadComma => b'l',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enc(key: Key<&str>, mods: Mods, mode: TermMode, level: u8) -> String {
+        let text = match key {
+            Key::Character(s) => Some(s),
+            _ => None,
+        };
+        String::from_utf8(encode(key, None, text, mods, mode, level).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn xterm_encoding() {
+        let none = Mods::default();
+        let shift = Mods { shift: true, ..Default::default() };
+        let ctrl = Mods { ctrl: true, ..Default::default() };
+        let ctrl_shift = Mods { ctrl: true, shift: true, ..Default::default() };
+        let normal = TermMode::empty();
+
+        assert_eq!(enc(Key::Named(NamedKey::ArrowUp), shift, normal, 0), "\x1b[1;2A");
+        assert_eq!(enc(Key::Named(NamedKey::ArrowUp), none, TermMode::APP_CURSOR, 0), "\x1bOA");
+        assert_eq!(enc(Key::Named(NamedKey::PageUp), ctrl, normal, 0), "\x1b[5;5~");
+        assert_eq!(enc(Key::Named(NamedKey::F1), shift, normal, 0), "\x1b[1;2P");
+        assert_eq!(enc(Key::Named(NamedKey::F5), none, normal, 0), "\x1b[15~");
+        assert_eq!(enc(Key::Named(NamedKey::Tab), shift, normal, 0), "\x1b[Z");
+        assert_eq!(enc(Key::Character("a"), ctrl, normal, 0), "\x01");
+
+        // modifyOtherKeys
+        assert_eq!(enc(Key::Character("a"), ctrl, normal, 1), "\x01");
+        assert_eq!(enc(Key::Character("A"), ctrl_shift, normal, 1), "\x1b[27;6;65~");
+        assert_eq!(enc(Key::Character("1"), ctrl, normal, 1), "\x1b[27;5;49~");
+        assert_eq!(enc(Key::Character("a"), ctrl, normal, 2), "\x1b[27;5;97~");
+        assert_eq!(enc(Key::Character("A"), shift, normal, 2), "A");
+        assert_eq!(enc(Key::Named(NamedKey::Tab), ctrl, normal, 2), "\x1b[27;5;9~");
+
+        // Application keypad
+        let kp = encode(Key::Character("5"), Some(KeyCode::Numpad5), Some("5"), none,
+            TermMode::APP_KEYPAD, 0);
+        assert_eq!(kp.as_deref(), Some(&b"\x1bOu"[..]));
+    }
+
+    #[test]
+    fn other_keys_scanner() {
+        let keys = OtherKeys::default();
+        let mut scanner = keys.scanner();
+        scanner.feed(b"hello\x1b[>4;");
+        scanner.feed(b"2m");
+        assert_eq!(keys.level(), 2);
+        scanner.feed(b"\x1b[4;1m\x1b[>4m");
+        assert_eq!(keys.level(), 0);
+        scanner.feed(b"\x1b[>4;1m\x1bc");
+        assert_eq!(keys.level(), 0);
+    }
+}